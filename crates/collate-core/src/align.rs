@@ -0,0 +1,389 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::paragraphs::ParsedParagraph;
+use crate::track_changes;
+use crate::types::{BlockKind, HeaderFooterType};
+
+/// Paragraphs scoring at or below this Jaccard similarity are never aligned
+const MATCH_THRESHOLD: f64 = 0.3;
+
+/// Largest gap (base × other paragraphs) aligned with the full similarity table;
+/// bigger gaps fall back to an in-order greedy scan to bound time and memory
+const MAX_DP_CELLS: usize = 1_000_000;
+
+/// How a pair of paragraphs was aligned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchKind {
+    /// Same `w14:paraId` in both copies, whatever the text now says
+    ParaId,
+    /// Identical text
+    Exact,
+    /// Similar text, matched inside a gap between exact matches
+    Similar,
+}
+
+/// One step of an alignment, in document order. A pair with only `base` is a
+/// paragraph missing from the other copy; one with only `other` was inserted
+/// there and belongs between its neighbours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedPair {
+    pub base: Option<usize>,
+    pub other: Option<usize>,
+    /// Text similarity of the pair (1.0 for exact matches, 0.0 when unpaired)
+    pub score: f64,
+    pub kind: Option<MatchKind>,
+}
+
+/// What the aligner compares for one paragraph
+#[derive(Debug, Clone)]
+pub struct AlignKey {
+    /// Paragraphs only align with paragraphs in the same place: same kind of
+    /// story, same header/footer pages, same table column
+    slot: Slot,
+    para_id: Option<String>,
+    /// Whitespace-normalised text used for exact matching
+    text: String,
+    tokens: HashSet<String>,
+    /// Paragraphs with no base text only align with other new paragraphs
+    wholly_inserted: bool,
+}
+
+impl AlignKey {
+    pub fn new(paragraph: &ParsedParagraph) -> Self {
+        let base = track_changes::base_text(&paragraph.segments);
+        let wholly_inserted = base.trim().is_empty();
+        let text = if wholly_inserted {
+            track_changes::revised_text(&paragraph.segments)
+        } else {
+            base
+        };
+        AlignKey {
+            slot: Slot {
+                kind: paragraph.kind,
+                header_footer_type: paragraph.header_footer_type,
+                column: paragraph.table.as_ref().map(|t| t.column),
+            },
+            para_id: paragraph.para_id.clone(),
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            tokens: tokenize(&text),
+            wholly_inserted,
+        }
+    }
+
+    /// Identity used when looking for paragraphs that occur once on each side
+    fn text_key(&self) -> (Slot, String, bool) {
+        (self.slot.clone(), self.text.clone(), self.wholly_inserted)
+    }
+
+    fn comparable(&self, other: &AlignKey) -> bool {
+        self.slot == other.slot && self.wholly_inserted == other.wholly_inserted
+    }
+
+    fn exact(&self, other: &AlignKey) -> bool {
+        self.comparable(other) && self.text == other.text
+    }
+
+    fn similarity(&self, other: &AlignKey) -> f64 {
+        if !self.comparable(other) {
+            return 0.0;
+        }
+        jaccard(&self.tokens, &other.tokens)
+    }
+}
+
+/// Where a paragraph sits in the document, beyond its text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Slot {
+    kind: BlockKind,
+    header_footer_type: Option<HeaderFooterType>,
+    /// Grid column for paragraphs inside a table cell
+    column: Option<usize>,
+}
+
+/// Align two key lists, preserving order on both sides.
+///
+/// Paragraphs sharing a `w14:paraId` are paired first, however much their text
+/// has changed. Between those, identical paragraphs are matched patience-diff
+/// style: common runs at the ends of each range, then paragraphs that occur
+/// exactly once on both sides and in the same relative order. Similarity is only
+/// used for the gaps left between anchors, so repeated boilerplate cannot jump
+/// to another clause.
+pub fn align(base: &[AlignKey], other: &[AlignKey]) -> Vec<AlignedPair> {
+    let mut pairs = Vec::new();
+    let (mut pb, mut po) = (0, 0);
+    for (ab, ao) in para_id_anchors(base, other) {
+        align_range(base, other, pb..ab, po..ao, &mut pairs);
+        let score = jaccard(&base[ab].tokens, &other[ao].tokens);
+        pairs.push(pair(Some(ab), Some(ao), score, Some(MatchKind::ParaId)));
+        pb = ab + 1;
+        po = ao + 1;
+    }
+    align_range(base, other, pb..base.len(), po..other.len(), &mut pairs);
+    pairs
+}
+
+/// Paragraphs whose `w14:paraId` occurs once on each side, in an order both
+/// sides agree on. Ids that repeat (e.g. after copy and paste) are ignored.
+fn para_id_anchors(base: &[AlignKey], other: &[AlignKey]) -> Vec<(usize, usize)> {
+    let ids = |keys: &'_ [AlignKey]| -> Vec<(usize, String)> {
+        keys.iter()
+            .enumerate()
+            .filter_map(|(i, k)| k.para_id.clone().map(|id| (i, id)))
+            .collect()
+    };
+    unique_pairs(ids(base), ids(other))
+}
+
+fn align_range(
+    base: &[AlignKey],
+    other: &[AlignKey],
+    b: std::ops::Range<usize>,
+    o: std::ops::Range<usize>,
+    out: &mut Vec<AlignedPair>,
+) {
+    let (mut b0, mut b1, mut o0, mut o1) = (b.start, b.end, o.start, o.end);
+
+    // Common prefix
+    while b0 < b1 && o0 < o1 && base[b0].exact(&other[o0]) {
+        out.push(pair(Some(b0), Some(o0), 1.0, Some(MatchKind::Exact)));
+        b0 += 1;
+        o0 += 1;
+    }
+
+    // Common suffix, emitted after the middle
+    let mut suffix = Vec::new();
+    while b0 < b1 && o0 < o1 && base[b1 - 1].exact(&other[o1 - 1]) {
+        b1 -= 1;
+        o1 -= 1;
+        suffix.push(pair(Some(b1), Some(o1), 1.0, Some(MatchKind::Exact)));
+    }
+
+    let anchors = unique_anchors(base, other, b0..b1, o0..o1);
+    if anchors.is_empty() {
+        align_gap(base, other, b0..b1, o0..o1, out);
+    } else {
+        let (mut pb, mut po) = (b0, o0);
+        for (ab, ao) in anchors {
+            align_range(base, other, pb..ab, po..ao, out);
+            out.push(pair(Some(ab), Some(ao), 1.0, Some(MatchKind::Exact)));
+            pb = ab + 1;
+            po = ao + 1;
+        }
+        align_range(base, other, pb..b1, po..o1, out);
+    }
+
+    out.extend(suffix.into_iter().rev());
+}
+
+/// Paragraphs whose text occurs exactly once in both ranges, reduced to the
+/// longest run that is increasing on both sides
+fn unique_anchors(
+    base: &[AlignKey],
+    other: &[AlignKey],
+    b: std::ops::Range<usize>,
+    o: std::ops::Range<usize>,
+) -> Vec<(usize, usize)> {
+    let base_texts = b.map(|i| (i, base[i].text_key())).collect();
+    let other_texts = o.map(|j| (j, other[j].text_key())).collect();
+    unique_pairs(base_texts, other_texts)
+}
+
+/// Pair up keys that occur exactly once on each side, keeping the longest
+/// subset whose order both sides agree on
+fn unique_pairs<K: Eq + Hash>(
+    base: Vec<(usize, K)>,
+    other: Vec<(usize, K)>,
+) -> Vec<(usize, usize)> {
+    // key → (count in base, count in other, last base index, last other index)
+    let mut counts: HashMap<K, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, key) in base {
+        let e = counts.entry(key).or_insert((0, 0, 0, 0));
+        e.0 += 1;
+        e.2 = i;
+    }
+    for (j, key) in other {
+        if let Some(e) = counts.get_mut(&key) {
+            e.1 += 1;
+            e.3 = j;
+        }
+    }
+
+    let mut candidates: Vec<(usize, usize)> = counts
+        .into_values()
+        .filter(|(in_base, in_other, _, _)| *in_base == 1 && *in_other == 1)
+        .map(|(_, _, bi, oi)| (bi, oi))
+        .collect();
+    candidates.sort_unstable();
+    longest_increasing(&candidates)
+}
+
+/// Longest subsequence (already sorted by base index) whose other index increases
+fn longest_increasing(candidates: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // tails[k] = index into candidates of the smallest tail of an increasing run of length k+1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; candidates.len()];
+    for (i, &(_, oi)) in candidates.iter().enumerate() {
+        let k = tails.partition_point(|&t| candidates[t].1 < oi);
+        if k > 0 {
+            prev[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut run = Vec::new();
+    let mut cur = tails.last().copied();
+    while let Some(i) = cur {
+        run.push(candidates[i]);
+        cur = prev[i];
+    }
+    run.reverse();
+    run
+}
+
+/// Align a gap with no exact anchors by maximising total similarity, keeping order
+fn align_gap(
+    base: &[AlignKey],
+    other: &[AlignKey],
+    b: std::ops::Range<usize>,
+    o: std::ops::Range<usize>,
+    out: &mut Vec<AlignedPair>,
+) {
+    let (n, m) = (b.len(), o.len());
+    if n == 0 || m == 0 {
+        out.extend(b.map(|i| pair(Some(i), None, 0.0, None)));
+        out.extend(o.map(|j| pair(None, Some(j), 0.0, None)));
+        return;
+    }
+    if n * m > MAX_DP_CELLS {
+        align_greedy(base, other, b, o, out);
+        return;
+    }
+
+    let sim = |i: usize, j: usize| -> f64 {
+        let s = base[b.start + i].similarity(&other[o.start + j]);
+        if s > MATCH_THRESHOLD {
+            s
+        } else {
+            0.0
+        }
+    };
+
+    // best[i][j] = best total score aligning the first i base and j other paragraphs
+    let width = m + 1;
+    let mut best = vec![0.0f64; (n + 1) * width];
+    for i in 1..=n {
+        for j in 1..=m {
+            let s = sim(i - 1, j - 1);
+            let diag = if s > 0.0 {
+                best[(i - 1) * width + j - 1] + s
+            } else {
+                0.0
+            };
+            best[i * width + j] = diag
+                .max(best[(i - 1) * width + j])
+                .max(best[i * width + j - 1]);
+        }
+    }
+
+    // Trace back, preferring matches, then base-only before other-only steps so
+    // that inserted paragraphs land after the paragraph they follow
+    let mut steps = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let s = sim(i - 1, j - 1);
+            if s > 0.0 && best[i * width + j] == best[(i - 1) * width + j - 1] + s {
+                steps.push(pair(
+                    Some(b.start + i - 1),
+                    Some(o.start + j - 1),
+                    s,
+                    Some(MatchKind::Similar),
+                ));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if j > 0 && (i == 0 || best[i * width + j] == best[i * width + j - 1]) {
+            steps.push(pair(None, Some(o.start + j - 1), 0.0, None));
+            j -= 1;
+        } else {
+            steps.push(pair(Some(b.start + i - 1), None, 0.0, None));
+            i -= 1;
+        }
+    }
+    out.extend(steps.into_iter().rev());
+}
+
+/// In-order fallback for very large gaps: each other paragraph takes the best
+/// match at or after the previous match, without looking back
+fn align_greedy(
+    base: &[AlignKey],
+    other: &[AlignKey],
+    b: std::ops::Range<usize>,
+    o: std::ops::Range<usize>,
+    out: &mut Vec<AlignedPair>,
+) {
+    let mut next_base = b.start;
+    for j in o {
+        let found = (next_base..b.end)
+            .map(|i| (i, base[i].similarity(&other[j])))
+            .filter(|(_, s)| *s > MATCH_THRESHOLD)
+            .fold(None, |best: Option<(usize, f64)>, (i, s)| match best {
+                Some((_, bs)) if bs >= s => best,
+                _ => Some((i, s)),
+            });
+        match found {
+            Some((i, s)) => {
+                out.extend((next_base..i).map(|k| pair(Some(k), None, 0.0, None)));
+                out.push(pair(Some(i), Some(j), s, Some(MatchKind::Similar)));
+                next_base = i + 1;
+            }
+            None => out.push(pair(None, Some(j), 0.0, None)),
+        }
+    }
+    out.extend((next_base..b.end).map(|k| pair(Some(k), None, 0.0, None)));
+}
+
+fn pair(
+    base: Option<usize>,
+    other: Option<usize>,
+    score: f64,
+    kind: Option<MatchKind>,
+) -> AlignedPair {
+    AlignedPair {
+        base,
+        other,
+        score,
+        kind,
+    }
+}
+
+/// Lower-cased word set with punctuation removed
+pub fn tokenize(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Jaccard similarity of two word sets (1.0 when both are empty)
+pub fn jaccard(set_a: &HashSet<String>, set_b: &HashSet<String>) -> f64 {
+    if set_a.is_empty() && set_b.is_empty() {
+        return 1.0;
+    }
+    if set_a.is_empty() || set_b.is_empty() {
+        return 0.0;
+    }
+    let intersection = set_a.intersection(set_b).count();
+    let union = set_a.len() + set_b.len() - intersection;
+    intersection as f64 / union as f64
+}
@@ -0,0 +1,244 @@
+//! Compound File Binary (OLE2 structured storage), the container of legacy
+//! Word .doc files. Only reading root-level streams is supported.
+
+const SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+const END_OF_CHAIN: u32 = 0xFFFF_FFFE;
+const FREE_SECTOR: u32 = 0xFFFF_FFFF;
+const NO_STREAM: u32 = 0xFFFF_FFFF;
+
+const DIRECTORY_ENTRY_SIZE: usize = 128;
+const STREAM: u8 = 2;
+
+/// A parsed compound file: its allocation tables and directory
+pub struct CompoundFile<'a> {
+    data: &'a [u8],
+    sector_size: usize,
+    mini_sector_size: usize,
+    mini_stream_cutoff: u64,
+    fat: Vec<u32>,
+    mini_fat: Vec<u32>,
+    /// The root entry's stream, which holds every small stream
+    mini_stream: Vec<u8>,
+    entries: Vec<DirectoryEntry>,
+}
+
+struct DirectoryEntry {
+    name: String,
+    entry_type: u8,
+    left: u32,
+    right: u32,
+    child: u32,
+    start_sector: u32,
+    size: u64,
+}
+
+/// Whether the data starts with the compound file signature
+pub fn is_compound_file(data: &[u8]) -> bool {
+    data.starts_with(&SIGNATURE)
+}
+
+impl<'a> CompoundFile<'a> {
+    pub fn open(data: &'a [u8]) -> Result<Self, String> {
+        if !is_compound_file(data) || data.len() < 512 {
+            return Err("Not a compound file (OLE2) — is this a valid .doc file?".to_string());
+        }
+        let sector_shift = u16_at(data, 0x1E).unwrap_or(9);
+        if !(7..=16).contains(&sector_shift) {
+            return Err(format!(
+                "Compound file has an invalid sector size (2^{})",
+                sector_shift
+            ));
+        }
+        let sector_size = 1usize << sector_shift;
+        let mini_sector_size = 1usize << u16_at(data, 0x20).unwrap_or(6).min(16);
+        let first_directory = u32_at(data, 0x30).unwrap_or(END_OF_CHAIN);
+        let mini_stream_cutoff = u32_at(data, 0x38).unwrap_or(4096) as u64;
+        let first_mini_fat = u32_at(data, 0x3C).unwrap_or(END_OF_CHAIN);
+        let mut next_difat = u32_at(data, 0x44).unwrap_or(END_OF_CHAIN);
+
+        let mut file = CompoundFile {
+            data,
+            sector_size,
+            mini_sector_size,
+            mini_stream_cutoff,
+            fat: Vec::new(),
+            mini_fat: Vec::new(),
+            mini_stream: Vec::new(),
+            entries: Vec::new(),
+        };
+
+        // FAT sector locations: 109 in the header, the rest in a DIFAT chain
+        let mut fat_sectors: Vec<u32> = (0..109)
+            .filter_map(|i| u32_at(data, 0x4C + i * 4))
+            .filter(|&s| s != FREE_SECTOR && s != END_OF_CHAIN)
+            .collect();
+        let per_difat = sector_size / 4 - 1;
+        let mut visited = 0;
+        while next_difat != END_OF_CHAIN
+            && next_difat != FREE_SECTOR
+            && visited < file.sector_count()
+        {
+            let sector = file
+                .sector(next_difat)
+                .ok_or("Compound file DIFAT points outside the file")?;
+            fat_sectors.extend(
+                (0..per_difat)
+                    .filter_map(|i| u32_at(sector, i * 4))
+                    .filter(|&s| s != FREE_SECTOR && s != END_OF_CHAIN),
+            );
+            next_difat = u32_at(sector, per_difat * 4).unwrap_or(END_OF_CHAIN);
+            visited += 1;
+        }
+        for s in fat_sectors {
+            let sector = file
+                .sector(s)
+                .ok_or("Compound file FAT points outside the file")?;
+            file.fat
+                .extend((0..sector_size / 4).filter_map(|i| u32_at(sector, i * 4)));
+        }
+
+        file.mini_fat = file
+            .chain(first_mini_fat)
+            .chunks(4)
+            .filter_map(|b| u32_at(b, 0))
+            .collect();
+
+        let directory = file.chain(first_directory);
+        file.entries = directory
+            .chunks(DIRECTORY_ENTRY_SIZE)
+            .filter(|c| c.len() == DIRECTORY_ENTRY_SIZE)
+            .map(DirectoryEntry::parse)
+            .collect();
+        if file.entries.is_empty() {
+            return Err("Compound file has no directory".to_string());
+        }
+
+        let root = &file.entries[0];
+        let (root_start, root_size) = (root.start_sector, root.size);
+        let mut mini_stream = file.chain(root_start);
+        mini_stream.truncate(root_size as usize);
+        file.mini_stream = mini_stream;
+
+        Ok(file)
+    }
+
+    /// Read a stream stored directly under the root storage. Names compare
+    /// case-insensitively, as in the format itself.
+    pub fn stream(&self, name: &str) -> Option<Vec<u8>> {
+        let entry = self
+            .root_children()
+            .into_iter()
+            .map(|i| &self.entries[i])
+            .find(|e| e.entry_type == STREAM && e.name.eq_ignore_ascii_case(name))?;
+
+        let mut bytes = if entry.size < self.mini_stream_cutoff {
+            self.mini_chain(entry.start_sector)
+        } else {
+            self.chain(entry.start_sector)
+        };
+        bytes.truncate(entry.size as usize);
+        Some(bytes)
+    }
+
+    /// Entries of the root storage, found by walking its red-black tree
+    fn root_children(&self) -> Vec<usize> {
+        let mut children = Vec::new();
+        let mut stack = vec![self.entries[0].child];
+        while let Some(id) = stack.pop() {
+            if id == NO_STREAM
+                || id as usize >= self.entries.len()
+                || children.len() > self.entries.len()
+            {
+                continue;
+            }
+            let entry = &self.entries[id as usize];
+            children.push(id as usize);
+            stack.push(entry.left);
+            stack.push(entry.right);
+        }
+        children
+    }
+
+    fn sector_count(&self) -> usize {
+        self.data.len() / self.sector_size
+    }
+
+    fn sector(&self, id: u32) -> Option<&'a [u8]> {
+        let start = (id as usize)
+            .checked_add(1)?
+            .checked_mul(self.sector_size)?;
+        self.data.get(start..start + self.sector_size)
+    }
+
+    /// Concatenate a chain of regular sectors. Cycles and dangling links end
+    /// the chain rather than failing.
+    fn chain(&self, start: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut id = start;
+        let mut steps = 0;
+        while id != END_OF_CHAIN && steps <= self.fat.len() {
+            match self.sector(id) {
+                Some(sector) => bytes.extend_from_slice(sector),
+                None => break,
+            }
+            id = match self.fat.get(id as usize) {
+                Some(&next) => next,
+                None => break,
+            };
+            steps += 1;
+        }
+        bytes
+    }
+
+    /// Concatenate a chain of mini sectors from the mini stream
+    fn mini_chain(&self, start: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut id = start;
+        let mut steps = 0;
+        while id != END_OF_CHAIN && steps <= self.mini_fat.len() {
+            let offset = id as usize * self.mini_sector_size;
+            match self.mini_stream.get(offset..offset + self.mini_sector_size) {
+                Some(sector) => bytes.extend_from_slice(sector),
+                None => break,
+            }
+            id = match self.mini_fat.get(id as usize) {
+                Some(&next) => next,
+                None => break,
+            };
+            steps += 1;
+        }
+        bytes
+    }
+}
+
+impl DirectoryEntry {
+    fn parse(raw: &[u8]) -> Self {
+        let name_bytes = (u16_at(raw, 64).unwrap_or(0) as usize).min(64);
+        let units: Vec<u16> = raw[..name_bytes]
+            .chunks(2)
+            .filter_map(|c| u16_at(c, 0))
+            .take_while(|&u| u != 0)
+            .collect();
+        DirectoryEntry {
+            name: String::from_utf16_lossy(&units),
+            entry_type: raw[66],
+            left: u32_at(raw, 68).unwrap_or(NO_STREAM),
+            right: u32_at(raw, 72).unwrap_or(NO_STREAM),
+            child: u32_at(raw, 76).unwrap_or(NO_STREAM),
+            start_sector: u32_at(raw, 116).unwrap_or(END_OF_CHAIN),
+            // Version 3 files may leave garbage in the high half
+            size: u32_at(raw, 120).unwrap_or(0) as u64,
+        }
+    }
+}
+
+pub fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
@@ -0,0 +1,216 @@
+use crate::track_changes::{Revision, Segment};
+use crate::types::{Conflict, ConflictingEdit};
+
+/// One reviewer's edit expressed as a span of the base text and its replacement.
+/// Offsets are in characters of the paragraph's base text; an insertion is an
+/// empty span (`start == end`).
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub author: String,
+    pub change_ids: Vec<String>,
+    pub start: usize,
+    pub end: usize,
+    pub new_text: String,
+}
+
+/// Turn a paragraph's segments into per-author edits against its base text.
+/// Deletions and insertions by the same author that touch each other (even with
+/// another author's insertion in between) are folded into a single replacement.
+pub fn edits(segments: &[Segment]) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut pos: usize = 0;
+
+    for seg in segments {
+        let layers: Vec<(&String, &String, usize, usize, &str)> = match seg {
+            // Formatting changes leave the text, so they cannot conflict with edits to it
+            Segment::Stable(t) | Segment::FormatChange { text: t, .. } => {
+                pos += t.chars().count();
+                continue;
+            }
+            Segment::Deletion {
+                id, author, text, ..
+            }
+            | Segment::MoveFrom {
+                id, author, text, ..
+            } => {
+                let start = pos;
+                pos += text.chars().count();
+                vec![(id, author, start, pos, "")]
+            }
+            Segment::Insertion {
+                id, author, text, ..
+            }
+            | Segment::MoveTo {
+                id, author, text, ..
+            } => vec![(id, author, pos, pos, text.as_str())],
+            // Every revision proposes its own outcome for the same base span:
+            // the text when it adds it, nothing when it removes it. Deleting
+            // another reviewer's insertion thus disagrees with it, while an
+            // author undoing their own revision leaves no edit at all.
+            Segment::Nested { revisions, text } => {
+                let start = pos;
+                if revisions.first().is_some_and(|r| !r.adds()) {
+                    pos += text.chars().count();
+                }
+                let mut kept: Vec<&Revision> = Vec::new();
+                for r in revisions {
+                    match kept.iter().rposition(|k| k.author == r.author) {
+                        Some(i) if kept[i].adds() != r.adds() => {
+                            kept.remove(i);
+                        }
+                        _ => kept.push(r),
+                    }
+                }
+                kept.into_iter()
+                    .map(|r| {
+                        let new_text = if r.adds() { text.as_str() } else { "" };
+                        (&r.id, &r.author, start, pos, new_text)
+                    })
+                    .collect()
+            }
+        };
+
+        for (id, author, start, end, new_text) in layers {
+            let previous = edits.iter_mut().rev().find(|e| &e.author == author);
+            match previous {
+                Some(prev) if prev.end == start => {
+                    prev.end = end;
+                    prev.new_text.push_str(new_text);
+                    if !prev.change_ids.contains(id) {
+                        prev.change_ids.push(id.clone());
+                    }
+                }
+                _ => edits.push(Edit {
+                    author: author.clone(),
+                    change_ids: vec![id.clone()],
+                    start,
+                    end,
+                    new_text: new_text.to_string(),
+                }),
+            }
+        }
+    }
+
+    edits
+}
+
+/// Find base spans that more than one author edited in incompatible ways.
+///
+/// Two edits conflict when their spans overlap, when one author inserts inside
+/// text another deletes, or when two authors insert different text at the same
+/// point. Identical edits (same span, same replacement) agree and do not conflict.
+/// Overlapping conflicts are merged into one record covering the combined span.
+pub fn detect(base: &str, edits: &[Edit]) -> Vec<Conflict> {
+    // Union-find over edits that conflict pairwise
+    let mut parent: Vec<usize> = (0..edits.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    let mut in_conflict = vec![false; edits.len()];
+    for i in 0..edits.len() {
+        for j in (i + 1)..edits.len() {
+            if edits[i].author != edits[j].author && incompatible(&edits[i], &edits[j]) {
+                in_conflict[i] = true;
+                in_conflict[j] = true;
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    // Group conflicting edits by cluster, in order of first appearance
+    let mut clusters: Vec<(usize, Vec<usize>)> = Vec::new();
+    for (i, _) in in_conflict.iter().enumerate().filter(|(_, c)| **c) {
+        let root = find(&mut parent, i);
+        match clusters.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, members)) => members.push(i),
+            None => clusters.push((root, vec![i])),
+        }
+    }
+
+    let base_chars: Vec<char> = base.chars().collect();
+    let mut conflicts: Vec<Conflict> = clusters
+        .into_iter()
+        .map(|(_, members)| {
+            let start = members.iter().map(|&i| edits[i].start).min().unwrap_or(0);
+            let end = members.iter().map(|&i| edits[i].end).max().unwrap_or(0);
+
+            let mut authors: Vec<&str> = Vec::new();
+            for &i in &members {
+                if !authors.contains(&edits[i].author.as_str()) {
+                    authors.push(&edits[i].author);
+                }
+            }
+
+            let competing = authors
+                .iter()
+                .map(|author| {
+                    let own: Vec<&Edit> = edits
+                        .iter()
+                        .filter(|e| e.author == *author && e.start >= start && e.end <= end)
+                        .collect();
+                    ConflictingEdit {
+                        author: author.to_string(),
+                        change_ids: own.iter().flat_map(|e| e.change_ids.clone()).collect(),
+                        proposed_text: apply(&base_chars, start, end, &own),
+                    }
+                })
+                .collect();
+
+            Conflict {
+                start,
+                end,
+                original_text: slice(&base_chars, start, end),
+                edits: competing,
+            }
+        })
+        .collect();
+
+    conflicts.sort_by_key(|c| (c.start, c.end));
+    conflicts
+}
+
+fn incompatible(a: &Edit, b: &Edit) -> bool {
+    let a_point = a.start == a.end;
+    let b_point = b.start == b.end;
+    match (a_point, b_point) {
+        (true, true) => a.start == b.start && a.new_text != b.new_text,
+        (true, false) => b.start < a.start && a.start < b.end,
+        (false, true) => a.start < b.start && b.start < a.end,
+        (false, false) => {
+            let identical = a.start == b.start && a.end == b.end && a.new_text == b.new_text;
+            !identical && a.start.max(b.start) < a.end.min(b.end)
+        }
+    }
+}
+
+/// Apply one author's edits (sorted by position) to the base span [start, end)
+fn apply(base: &[char], start: usize, end: usize, own: &[&Edit]) -> String {
+    let mut result = String::new();
+    let mut pos = start;
+    let mut sorted = own.to_vec();
+    sorted.sort_by_key(|e| (e.start, e.end));
+    for e in sorted {
+        if e.start > pos {
+            result.push_str(&slice(base, pos, e.start));
+        }
+        result.push_str(&e.new_text);
+        pos = pos.max(e.end);
+    }
+    if end > pos {
+        result.push_str(&slice(base, pos, end));
+    }
+    result
+}
+
+fn slice(chars: &[char], start: usize, end: usize) -> String {
+    let end = end.min(chars.len());
+    let start = start.min(end);
+    chars[start..end].iter().collect()
+}
@@ -0,0 +1,868 @@
+//! Legacy Word 97–2003 binary documents (.doc).
+//!
+//! Text comes from the piece table in the table stream; revision marks from
+//! the character properties (CHPX) of each run; comments from the annotation
+//! tables. Parts of the format that are not read are reported as warnings.
+
+use std::collections::HashMap;
+
+use crate::cfb::{self, u16_at, u32_at, CompoundFile};
+use crate::matcher;
+use crate::paragraphs::{self, ParsedDocument, ParsedParagraph};
+use crate::track_changes::{Revision, RevisionKind, Segment};
+use crate::types::{BlockKind, CollateResult, Comment};
+
+/// `wIdent` of a Word binary FIB
+const WORD_IDENT: u16 = 0xA5EC;
+/// `nFib` of Word 97; earlier versions use a different file layout
+const WORD_97_NFIB: u16 = 0x00C1;
+
+// Indices of (fc, lcb) pairs in FibRgFcLcb97
+const PLCF_FND_REF: usize = 2;
+const PLCF_FND_TXT: usize = 3;
+const PLCF_AND_REF: usize = 4;
+const PLCF_AND_TXT: usize = 5;
+const PLCF_BTE_CHPX: usize = 12;
+const CLX: usize = 33;
+const GRP_XST_ATN_OWNERS: usize = 36;
+const STTBF_ATN_BKMK: usize = 37;
+const PLCF_ATN_BKF: usize = 42;
+const PLCF_ATN_BKL: usize = 43;
+const PLCF_END_REF: usize = 46;
+const PLCF_END_TXT: usize = 47;
+const STTBF_RMARK: usize = 51;
+// Index of PlcfAtrdExtra in FibRgFcLcb2002 (following the 93 pairs of 97 and 30 of 2000)
+const ATRD_EXTRA: usize = 127;
+
+// Character property modifiers (sprms) carrying revision marks
+const SPRM_C_F_RMARK_DEL: u16 = 0x0800;
+const SPRM_C_F_RMARK_INS: u16 = 0x0801;
+const SPRM_C_IBST_RMARK: u16 = 0x4804;
+const SPRM_C_DTTM_RMARK: u16 = 0x6805;
+const SPRM_C_IBST_RMARK_DEL: u16 = 0x4863;
+const SPRM_C_DTTM_RMARK_DEL: u16 = 0x6864;
+const SPRM_C_PROP_RMARK: u16 = 0xCA57;
+const SPRM_C_PROP_RMARK_90: u16 = 0xCA89;
+
+const FKP_PAGE_SIZE: usize = 512;
+/// Size of an ATRDPre10 (annotation reference) record
+const ATRD_SIZE: usize = 30;
+/// Size of an ATRDPost10 record, which starts with the annotation's DTTM
+const ATRD_EXTRA_SIZE: usize = 18;
+
+/// A compound file; whether it holds a Word document is decided by `read`
+pub fn is_doc(data: &[u8]) -> bool {
+    cfb::is_compound_file(data)
+}
+
+/// Parse a .doc file from raw bytes
+pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
+    let document = read(data)?;
+    let (paragraph_blocks, reviewers) =
+        matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, file_name);
+
+    Ok(CollateResult {
+        paragraphs: paragraph_blocks,
+        reviewers,
+        document_title: document.title,
+        error: None,
+        warnings: document.warnings,
+    })
+}
+
+/// Read a .doc file into parsed paragraphs, comments and title
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    let file = CompoundFile::open(data)?;
+    let word = match file.stream("WordDocument") {
+        Some(stream) => stream,
+        None if file.stream("EncryptedPackage").is_some() => return Err(
+            "This document is password-protected — remove the password in Word and upload it again"
+                .to_string(),
+        ),
+        None => return Err("WordDocument stream not found — is this a Word .doc file?".to_string()),
+    };
+    let fib = Fib::parse(&word)?;
+    let table_name = if fib.which_table { "1Table" } else { "0Table" };
+    let table = file
+        .stream(table_name)
+        .ok_or_else(|| format!("{} stream not found — the .doc file is damaged", table_name))?;
+
+    let text = Text::new(&word, &table, &fib)?;
+    let mut warnings = Vec::new();
+
+    // Subdocuments follow the main text in CP order
+    let main_end = fib.ccp_text;
+    let footnotes_start = main_end;
+    // The counts come from the file, so a damaged one must not overflow
+    let annotations_start = footnotes_start
+        .saturating_add(fib.ccp_ftn)
+        .saturating_add(fib.ccp_hdd)
+        .saturating_add(fib.ccp_mcr);
+    let endnotes_start = annotations_start.saturating_add(fib.ccp_atn);
+
+    let comments = text.comments(&fib, &table, annotations_start);
+    let mut story = text.story(0, main_end, BlockKind::Body, &comments.references);
+    let mut paragraphs = std::mem::take(&mut story.paragraphs);
+
+    for (kind, refs, txt, start) in [
+        (
+            BlockKind::Footnote,
+            PLCF_FND_REF,
+            PLCF_FND_TXT,
+            footnotes_start,
+        ),
+        (
+            BlockKind::Endnote,
+            PLCF_END_REF,
+            PLCF_END_TXT,
+            endnotes_start,
+        ),
+    ] {
+        let references = plc_cps(fib.blob(&table, refs), 2);
+        let ranges = plc_cps(fib.blob(&table, txt), 0);
+        // The last CP of a PLC closes the final element rather than starting one
+        let count = references.len().saturating_sub(1);
+        for (i, reference) in references.iter().take(count).enumerate() {
+            let (Some(&from), Some(&to)) = (ranges.get(i), ranges.get(i + 1)) else {
+                break;
+            };
+            let note_id = (i + 1).to_string();
+            let host = story.host(*reference);
+            if let Some(host) = host {
+                let refs = match kind {
+                    BlockKind::Endnote => &mut paragraphs[host].endnote_refs,
+                    _ => &mut paragraphs[host].footnote_refs,
+                };
+                refs.push(note_id.clone());
+            }
+            let note = text.story(
+                start.saturating_add(from),
+                start.saturating_add(to),
+                kind,
+                &HashMap::new(),
+            );
+            for mut paragraph in note.paragraphs {
+                paragraph.index = paragraphs.len();
+                paragraph.note_id = Some(note_id.clone());
+                paragraph.anchor_index = host;
+                paragraphs.push(paragraph);
+            }
+            story.has_cells |= note.has_cells;
+        }
+    }
+    paragraphs::label_columns(&mut paragraphs);
+
+    if story.has_cells {
+        warnings.push(
+            "Tables in .doc files are read as ordinary paragraphs; cell positions are not recovered"
+                .to_string(),
+        );
+    }
+    if fib.ccp_hdd > 0 {
+        warnings.push("Headers and footers in .doc files are not read".to_string());
+    }
+    if fib.ccp_txbx > 0 || fib.ccp_hdr_txbx > 0 {
+        warnings.push("Text boxes in .doc files are not read".to_string());
+    }
+    if text.has_format_changes {
+        warnings.push(
+            "Formatting revisions in .doc files are not read; only insertions and deletions are"
+                .to_string(),
+        );
+    }
+    if comments.unanchored > 0 {
+        warnings.push(format!(
+            "{} comment(s) in this .doc file have no recoverable anchor text",
+            comments.unanchored
+        ));
+    }
+    let undated = comments
+        .comments
+        .values()
+        .filter(|c| c.date.is_none())
+        .count();
+    if undated > 0 {
+        warnings.push(format!(
+            "{} comment(s) in this .doc file have no date; files saved before Word 2002 do not store one",
+            undated
+        ));
+    }
+
+    Ok(ParsedDocument {
+        paragraphs,
+        comments: comments.comments,
+        title: file
+            .stream("\u{5}SummaryInformation")
+            .and_then(|s| summary_title(&s)),
+        warnings,
+    })
+}
+
+/// The parts of the File Information Block this reader needs
+struct Fib {
+    which_table: bool,
+    ccp_text: u32,
+    ccp_ftn: u32,
+    ccp_hdd: u32,
+    ccp_mcr: u32,
+    ccp_atn: u32,
+    ccp_txbx: u32,
+    ccp_hdr_txbx: u32,
+    /// (fc, lcb) pairs locating structures in the table stream
+    fc_lcb: Vec<(u32, u32)>,
+}
+
+impl Fib {
+    fn parse(word: &[u8]) -> Result<Self, String> {
+        if u16_at(word, 0) != Some(WORD_IDENT) {
+            return Err(
+                "Not a Word document — the WordDocument stream has no Word header".to_string(),
+            );
+        }
+        let n_fib = u16_at(word, 2).unwrap_or(0);
+        if n_fib < WORD_97_NFIB {
+            return Err(
+                "Word 6.0/95 .doc files are not supported — save the document in Word 97–2003 or .docx format"
+                    .to_string(),
+            );
+        }
+        let flags = u16_at(word, 0x0A).unwrap_or(0);
+        if flags & 0x0100 != 0 {
+            return Err(
+                "This document is password-protected — remove the password in Word and upload it again"
+                    .to_string(),
+            );
+        }
+
+        // FibRgW97 and FibRgLw97 have counted lengths, then FibRgFcLcb
+        let csw = u16_at(word, 32).ok_or("Truncated .doc header")? as usize;
+        let rg_lw_count_at = 34 + csw * 2;
+        let cslw = u16_at(word, rg_lw_count_at).ok_or("Truncated .doc header")? as usize;
+        let rg_lw = rg_lw_count_at + 2;
+        let lw = |i: usize| u32_at(word, rg_lw + i * 4).unwrap_or(0);
+        let fc_lcb_count_at = rg_lw + cslw * 4;
+        let cb_rg_fc_lcb = u16_at(word, fc_lcb_count_at).ok_or("Truncated .doc header")? as usize;
+        let fc_lcb = (0..cb_rg_fc_lcb)
+            .filter_map(|i| {
+                let at = fc_lcb_count_at + 2 + i * 8;
+                Some((u32_at(word, at)?, u32_at(word, at + 4)?))
+            })
+            .collect();
+
+        Ok(Fib {
+            which_table: flags & 0x0200 != 0,
+            ccp_text: lw(3),
+            ccp_ftn: lw(4),
+            ccp_hdd: lw(5),
+            ccp_mcr: lw(6),
+            ccp_atn: lw(7),
+            ccp_txbx: lw(9),
+            ccp_hdr_txbx: lw(10),
+            fc_lcb,
+        })
+    }
+
+    /// The table stream bytes of structure `index`, empty when absent
+    fn blob<'t>(&self, table: &'t [u8], index: usize) -> &'t [u8] {
+        match self.fc_lcb.get(index) {
+            Some(&(fc, lcb)) if lcb > 0 => {
+                let (start, end) = (fc as usize, fc as usize + lcb as usize);
+                table.get(start..end).unwrap_or(&[])
+            }
+            _ => &[],
+        }
+    }
+}
+
+/// A run of text stored contiguously in the WordDocument stream
+struct Piece {
+    cp_start: u32,
+    cp_end: u32,
+    /// Byte offset of the first character
+    offset: usize,
+    /// 8-bit (Windows-1252) rather than UTF-16 characters
+    compressed: bool,
+}
+
+/// Revision attributes of a character, from a CHPX here or from RTF
+/// character formatting. Authors are indices into the revision author table.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct CharRevision {
+    pub inserted: bool,
+    pub deleted: bool,
+    pub author: Option<usize>,
+    pub date: u32,
+    pub delete_author: Option<usize>,
+    pub delete_date: Option<u32>,
+}
+
+/// Who inserted and who deleted a character, as (author index, DTTM)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Mark {
+    inserted: Option<(Option<usize>, u32)>,
+    deleted: Option<(Option<usize>, u32)>,
+}
+
+impl CharRevision {
+    pub fn mark(&self) -> Mark {
+        Mark {
+            inserted: self.inserted.then_some((self.author, self.date)),
+            // The deleting author defaults to the one who marked the text
+            deleted: self.deleted.then(|| {
+                (
+                    self.delete_author.or(self.author),
+                    self.delete_date.unwrap_or(self.date),
+                )
+            }),
+        }
+    }
+}
+
+impl Mark {
+    /// The segment for `text` under these marks. Text inserted by one
+    /// reviewer and deleted by another is `Nested`, insertion first. `id`
+    /// gives each revision an id, as the formats have none of their own.
+    pub fn segment(
+        &self,
+        text: String,
+        authors: &[String],
+        mut id: impl FnMut() -> String,
+    ) -> Segment {
+        let revisions = [
+            (RevisionKind::Insertion, self.inserted),
+            (RevisionKind::Deletion, self.deleted),
+        ]
+        .into_iter()
+        .filter_map(|(kind, mark)| {
+            let (author, date) = mark?;
+            Some(Revision {
+                kind,
+                id: id(),
+                author: author
+                    .and_then(|i| authors.get(i))
+                    .cloned()
+                    .unwrap_or_default(),
+                date: dttm(date),
+            })
+        })
+        .collect();
+        Segment::revised(revisions, text)
+    }
+}
+
+/// One character of a story after field codes are dropped
+enum Item {
+    Char(u16, Mark),
+    ParagraphEnd,
+    /// A table cell or row end mark
+    CellEnd,
+    /// Annotation reference mark (0x05)
+    AnnotationReference,
+}
+
+/// The document text with the revision marks of every character
+struct Text {
+    pieces: Vec<Piece>,
+    word: Vec<u8>,
+    /// (first fc, end fc, revision) for each CHPX run, sorted by fc
+    runs: Vec<(u32, u32, CharRevision)>,
+    authors: Vec<String>,
+    has_format_changes: bool,
+}
+
+/// Paragraphs of one story, with the CP ranges they came from
+struct Story {
+    paragraphs: Vec<ParsedParagraph>,
+    /// (first cp, end cp, paragraph index) for each paragraph kept
+    spans: Vec<(u32, u32, usize)>,
+    has_cells: bool,
+}
+
+impl Story {
+    /// Index of the paragraph containing `cp`
+    fn host(&self, cp: u32) -> Option<usize> {
+        self.spans
+            .iter()
+            .find(|(start, end, _)| (*start..=*end).contains(&cp))
+            .map(|(_, _, index)| *index)
+    }
+}
+
+/// Comments read from the annotation tables
+struct Annotations {
+    comments: HashMap<String, Comment>,
+    /// Comment ids keyed by the CP of their reference mark in the main text
+    references: HashMap<u32, (String, String)>,
+    unanchored: usize,
+}
+
+impl Text {
+    fn new(word: &[u8], table: &[u8], fib: &Fib) -> Result<Self, String> {
+        let pieces = pieces(fib.blob(table, CLX))?;
+        let mut text = Text {
+            pieces,
+            word: word.to_vec(),
+            runs: Vec::new(),
+            authors: sttb(fib.blob(table, STTBF_RMARK))
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+            has_format_changes: false,
+        };
+        text.read_runs(fib.blob(table, PLCF_BTE_CHPX));
+        Ok(text)
+    }
+
+    /// Collect character property runs from the CHPX FKP pages
+    fn read_runs(&mut self, plc_bte: &[u8]) {
+        let count = plc_bte.len().saturating_sub(4) / 8;
+        for i in 0..count {
+            let pn = u32_at(plc_bte, (count + 1) * 4 + i * 4).unwrap_or(0) & 0x003F_FFFF;
+            let start = pn as usize * FKP_PAGE_SIZE;
+            let page = match self.word.get(start..start + FKP_PAGE_SIZE) {
+                Some(page) => page.to_vec(),
+                None => continue,
+            };
+            let crun = page[FKP_PAGE_SIZE - 1] as usize;
+            for r in 0..crun {
+                let (Some(fc_start), Some(fc_end)) =
+                    (u32_at(&page, r * 4), u32_at(&page, r * 4 + 4))
+                else {
+                    break;
+                };
+                let word_offset = page.get((crun + 1) * 4 + r).copied().unwrap_or(0) as usize;
+                let revision = if word_offset == 0 {
+                    CharRevision::default()
+                } else {
+                    let at = word_offset * 2;
+                    let cb = page.get(at).copied().unwrap_or(0) as usize;
+                    let grpprl = page.get(at + 1..at + 1 + cb).unwrap_or(&[]);
+                    self.revision(grpprl)
+                };
+                self.runs.push((fc_start, fc_end, revision));
+            }
+        }
+        self.runs.sort_by_key(|(start, _, _)| *start);
+    }
+
+    /// Revision attributes from a CHPX's property modifiers
+    fn revision(&mut self, grpprl: &[u8]) -> CharRevision {
+        let mut revision = CharRevision::default();
+        let mut pos = 0;
+        while let Some(sprm) = u16_at(grpprl, pos) {
+            pos += 2;
+            let size = match sprm >> 13 {
+                0 | 1 => 1,
+                2 | 4 | 5 => 2,
+                3 => 4,
+                7 => 3,
+                _ => 1 + grpprl.get(pos).copied().unwrap_or(0) as usize,
+            };
+            let operand = grpprl.get(pos..pos + size).unwrap_or(&[]);
+            // Toggle operands: 1 sets the property, 0x81 sets the opposite of the style (off)
+            let on = matches!(operand.first(), Some(1) | Some(0x81));
+            match sprm {
+                SPRM_C_F_RMARK_DEL => revision.deleted = on,
+                SPRM_C_F_RMARK_INS => revision.inserted = on,
+                SPRM_C_IBST_RMARK => revision.author = u16_at(operand, 0).map(usize::from),
+                SPRM_C_DTTM_RMARK => revision.date = u32_at(operand, 0).unwrap_or(0),
+                SPRM_C_IBST_RMARK_DEL => {
+                    revision.delete_author = u16_at(operand, 0).map(usize::from)
+                }
+                SPRM_C_DTTM_RMARK_DEL => revision.delete_date = u32_at(operand, 0),
+                // Operand: cb, then fPropRMark
+                SPRM_C_PROP_RMARK | SPRM_C_PROP_RMARK_90
+                    if operand.get(1).copied().unwrap_or(0) != 0 =>
+                {
+                    self.has_format_changes = true;
+                }
+                _ => {}
+            }
+            pos += size;
+        }
+        revision
+    }
+
+    /// Characters of a CP range with their revision marks, walking the
+    /// pieces in order. Text a piece claims beyond the stream is skipped.
+    fn chars(&self, start: u32, end: u32) -> impl Iterator<Item = (u32, u16, Mark)> + '_ {
+        self.pieces
+            .iter()
+            .filter(move |p| p.cp_start < end && start < p.cp_end)
+            .flat_map(move |piece| {
+                (start.max(piece.cp_start)..end.min(piece.cp_end)).map_while(move |cp| {
+                    let index = (cp - piece.cp_start) as usize;
+                    let (unit, fc) = if piece.compressed {
+                        let at = piece.offset + index;
+                        (cp1252(*self.word.get(at)?), at as u32)
+                    } else {
+                        let at = piece.offset + index * 2;
+                        (u16_at(&self.word, at)?, at as u32)
+                    };
+                    Some((cp, unit, self.mark_at(fc)))
+                })
+            })
+    }
+
+    /// Revision mark of the character at file offset `fc`
+    fn mark_at(&self, fc: u32) -> Mark {
+        let run = self.runs.partition_point(|(start, _, _)| *start <= fc);
+        match run.checked_sub(1).map(|r| &self.runs[r]) {
+            Some((_, end, revision)) if fc < *end => revision.mark(),
+            _ => Mark::default(),
+        }
+    }
+
+    /// The characters of a CP range, with field codes removed (field results are kept)
+    fn items(&self, start: u32, end: u32) -> Vec<(u32, Item)> {
+        let mut items = Vec::new();
+        // One entry per open field: whether its result (after the separator) has started
+        let mut fields: Vec<bool> = Vec::new();
+        for (cp, unit, mark) in self.chars(start, end) {
+            let item = match unit {
+                0x13 => {
+                    fields.push(false);
+                    continue;
+                }
+                0x14 => {
+                    if let Some(in_result) = fields.last_mut() {
+                        *in_result = true;
+                    }
+                    continue;
+                }
+                0x15 => {
+                    fields.pop();
+                    continue;
+                }
+                _ if fields.iter().any(|in_result| !in_result) => continue,
+                0x0D | 0x0C => Item::ParagraphEnd,
+                0x07 => Item::CellEnd,
+                0x05 => Item::AnnotationReference,
+                0x09 => Item::Char(u16::from(b'\t'), mark),
+                0x0B => Item::Char(u16::from(b'\n'), mark),
+                0x1E => Item::Char(u16::from(b'-'), mark),
+                u if u < 0x20 => continue,
+                u => Item::Char(u, mark),
+            };
+            items.push((cp, item));
+        }
+        items
+    }
+
+    /// Visible text of a CP range, paragraphs separated by line breaks
+    fn plain_text(&self, start: u32, end: u32) -> String {
+        let mut units = Vec::new();
+        for (_, item) in self.items(start, end) {
+            match item {
+                Item::Char(unit, _) => units.push(unit),
+                Item::ParagraphEnd | Item::CellEnd => units.push(u16::from(b'\n')),
+                Item::AnnotationReference => {}
+            }
+        }
+        String::from_utf16_lossy(&units).trim().to_string()
+    }
+
+    /// Split a CP range into paragraphs. `comment_refs` maps annotation
+    /// reference CPs to (comment id, anchor text).
+    fn story(
+        &self,
+        start: u32,
+        end: u32,
+        kind: BlockKind,
+        comment_refs: &HashMap<u32, (String, String)>,
+    ) -> Story {
+        let mut story = Story {
+            paragraphs: Vec::new(),
+            spans: Vec::new(),
+            has_cells: false,
+        };
+        let mut runs: Vec<(Mark, Vec<u16>)> = Vec::new();
+        let mut comment_ids = Vec::new();
+        let mut comment_anchor_texts = HashMap::new();
+        let mut paragraph_start = start;
+
+        for (cp, item) in self.items(start, end) {
+            match item {
+                Item::Char(unit, mark) => match runs.last_mut() {
+                    Some((last, units)) if *last == mark => units.push(unit),
+                    _ => runs.push((mark, vec![unit])),
+                },
+                Item::AnnotationReference => {
+                    if let Some((id, anchor)) = comment_refs.get(&cp) {
+                        comment_ids.push(id.clone());
+                        comment_anchor_texts.insert(id.clone(), anchor.clone());
+                    }
+                }
+                Item::ParagraphEnd | Item::CellEnd => {
+                    story.has_cells |= matches!(item, Item::CellEnd);
+                    let segments = self.segments(std::mem::take(&mut runs), paragraph_start);
+                    let has_content = segments.iter().any(Segment::has_content);
+                    if has_content {
+                        let index = story.paragraphs.len();
+                        story.spans.push((paragraph_start, cp, index));
+                        story.paragraphs.push(ParsedParagraph {
+                            index,
+                            segments,
+                            comment_ids: std::mem::take(&mut comment_ids),
+                            comment_anchor_texts: std::mem::take(&mut comment_anchor_texts),
+                            kind,
+                            ..Default::default()
+                        });
+                    }
+                    comment_ids.clear();
+                    comment_anchor_texts.clear();
+                    paragraph_start = cp + 1;
+                }
+            }
+        }
+        story
+    }
+
+    /// Turn runs of equally marked characters into segments
+    fn segments(&self, runs: Vec<(Mark, Vec<u16>)>, paragraph_start: u32) -> Vec<Segment> {
+        let mut revisions = 0;
+        runs.into_iter()
+            .map(|(mark, units)| {
+                mark.segment(String::from_utf16_lossy(&units), &self.authors, || {
+                    revisions += 1;
+                    format!("{}-{}", paragraph_start, revisions)
+                })
+            })
+            .collect()
+    }
+
+    /// Comments from the annotation reference and text tables, anchored on
+    /// the range of their annotation bookmark when there is one
+    fn comments(&self, fib: &Fib, table: &[u8], annotations_start: u32) -> Annotations {
+        let mut annotations = Annotations {
+            comments: HashMap::new(),
+            references: HashMap::new(),
+            unanchored: 0,
+        };
+        let owners = xst_list(fib.blob(table, GRP_XST_ATN_OWNERS));
+        let plc_ref = fib.blob(table, PLCF_AND_REF);
+        let references = plc_cps(plc_ref, ATRD_SIZE);
+        let texts = plc_cps(fib.blob(table, PLCF_AND_TXT), 0);
+        // Only Word 2002 and later store annotation dates
+        let extra = fib.blob(table, ATRD_EXTRA);
+
+        // Bookmark ranges keyed by the tag an annotation refers to them by
+        let tags: Vec<u32> = sttb(fib.blob(table, STTBF_ATN_BKMK))
+            .into_iter()
+            .map(|(_, extra)| u32_at(&extra, 2).unwrap_or(u32::MAX))
+            .collect();
+        let plc_bkf = fib.blob(table, PLCF_ATN_BKF);
+        let starts = plc_cps(plc_bkf, 4);
+        let ends = plc_cps(fib.blob(table, PLCF_ATN_BKL), 0);
+        let ranges: HashMap<u32, (u32, u32)> = tags
+            .iter()
+            .enumerate()
+            .filter_map(|(k, tag)| {
+                let ibkl = u16_at(plc_bkf, starts.len() * 4 + k * 4)? as usize;
+                Some((*tag, (*starts.get(k)?, *ends.get(ibkl)?)))
+            })
+            .collect();
+
+        let count = references.len().saturating_sub(1);
+        for (i, &reference) in references.iter().take(count).enumerate() {
+            let atrd = references.len() * 4 + i * ATRD_SIZE;
+            let initials_len = (u16_at(plc_ref, atrd).unwrap_or(0) as usize).min(9);
+            let initials: Vec<u16> = (0..initials_len)
+                .filter_map(|c| u16_at(plc_ref, atrd + 2 + c * 2))
+                .collect();
+            let owner = u16_at(plc_ref, atrd + 20).unwrap_or(0) as usize;
+            let tag = u32_at(plc_ref, atrd + 26).unwrap_or(u32::MAX);
+
+            let text = match (texts.get(i), texts.get(i + 1)) {
+                (Some(&from), Some(&to)) => self.plain_text(
+                    annotations_start.saturating_add(from),
+                    annotations_start.saturating_add(to),
+                ),
+                _ => String::new(),
+            };
+            let anchor_text = match ranges.get(&tag) {
+                Some(&(from, to)) => self.plain_text(from, to),
+                None => {
+                    annotations.unanchored += 1;
+                    String::new()
+                }
+            };
+
+            let id = i.to_string();
+            annotations.comments.insert(
+                id.clone(),
+                Comment {
+                    id: id.clone(),
+                    author: owners.get(owner).cloned().unwrap_or_default(),
+                    date: u32_at(extra, i * ATRD_EXTRA_SIZE).and_then(dttm),
+                    text,
+                    anchor_text: String::new(),
+                    initials: Some(String::from_utf16_lossy(&initials)).filter(|s| !s.is_empty()),
+                    source_file: None,
+                },
+            );
+            annotations.references.insert(reference, (id, anchor_text));
+        }
+        annotations
+    }
+}
+
+/// Read the piece table from the CLX, skipping any leading property modifiers
+fn pieces(clx: &[u8]) -> Result<Vec<Piece>, String> {
+    let mut pos = 0;
+    while let Some(&clxt) = clx.get(pos) {
+        match clxt {
+            0x01 => {
+                let cb = u16_at(clx, pos + 1).unwrap_or(0) as usize;
+                pos += 3 + cb;
+            }
+            0x02 => {
+                let lcb = u32_at(clx, pos + 1).unwrap_or(0) as usize;
+                let plc = clx.get(pos + 5..pos + 5 + lcb).unwrap_or(&[]);
+                let cps = plc_cps(plc, 8);
+                let count = cps.len().saturating_sub(1);
+                let pcd_start = (count + 1) * 4;
+                return Ok((0..count)
+                    .filter_map(|i| {
+                        let fc = u32_at(plc, pcd_start + i * 8 + 2)?;
+                        let compressed = fc & 0x4000_0000 != 0;
+                        let fc = (fc & 0x3FFF_FFFF) as usize;
+                        Some(Piece {
+                            cp_start: cps[i],
+                            cp_end: cps[i + 1],
+                            offset: if compressed { fc / 2 } else { fc },
+                            compressed,
+                        })
+                    })
+                    .collect());
+            }
+            _ => break,
+        }
+    }
+    Err("The .doc file has no readable piece table".to_string())
+}
+
+/// The CPs of a PLC whose data elements are `data_size` bytes each. The
+/// count is derived from the size: n data elements follow n + 1 CPs.
+fn plc_cps(plc: &[u8], data_size: usize) -> Vec<u32> {
+    if plc.len() < 4 {
+        return Vec::new();
+    }
+    let count = (plc.len() - 4) / (4 + data_size);
+    let cps = if data_size == 0 {
+        plc.len() / 4
+    } else {
+        count + 1
+    };
+    (0..cps).filter_map(|i| u32_at(plc, i * 4)).collect()
+}
+
+/// Strings of an STTB and the extra data after each one
+fn sttb(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let extended = u16_at(data, 0) == Some(0xFFFF);
+    let mut pos = if extended { 2 } else { 0 };
+    let count = u16_at(data, pos).unwrap_or(0) as usize;
+    let cb_extra = u16_at(data, pos + 2).unwrap_or(0) as usize;
+    pos += 4;
+    for _ in 0..count {
+        let name = if extended {
+            let Some(cch) = u16_at(data, pos) else { break };
+            let units: Vec<u16> = (0..cch as usize)
+                .filter_map(|c| u16_at(data, pos + 2 + c * 2))
+                .collect();
+            pos += 2 + cch as usize * 2;
+            String::from_utf16_lossy(&units)
+        } else {
+            let Some(&cch) = data.get(pos) else { break };
+            let bytes = data.get(pos + 1..pos + 1 + cch as usize).unwrap_or(&[]);
+            pos += 1 + cch as usize;
+            bytes
+                .iter()
+                .map(|&b| char::from_u32(cp1252(b) as u32).unwrap_or('?'))
+                .collect()
+        };
+        let extra = data.get(pos..pos + cb_extra).unwrap_or(&[]).to_vec();
+        pos += cb_extra;
+        entries.push((name, extra));
+    }
+    entries
+}
+
+/// A sequence of length-prefixed UTF-16 strings (Xst)
+fn xst_list(data: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut pos = 0;
+    while let Some(cch) = u16_at(data, pos) {
+        let units: Vec<u16> = (0..cch as usize)
+            .filter_map(|c| u16_at(data, pos + 2 + c * 2))
+            .collect();
+        strings.push(String::from_utf16_lossy(&units));
+        pos += 2 + cch as usize * 2;
+    }
+    strings
+}
+
+/// Format a DTTM (packed date and time) as an ISO 8601 timestamp
+pub fn dttm(value: u32) -> Option<String> {
+    if value == 0 {
+        return None;
+    }
+    let minute = value & 0x3F;
+    let hour = (value >> 6) & 0x1F;
+    let day = (value >> 11) & 0x1F;
+    let month = (value >> 16) & 0x0F;
+    let year = 1900 + ((value >> 20) & 0x1FF);
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:00Z",
+        year, month, day, hour, minute
+    ))
+}
+
+/// Windows-1252 byte to UTF-16 code unit
+pub fn cp1252(byte: u8) -> u16 {
+    const HIGH: [u16; 32] = [
+        0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022,
+        0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        b => b as u16,
+    }
+}
+
+/// Document title (PIDSI_TITLE) from the SummaryInformation property set
+fn summary_title(stream: &[u8]) -> Option<String> {
+    const PIDSI_TITLE: u32 = 2;
+    const VT_LPSTR: u32 = 30;
+    const VT_LPWSTR: u32 = 31;
+
+    let section = u32_at(stream, 44)? as usize;
+    let count = u32_at(stream, section + 4)? as usize;
+    let offset = (0..count.min(1024)).find_map(|i| {
+        let at = section + 8 + i * 8;
+        (u32_at(stream, at)? == PIDSI_TITLE).then(|| u32_at(stream, at + 4))?
+    })? as usize;
+    let property = section + offset;
+    let len = u32_at(stream, property + 4)? as usize;
+    let value = property + 8;
+    let title: String = match u32_at(stream, property)? {
+        VT_LPSTR => stream
+            .get(value..value.checked_add(len)?)?
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| char::from_u32(cp1252(b) as u32).unwrap_or('?'))
+            .collect(),
+        VT_LPWSTR => {
+            // The length comes from the file; stop at the end of the stream
+            let units: Vec<u16> = (0..len)
+                .map_while(|c| u16_at(stream, value + c * 2))
+                .take_while(|&u| u != 0)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    Some(title.trim().to_string()).filter(|t| !t.is_empty())
+}
@@ -1,98 +1,178 @@
-use std::io::Read;
-
-use zip::ZipArchive;
+use std::collections::{HashMap, HashSet};
 
 use crate::comments;
 use crate::matcher;
-use crate::paragraphs;
-use crate::types::CollateResult;
+use crate::package::{self, Package};
+use crate::paragraphs::{self, ParsedDocument};
+use crate::sections;
+use crate::types::{BlockKind, CollateResult};
+use crate::xml::{self, Ns};
+
+const COMMENTS_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.comments+xml";
+const FOOTNOTES_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml";
+const ENDNOTES_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.endnotes+xml";
+const STYLES_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml";
+const CORE_PROPERTIES_TYPE: &str = "application/vnd.openxmlformats-package.core-properties+xml";
 
 /// Parse a .docx file from raw bytes
 pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
-    let cursor = std::io::Cursor::new(data);
-    let mut archive =
-        ZipArchive::new(cursor).map_err(|e| format!("Failed to open ZIP archive: {}", e))?;
+    let document = read(data)?;
 
-    // Read document.xml (required)
-    let document_xml = read_zip_file(&mut archive, "word/document.xml")
-        .ok_or_else(|| "word/document.xml not found — is this a valid .docx file?".to_string())?;
+    // Build paragraph blocks with matched comments
+    let (paragraph_blocks, reviewers) =
+        matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, file_name);
 
-    // Read comments.xml (optional)
-    let comments_xml = read_zip_file(&mut archive, "word/comments.xml");
+    Ok(CollateResult {
+        paragraphs: paragraph_blocks,
+        reviewers,
+        document_title: document.title,
+        error: None,
+        warnings: document.warnings,
+    })
+}
 
-    // Read core.xml for document title (optional)
-    let core_xml = read_zip_file(&mut archive, "docProps/core.xml");
+/// Read a .docx package into parsed paragraphs, comments and title, without
+/// building paragraph blocks
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    let mut package = Package::open(data)?;
+    let content_types = package.content_types();
+
+    // Main document part (required), found through _rels/.rels
+    let main = package.main_document(&content_types)?;
+    let document_xml = package
+        .read(&main)
+        .ok_or_else(|| format!("Main document part '{}' could not be read", main))?;
+
+    // Parts related to the main document (optional)
+    let relationships = package.relationships(&main);
+    let mut related = |short_type: &str, content_type: &str, file_name: &str| {
+        let fallback = package::sibling(&main, file_name);
+        package
+            .related_part(
+                &relationships,
+                short_type,
+                content_type,
+                &content_types,
+                &fallback,
+            )
+            .and_then(|part| package.read(&part))
+    };
+    let comments_xml = related("comments", COMMENTS_TYPE, "comments.xml");
+    let footnotes_xml = related("footnotes", FOOTNOTES_TYPE, "footnotes.xml");
+    let endnotes_xml = related("endnotes", ENDNOTES_TYPE, "endnotes.xml");
+    let styles_xml = related("styles", STYLES_TYPE, "styles.xml");
+
+    // Core properties for the document title (optional), related to the package
+    let core_part = package
+        .relationships("")
+        .into_iter()
+        .find(|r| r.rel_type.ends_with("/metadata/core-properties"))
+        .map(|r| r.target)
+        .or_else(|| content_types.part_of_type(CORE_PROPERTIES_TYPE).map(String::from))
+        .unwrap_or_else(|| "docProps/core.xml".to_string());
+    let core_xml = package.read(&core_part);
 
     // Parse comments
-    let comments_map = match &comments_xml {
+    let comments = match &comments_xml {
         Some(xml) => comments::parse_comments(xml),
         None => std::collections::HashMap::new(),
     };
 
-    // Parse document paragraphs
-    let parsed_paragraphs = paragraphs::parse_document(&document_xml);
+    // Parse document paragraphs, then notes linked back to their references
+    let mut paragraphs = paragraphs::parse_document(&document_xml);
+
+    // Headers and footers, in the order sections use them. A part shared by
+    // several sections is reported once, against the first section using it.
+    let mut seen_parts = HashSet::new();
+    for reference in sections::references(&document_xml) {
+        let rel = match relationships.iter().find(|r| r.id == reference.rel_id) {
+            Some(rel) => rel,
+            None => continue,
+        };
+        if !seen_parts.insert(rel.target.clone()) {
+            continue;
+        }
+        if let Some(xml) = package.read(&rel.target) {
+            paragraphs::append_header_footer(
+                &mut paragraphs,
+                &xml,
+                &rel.target,
+                reference.kind,
+                Some(reference.section),
+                Some(reference.header_footer_type),
+            );
+        }
+    }
+    // Parts related to the document that no section refers to
+    for rel in &relationships {
+        let kind = match rel.short_type() {
+            Some("header") => BlockKind::Header,
+            Some("footer") => BlockKind::Footer,
+            _ => continue,
+        };
+        if !seen_parts.insert(rel.target.clone()) {
+            continue;
+        }
+        if let Some(xml) = package.read(&rel.target) {
+            paragraphs::append_header_footer(&mut paragraphs, &xml, &rel.target, kind, None, None);
+        }
+    }
 
-    // Build paragraph blocks with matched comments
-    let (paragraph_blocks, reviewers) =
-        matcher::build_paragraph_blocks(&parsed_paragraphs, &comments_map, file_name);
+    if let Some(xml) = &footnotes_xml {
+        paragraphs::append_notes(&mut paragraphs, paragraphs::parse_notes(xml, BlockKind::Footnote));
+    }
+    if let Some(xml) = &endnotes_xml {
+        paragraphs::append_notes(&mut paragraphs, paragraphs::parse_notes(xml, BlockKind::Endnote));
+    }
+
+    // Format revisions name styles by id; show the names Word displays
+    if let Some(xml) = &styles_xml {
+        paragraphs::name_styles(&mut paragraphs, &style_names(xml));
+    }
 
     // Extract document title
-    let document_title = core_xml.and_then(|xml| extract_title(&xml));
+    let title = core_xml.and_then(|xml| extract_title(&xml));
 
-    Ok(CollateResult {
-        paragraphs: paragraph_blocks,
-        reviewers,
-        document_title,
-        error: None,
+    Ok(ParsedDocument {
+        paragraphs,
+        comments,
+        title,
+        warnings: Vec::new(),
     })
 }
 
-fn read_zip_file(archive: &mut ZipArchive<std::io::Cursor<&[u8]>>, path: &str) -> Option<String> {
-    let mut file = archive.by_name(path).ok()?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).ok()?;
-    Some(contents)
-}
-
-fn extract_title(core_xml: &str) -> Option<String> {
+/// Read `dc:title` from a core properties (or ODF meta.xml) part
+pub fn extract_title(core_xml: &str) -> Option<String> {
     use quick_xml::events::Event;
-    use quick_xml::Reader;
+    use quick_xml::NsReader;
 
-    let mut reader = Reader::from_str(core_xml);
+    let mut reader = NsReader::from_str(core_xml);
     reader.config_mut().trim_text(true);
     let mut buf = Vec::new();
     let mut in_title = false;
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = e.name();
-                let name_ref = name.as_ref();
-                let local = local_name(name_ref);
-                if local == b"title" {
-                    in_title = true;
-                }
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) if ns == Ns::DublinCore && e.local_name().as_ref() == b"title" => {
+                in_title = true;
             }
-            Ok(Event::Text(ref e)) => {
-                if in_title {
-                    if let Ok(txt) = e.unescape() {
-                        let title = txt.trim().to_string();
-                        if !title.is_empty() {
-                            return Some(title);
-                        }
+            Event::Text(ref e) if in_title => {
+                if let Ok(txt) = e.unescape() {
+                    let title = txt.trim().to_string();
+                    if !title.is_empty() {
+                        return Some(title);
                     }
                 }
             }
-            Ok(Event::End(ref e)) => {
-                let name = e.name();
-                let name_ref = name.as_ref();
-                let local = local_name(name_ref);
-                if local == b"title" {
-                    in_title = false;
-                }
+            Event::End(ref e) if ns == Ns::DublinCore && e.local_name().as_ref() == b"title" => {
+                in_title = false;
             }
-            Ok(Event::Eof) => break,
-            Err(_) => break,
+            Event::Eof => break,
             _ => {}
         }
         buf.clear();
@@ -101,10 +181,50 @@ fn extract_title(core_xml: &str) -> Option<String> {
     None
 }
 
-fn local_name(name: &[u8]) -> &[u8] {
-    if let Some(pos) = name.iter().position(|&b| b == b':') {
-        &name[pos + 1..]
-    } else {
-        name
+/// Display names of the styles in a styles part, by style id. Built-in styles
+/// are stored in lower case ("heading 2") but shown capitalised.
+fn style_names(styles_xml: &str) -> HashMap<String, String> {
+    use quick_xml::events::Event;
+    use quick_xml::NsReader;
+
+    let mut reader = NsReader::from_str(styles_xml);
+    let mut buf = Vec::new();
+    let mut names = HashMap::new();
+    let mut style_id: Option<String> = None;
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) if ns == Ns::Word && e.local_name().as_ref() == b"style" => {
+                style_id = e
+                    .attributes()
+                    .flatten()
+                    .find(|a| xml::local_name(a.key.as_ref()) == b"styleId")
+                    .map(|a| String::from_utf8_lossy(&a.value).to_string());
+            }
+            Event::Empty(ref e) if ns == Ns::Word && e.local_name().as_ref() == b"name" => {
+                let name = e
+                    .attributes()
+                    .flatten()
+                    .find(|a| xml::local_name(a.key.as_ref()) == b"val")
+                    .map(|a| String::from_utf8_lossy(&a.value).to_string());
+                if let (Some(id), Some(name)) = (style_id.take(), name) {
+                    let mut chars = name.chars();
+                    let display = match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => name,
+                    };
+                    names.insert(id, display);
+                }
+            }
+            Event::End(ref e) if ns == Ns::Word && e.local_name().as_ref() == b"style" => {
+                style_id = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
     }
+
+    names
 }
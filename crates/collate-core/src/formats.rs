@@ -0,0 +1,40 @@
+use crate::doc;
+use crate::docx;
+use crate::odt;
+use crate::paragraphs::ParsedDocument;
+use crate::pdf;
+use crate::rtf;
+use crate::types::CollateResult;
+
+/// Parse a document in any supported format. Legacy .doc files are recognised
+/// by the compound file signature, RTF by its `{\rtf` header, PDF markups by
+/// the `%PDF-` header and OpenDocument text by its `mimetype` entry; everything
+/// else goes to the .docx reader.
+pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
+    if doc::is_doc(data) {
+        doc::parse(data, file_name)
+    } else if rtf::is_rtf(data) {
+        rtf::parse(data, file_name)
+    } else if pdf::is_pdf(data) {
+        pdf::parse(data, file_name)
+    } else if odt::is_odt(data) {
+        odt::parse(data, file_name)
+    } else {
+        docx::parse(data, file_name)
+    }
+}
+
+/// Read a document in any supported format into parsed paragraphs
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    if doc::is_doc(data) {
+        doc::read(data)
+    } else if rtf::is_rtf(data) {
+        rtf::read(data)
+    } else if pdf::is_pdf(data) {
+        pdf::read(data)
+    } else if odt::is_odt(data) {
+        odt::read(data)
+    } else {
+        docx::read(data)
+    }
+}
@@ -1,26 +1,151 @@
 use wasm_bindgen::prelude::*;
 
+mod align;
+mod cfb;
 mod comments;
+mod conflicts;
+mod doc;
 mod docx;
+mod formats;
+mod markup;
 mod matcher;
+mod merge;
+mod odt;
+mod package;
 mod paragraphs;
+mod pdf;
+mod rtf;
+mod sections;
 mod track_changes;
 pub mod types;
+mod xml;
 
 #[cfg(test)]
 mod tests;
 
+use types::CollateResult;
+
+/// What to leave out of a result
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CollateOptions {
+    /// Leave out changes Word made while updating fields (see
+    /// `TrackChange::machine_generated`)
+    pub drop_machine_generated: bool,
+}
+
+impl CollateOptions {
+    /// Options from a JS `{ dropMachineGenerated?: boolean }` object
+    fn from_js(options: &JsValue) -> Self {
+        let flag = |name: &str| {
+            js_sys::Reflect::get(options, &JsValue::from_str(name))
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        };
+        CollateOptions {
+            drop_machine_generated: flag("dropMachineGenerated"),
+        }
+    }
+}
+
 /// Parse a .docx file and return a JSON string containing the CollateResult.
-/// This is the single public API — takes raw file bytes and filename, returns everything.
+/// Takes raw file bytes and filename, returns everything for that one file.
+/// Flat OPC (single-file Word XML), OpenDocument (.odt), legacy Word (.doc)
+/// and RTF input are detected and read the same way; a PDF gives one paragraph
+/// per reviewer markup.
 #[wasm_bindgen]
 pub fn parse_docx(data: &[u8], file_name: &str) -> String {
-    match docx::parse(data, file_name) {
-        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|e| {
-            format!(r#"{{"error":"Serialization failed: {}"}}"#, e)
-        }),
-        Err(e) => {
-            let escaped = e.replace('\\', "\\\\").replace('"', "\\\"");
-            format!(r#"{{"error":"{}"}}"#, escaped)
+    to_json(&parse(data, file_name, &CollateOptions::default()))
+}
+
+/// `parse_docx` with `options`, a `{ dropMachineGenerated?: boolean }` object
+#[wasm_bindgen]
+pub fn parse_docx_with_options(data: &[u8], file_name: &str, options: JsValue) -> String {
+    to_json(&parse(data, file_name, &CollateOptions::from_js(&options)))
+}
+
+/// Parse several documents and merge the reviewer copies into the base document,
+/// returning a JSON string containing one CollateResult for all of them.
+/// `files` is an array of `{ name: string, data: Uint8Array }` objects.
+#[wasm_bindgen]
+pub fn collate_documents(files: js_sys::Array, base_name: &str) -> String {
+    to_json(&collate(
+        &js_files(&files),
+        base_name,
+        &CollateOptions::default(),
+    ))
+}
+
+/// `collate_documents` with `options`, as for `parse_docx_with_options`
+#[wasm_bindgen]
+pub fn collate_documents_with_options(
+    files: js_sys::Array,
+    base_name: &str,
+    options: JsValue,
+) -> String {
+    to_json(&collate(
+        &js_files(&files),
+        base_name,
+        &CollateOptions::from_js(&options),
+    ))
+}
+
+fn js_files(files: &js_sys::Array) -> Vec<(String, Vec<u8>)> {
+    files
+        .iter()
+        .map(|file| {
+            let name = js_sys::Reflect::get(&file, &JsValue::from_str("name"))
+                .ok()
+                .and_then(|v| v.as_string())
+                .unwrap_or_default();
+            let data = js_sys::Reflect::get(&file, &JsValue::from_str("data"))
+                .map(|v| js_sys::Uint8Array::new(&v).to_vec())
+                .unwrap_or_default();
+            (name, data)
+        })
+        .collect()
+}
+
+/// Parse one document. A failure is reported in the result's `error` with
+/// no paragraphs.
+pub fn parse(data: &[u8], file_name: &str, options: &CollateOptions) -> CollateResult {
+    finish(formats::parse(data, file_name), options)
+}
+
+/// Parse several documents, given as `(file name, bytes)`, and merge the
+/// reviewer copies into the base document. A failure is reported in the
+/// result's `error` with no paragraphs.
+pub fn collate(
+    files: &[(String, Vec<u8>)],
+    base_name: &str,
+    options: &CollateOptions,
+) -> CollateResult {
+    let borrowed: Vec<(&str, &[u8])> = files
+        .iter()
+        .map(|(name, data)| (name.as_str(), data.as_slice()))
+        .collect();
+    finish(merge::collate(&borrowed, base_name), options)
+}
+
+fn finish(result: Result<CollateResult, String>, options: &CollateOptions) -> CollateResult {
+    match result {
+        Ok(mut result) => {
+            if options.drop_machine_generated {
+                matcher::drop_machine_generated(&mut result);
+            }
+            result
         }
+        Err(e) => CollateResult {
+            paragraphs: Vec::new(),
+            reviewers: Vec::new(),
+            document_title: None,
+            error: Some(e),
+            warnings: Vec::new(),
+        },
     }
 }
+
+fn to_json(result: &CollateResult) -> String {
+    serde_json::to_string(result)
+        .unwrap_or_else(|e| format!(r#"{{"error":"Serialization failed: {}"}}"#, e))
+}
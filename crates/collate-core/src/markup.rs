@@ -0,0 +1,436 @@
+//! Anchoring PDF markups to the paragraphs of the base document.
+//!
+//! The text under a markup comes from the printed page, so line breaks,
+//! hyphenation and extraction gaps can make it differ a little from the
+//! paragraph it was printed from. Markups are looked up first by exact text,
+//! then approximately, across the whole base text so that context spilling
+//! over a paragraph break still counts. Repeated phrases are told apart by the
+//! page text around the markup.
+
+use std::collections::HashMap;
+
+use crate::paragraphs::ParsedParagraph;
+use crate::pdf::{self, Markup, MarkupKind};
+use crate::track_changes::Segment;
+use crate::types::Comment;
+
+/// Characters of context used to place notes and carets
+const POINT_CONTEXT: usize = 30;
+
+/// Markups placed on base paragraphs
+pub struct Anchored {
+    /// Index of the base paragraph and a copy of it carrying the markups as
+    /// revisions and comment anchors
+    pub paragraphs: Vec<(usize, ParsedParagraph)>,
+    pub comments: HashMap<String, Comment>,
+    /// Markups that could not be placed
+    pub warnings: Vec<String>,
+}
+
+/// Where a markup landed: a paragraph and a span of its base text, in characters
+struct Placement<'a> {
+    paragraph: usize,
+    start: usize,
+    end: usize,
+    markup: &'a Markup,
+}
+
+/// Place each markup on the base paragraph whose text it matches. Strike-outs,
+/// carets and replacements become revisions; everything else becomes a
+/// comment. Markups that are not found, or that overlap a revision already
+/// placed, are reported as warnings.
+pub fn anchor(base_texts: &[&str], markups: &[Markup]) -> Anchored {
+    let text = BaseText::new(base_texts);
+    let mut anchored = Anchored {
+        paragraphs: Vec::new(),
+        comments: HashMap::new(),
+        warnings: Vec::new(),
+    };
+
+    let mut placements: Vec<Placement> = Vec::new();
+    for markup in markups {
+        let span = if markup.marked_text.is_empty() {
+            text.locate_point(&markup.context_before, &markup.context_after)
+        } else {
+            text.locate_span(
+                &markup.marked_text,
+                &markup.context_before,
+                &markup.context_after,
+            )
+        };
+        match span.and_then(|(from, to)| text.to_paragraph(from, to)) {
+            Some((paragraph, start, end)) => placements.push(Placement {
+                paragraph,
+                start,
+                end,
+                markup,
+            }),
+            None => anchored.warnings.push(format!(
+                "Page {}: {} by {} not found in the base document{}",
+                markup.page + 1,
+                describe(markup.kind),
+                if markup.author.is_empty() {
+                    "an unknown reviewer"
+                } else {
+                    &markup.author
+                },
+                if markup.marked_text.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (\"{}\")", markup.marked_text)
+                }
+            )),
+        }
+    }
+
+    placements.sort_by_key(|p| (p.paragraph, p.start, p.end));
+    let mut current: Option<(usize, ParsedParagraph, usize)> = None;
+    for placement in placements {
+        let chars: Vec<char> = base_texts[placement.paragraph].chars().collect();
+        if current
+            .as_ref()
+            .is_none_or(|(index, _, _)| *index != placement.paragraph)
+        {
+            finish(&mut anchored, current.take(), base_texts);
+            let paragraph = ParsedParagraph {
+                index: placement.paragraph,
+                ..Default::default()
+            };
+            current = Some((placement.paragraph, paragraph, 0));
+        }
+        let Some((_, paragraph, cursor)) = current.as_mut() else {
+            continue;
+        };
+
+        let markup = placement.markup;
+        let span: String = chars[placement.start..placement.end].iter().collect();
+        let revision = match markup.kind {
+            MarkupKind::StrikeOut => Some((span.clone(), String::new())),
+            MarkupKind::Replace => Some((span.clone(), markup.contents.clone())),
+            MarkupKind::Caret if !markup.contents.is_empty() => {
+                Some((String::new(), markup.contents.clone()))
+            }
+            _ => None,
+        };
+
+        match revision {
+            Some(_) if placement.start < *cursor => anchored.warnings.push(format!(
+                "Page {}: {} by {} overlaps another change and was skipped",
+                markup.page + 1,
+                describe(markup.kind),
+                markup.author
+            )),
+            Some((deleted, inserted)) => {
+                let stable: String = chars[*cursor..placement.start].iter().collect();
+                if !stable.is_empty() {
+                    paragraph.segments.push(Segment::Stable(stable));
+                }
+                if !deleted.is_empty() {
+                    paragraph.segments.push(Segment::Deletion {
+                        id: markup.id.clone(),
+                        author: markup.author.clone(),
+                        date: markup.date.clone(),
+                        text: deleted,
+                    });
+                }
+                if !inserted.is_empty() {
+                    paragraph.segments.push(Segment::Insertion {
+                        id: markup.id.clone(),
+                        author: markup.author.clone(),
+                        date: markup.date.clone(),
+                        text: inserted,
+                    });
+                }
+                *cursor = placement.end;
+            }
+            None => {
+                let mut comment = pdf::comment(markup);
+                comment.anchor_text = span.clone();
+                paragraph.comment_ids.push(markup.id.clone());
+                paragraph
+                    .comment_anchor_texts
+                    .insert(markup.id.clone(), span);
+                anchored.comments.insert(markup.id.clone(), comment);
+            }
+        }
+    }
+    finish(&mut anchored, current, base_texts);
+
+    anchored
+}
+
+/// Close a paragraph copy with the base text after its last revision
+fn finish(
+    anchored: &mut Anchored,
+    current: Option<(usize, ParsedParagraph, usize)>,
+    base_texts: &[&str],
+) {
+    let Some((index, mut paragraph, cursor)) = current else {
+        return;
+    };
+    let rest: String = base_texts[index].chars().skip(cursor).collect();
+    if !rest.is_empty() {
+        paragraph.segments.push(Segment::Stable(rest));
+    }
+    anchored.paragraphs.push((index, paragraph));
+}
+
+fn describe(kind: MarkupKind) -> &'static str {
+    match kind {
+        MarkupKind::Note => "note",
+        MarkupKind::FreeText => "text box",
+        MarkupKind::Highlight => "highlight",
+        MarkupKind::Underline => "underline",
+        MarkupKind::StrikeOut => "strike-out",
+        MarkupKind::Caret => "insertion",
+        MarkupKind::Replace => "replacement",
+    }
+}
+
+/// Fold characters that print alike so page text compares with document text
+fn fold(c: char) -> char {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => '\'',
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{2033}' => '"',
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2212}' => '-',
+        c if c.is_whitespace() => ' ',
+        c => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+/// Fold a string and collapse its whitespace runs
+fn normalise(text: &str) -> Vec<char> {
+    let mut chars: Vec<char> = Vec::new();
+    for c in text.chars().map(fold) {
+        if c != ' ' || chars.last() != Some(&' ') {
+            chars.push(c);
+        }
+    }
+    chars
+}
+
+/// The base paragraphs joined into one normalised string, with a map back to
+/// paragraph and character positions
+struct BaseText {
+    chars: Vec<char>,
+    /// (paragraph, character offset) of each normalised character; paragraph
+    /// breaks are a space mapped to the end of the preceding paragraph
+    origin: Vec<(usize, usize)>,
+    paragraph_lengths: Vec<usize>,
+    /// Positions of every three-character sequence, to seed approximate search
+    trigrams: HashMap<[char; 3], Vec<usize>>,
+}
+
+impl BaseText {
+    fn new(texts: &[&str]) -> Self {
+        let mut base = BaseText {
+            chars: Vec::new(),
+            origin: Vec::new(),
+            paragraph_lengths: Vec::new(),
+            trigrams: HashMap::new(),
+        };
+        for (p, text) in texts.iter().enumerate() {
+            let mut length = 0;
+            for (offset, c) in text.chars().enumerate() {
+                let c = fold(c);
+                if c != ' ' || base.chars.last().is_some_and(|&last| last != ' ') {
+                    base.chars.push(c);
+                    base.origin.push((p, offset));
+                }
+                length = offset + 1;
+            }
+            if base.chars.last().is_some_and(|&last| last != ' ') {
+                base.chars.push(' ');
+                base.origin.push((p, length));
+            }
+            base.paragraph_lengths.push(length);
+        }
+        for (i, w) in base.chars.windows(3).enumerate() {
+            base.trigrams.entry([w[0], w[1], w[2]]).or_default().push(i);
+        }
+        base
+    }
+
+    /// Approximate occurrences of `pattern`, searched only near exact
+    /// occurrences of its pieces rather than across the whole text. A match
+    /// within `m / 4` edits leaves at least one of `m / 4 + 1` pieces of the
+    /// pattern intact, so it lies around where that piece occurs.
+    fn approximate(&self, pattern: &[char]) -> Vec<(usize, usize, usize)> {
+        let m = pattern.len();
+        let limit = m / 4;
+        let piece = m / (limit + 1);
+        if piece < 3 {
+            // Short patterns are cheap to search everywhere
+            return approximate(&self.chars, pattern);
+        }
+
+        let mut windows: Vec<(usize, usize)> = Vec::new();
+        for offset in (0..=limit).map(|k| k * piece) {
+            let seed = [pattern[offset], pattern[offset + 1], pattern[offset + 2]];
+            for &at in self.trigrams.get(&seed).into_iter().flatten() {
+                let from = at.saturating_sub(offset + limit);
+                let to = (at + m + limit)
+                    .saturating_sub(offset)
+                    .min(self.chars.len());
+                windows.push((from, to));
+            }
+        }
+        windows.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (from, to) in windows {
+            match merged.last_mut() {
+                Some(last) if from <= last.1 => last.1 = last.1.max(to),
+                _ => merged.push((from, to)),
+            }
+        }
+
+        merged
+            .into_iter()
+            .flat_map(|(from, to)| {
+                approximate(&self.chars[from..to], pattern)
+                    .into_iter()
+                    .map(move |(start, end, cost)| (start + from, end + from, cost))
+            })
+            .collect()
+    }
+
+    /// Map a normalised span to a paragraph and a span of its text. A span
+    /// that runs into the next paragraph is cut at the paragraph end.
+    fn to_paragraph(&self, from: usize, to: usize) -> Option<(usize, usize, usize)> {
+        let (paragraph, start) = *self.origin.get(from)?;
+        let end = match to.checked_sub(1).and_then(|last| self.origin.get(last)) {
+            Some(&(p, offset)) if p == paragraph && to > from => offset + 1,
+            Some(_) if to > from => self.paragraph_lengths[paragraph],
+            _ => start,
+        };
+        Some((paragraph, start, end.min(self.paragraph_lengths[paragraph])))
+    }
+
+    /// Find the span of `marked`, preferring exact matches and, among several,
+    /// the one whose surroundings agree best with the page context
+    fn locate_span(&self, marked: &str, before: &str, after: &str) -> Option<(usize, usize)> {
+        let pattern = normalise(marked.trim());
+        let (before, after) = (normalise(before), normalise(after));
+        let mut candidates: Vec<(usize, usize, usize)> = exact(&self.chars, &pattern)
+            .into_iter()
+            .map(|start| (start, start + pattern.len(), 0))
+            .collect();
+        if candidates.is_empty() {
+            candidates = self.approximate(&pattern);
+        }
+        candidates
+            .into_iter()
+            .min_by_key(|&(start, end, cost)| {
+                let agreement = common_suffix(&self.chars[..start], &before)
+                    + common_prefix(&self.chars[end..], &after);
+                (cost * 4) as isize - agreement as isize
+            })
+            .map(|(start, end, _)| (start, end))
+    }
+
+    /// Find the point between the page text before and after a note or caret
+    fn locate_point(&self, before: &str, after: &str) -> Option<(usize, usize)> {
+        let before = normalise(before);
+        let after = normalise(after);
+        let before = &before[before.len().saturating_sub(POINT_CONTEXT)..];
+        let after = &after[..after.len().min(POINT_CONTEXT)];
+        let point = |p: usize| Some((p, p));
+
+        let joined: Vec<char> = before.iter().chain(after).copied().collect();
+        if joined.iter().all(|c| *c == ' ') {
+            return None;
+        }
+        if let Some(&start) = exact(&self.chars, &joined).first() {
+            return point(start + before.len());
+        }
+        let trimmed = |side: &[char]| -> Vec<char> {
+            let text: String = side.iter().collect();
+            text.trim().chars().collect()
+        };
+        let (before, after) = (trimmed(before), trimmed(after));
+        if !before.is_empty() {
+            if let Some(&(_, end, _)) = self.approximate(&before).iter().min_by_key(|c| c.2) {
+                return point(end);
+            }
+        }
+        if !after.is_empty() {
+            if let Some(&(start, _, _)) = self.approximate(&after).iter().min_by_key(|c| c.2) {
+                return point(start);
+            }
+        }
+        None
+    }
+}
+
+/// Start positions of every exact occurrence of `pattern`
+fn exact(text: &[char], pattern: &[char]) -> Vec<usize> {
+    if pattern.is_empty() || pattern.len() > text.len() {
+        return Vec::new();
+    }
+    text.windows(pattern.len())
+        .enumerate()
+        .filter(|(_, w)| *w == pattern)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Approximate occurrences of `pattern` as (start, end, edit distance), allowing
+/// one edit per four characters. Uses Sellers' algorithm, carrying the start of
+/// the best alignment through each cell; of candidates that share a start only
+/// the cheapest is kept.
+fn approximate(text: &[char], pattern: &[char]) -> Vec<(usize, usize, usize)> {
+    let m = pattern.len();
+    if m == 0 {
+        return Vec::new();
+    }
+    let limit = m / 4;
+    // (cost, start) for each pattern prefix length, for the current text position
+    let mut column: Vec<(usize, usize)> = (0..=m).map(|i| (i, 0)).collect();
+    let mut candidates: Vec<(usize, usize, usize)> = Vec::new();
+
+    for (j, &c) in text.iter().enumerate() {
+        let mut diagonal = column[0];
+        column[0] = (0, j + 1);
+        for i in 1..=m {
+            let substitute = (diagonal.0 + usize::from(pattern[i - 1] != c), diagonal.1);
+            let delete = (column[i].0 + 1, column[i].1);
+            let insert = (column[i - 1].0 + 1, column[i - 1].1);
+            diagonal = column[i];
+            column[i] = cheaper(cheaper(substitute, delete), insert);
+        }
+        let (cost, start) = column[m];
+        if cost <= limit {
+            match candidates.last_mut() {
+                Some(last) if last.0 == start => {
+                    if cost < last.2 {
+                        *last = (start, j + 1, cost);
+                    }
+                }
+                _ => candidates.push((start, j + 1, cost)),
+            }
+        }
+    }
+    candidates
+}
+
+/// The cheaper of two (cost, start) alignments; on a tie, the one starting
+/// later, which is the shorter match
+fn cheaper(a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+    if b.0 < a.0 || (b.0 == a.0 && b.1 > a.1) {
+        b
+    } else {
+        a
+    }
+}
+
+fn common_suffix(text: &[char], context: &[char]) -> usize {
+    text.iter()
+        .rev()
+        .zip(context.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+fn common_prefix(text: &[char], context: &[char]) -> usize {
+    text.iter().zip(context).take_while(|(a, b)| a == b).count()
+}
@@ -1,8 +1,41 @@
 use std::collections::HashMap;
 
+use crate::conflicts;
 use crate::paragraphs::ParsedParagraph;
-use crate::track_changes::{self, Segment};
-use crate::types::{Comment, ParagraphBlock, ParagraphStatus, Reviewer, ReviewerVersion, TrackChange};
+use crate::track_changes::{self, Revision, Segment};
+use crate::types::{
+    BlockKind, CollateResult, Comment, Field, HeaderFooterType, MoveEnd, ParagraphBlock,
+    ParagraphStatus, Reviewer, ReviewerVersion, TrackChange,
+};
+
+/// Accent colours assigned to reviewers in order of first appearance
+pub const REVIEWER_COLOURS: [&str; 8] = [
+    "#EF4444", "#3B82F6", "#10B981", "#F59E0B", "#8B5CF6", "#F97316", "#14B8A6", "#EC4899",
+];
+
+/// Fields whose results Word rewrites by itself when it updates fields
+const GENERATED_FIELDS: [&str; 6] = ["TOC", "PAGEREF", "PAGE", "REF", "NOTEREF", "DATE"];
+
+/// Identifies one story: the body, a note, or a header/footer part
+type StoryKey = (
+    BlockKind,
+    Option<String>,
+    Option<usize>,
+    Option<HeaderFooterType>,
+    Option<String>,
+);
+
+/// The last paragraph seen in a story, whose mark revision decides how the
+/// next paragraph relates to it
+struct PreviousParagraph {
+    index: usize,
+    mark: Option<Revision>,
+    status: ParagraphStatus,
+    base_text: String,
+    /// Relations a wholly inserted or deleted paragraph passes on to the next
+    split_from: Option<usize>,
+    merged_into: Option<usize>,
+}
 
 /// Build the final CollateResult paragraphs by combining parsed paragraphs with comments
 pub fn build_paragraph_blocks(
@@ -11,15 +44,91 @@ pub fn build_paragraph_blocks(
     file_name: &str,
 ) -> (Vec<ParagraphBlock>, Vec<Reviewer>) {
     let mut blocks = Vec::new();
-    let mut reviewer_changes: HashMap<String, (usize, usize)> = HashMap::new();
+    // (name, comment count, change count) in order of first appearance
+    let mut reviewer_counts: Vec<(String, usize, usize)> = Vec::new();
+    let mut previous: HashMap<StoryKey, PreviousParagraph> = HashMap::new();
 
     for para in parsed_paragraphs {
         let base = track_changes::base_text(&para.segments);
         let revised = track_changes::revised_text(&para.segments);
-        let changes = track_changes::extract_changes(&para.segments);
+        let mut changes = track_changes::extract_changes(&para.segments);
+        flag_machine_generated(&mut changes, para);
+        if let Some(revision) = &para.format_change {
+            changes.push(track_changes::format_change(
+                revision,
+                &revised,
+                String::new(),
+                String::new(),
+            ));
+        }
+        changes.extend(para.table_revisions.iter().map(track_changes::table_change));
+        changes.extend(
+            para.layout_revisions
+                .iter()
+                .map(track_changes::layout_change),
+        );
 
         // ─── Determine paragraph status ────────────────────────────
-        let (paragraph_status, paragraph_change_author) = classify_paragraph(&para.segments);
+        let (mut paragraph_status, mut paragraph_change_author) =
+            classify_paragraph(&para.segments);
+
+        // ─── Splits and joins through the previous paragraph's mark ───
+        let story = (
+            para.kind,
+            para.note_id.clone(),
+            para.section,
+            para.header_footer_type,
+            para.part.clone(),
+        );
+        let (mut split_from, mut merged_into) = (None, None);
+        if let Some(prev) = previous.get(&story) {
+            // A wholly inserted or deleted paragraph's mark goes with it, so
+            // the paragraph after it takes over its relation instead
+            match &prev.mark {
+                Some(mark) if mark.adds() => {
+                    if prev.status == ParagraphStatus::WhollyInserted {
+                        split_from = prev.split_from;
+                    } else {
+                        split_from = Some(prev.index);
+                        changes.insert(0, track_changes::mark_change(mark, &prev.base_text, &base));
+                    }
+                }
+                Some(mark) => {
+                    if prev.status == ParagraphStatus::WhollyDeleted {
+                        merged_into = prev.merged_into;
+                    } else {
+                        merged_into = Some(prev.index);
+                        changes.insert(0, track_changes::mark_change(mark, &prev.base_text, &base));
+                    }
+                }
+                None => {}
+            }
+        }
+        // The second half of a split continues an existing paragraph even when
+        // all its text is new; it is only new when its own mark is inserted too
+        let own_mark_inserted = para.mark_revision.as_ref().is_some_and(|m| m.adds());
+        if split_from.is_some()
+            && paragraph_status == ParagraphStatus::WhollyInserted
+            && !own_mark_inserted
+        {
+            paragraph_status = ParagraphStatus::Normal;
+            paragraph_change_author = None;
+        }
+        previous.insert(
+            story,
+            PreviousParagraph {
+                index: para.index,
+                mark: para.mark_revision.clone(),
+                status: paragraph_status.clone(),
+                base_text: base.clone(),
+                split_from,
+                merged_into,
+            },
+        );
+
+        for change in &mut changes {
+            change.source_file = Some(file_name.to_string());
+        }
 
         // Build comments for this paragraph
         let mut para_comments = Vec::new();
@@ -29,44 +138,42 @@ pub fn build_paragraph_blocks(
                 if let Some(anchor) = para.comment_anchor_texts.get(cid) {
                     c.anchor_text = anchor.clone();
                 }
-                para_comments.push(c.clone());
-
-                let entry = reviewer_changes.entry(c.author.clone()).or_insert((0, 0));
-                entry.0 += 1;
+                c.source_file = Some(file_name.to_string());
+                reviewer_entry(&mut reviewer_counts, &c.author).1 += 1;
+                para_comments.push(c);
             }
         }
 
-        // Track change authors
-        let mut change_authors = std::collections::HashSet::new();
+        // Track change authors, in order of first appearance
+        let mut change_authors: Vec<String> = Vec::new();
         for change in &changes {
-            change_authors.insert(change.author.clone());
-            let entry = reviewer_changes.entry(change.author.clone()).or_insert((0, 0));
-            entry.1 += 1;
+            if !change_authors.contains(&change.author) {
+                change_authors.push(change.author.clone());
+            }
+            reviewer_entry(&mut reviewer_counts, &change.author).2 += 1;
         }
 
-        // Build reviewer versions
+        // Build reviewer versions — each applies only that reviewer's own changes
         let mut reviewer_versions = Vec::new();
         if base != revised {
-            let mut author_changes: HashMap<String, Vec<&TrackChange>> = HashMap::new();
-            for change in &changes {
-                author_changes
-                    .entry(change.author.clone())
-                    .or_default()
-                    .push(change);
-            }
-
             for author in &change_authors {
                 reviewer_versions.push(ReviewerVersion {
                     reviewer_name: author.clone(),
-                    resulting_text: revised.clone(),
+                    resulting_text: track_changes::author_text(&para.segments, author),
                 });
             }
         }
 
-        let has_conflicts = change_authors.len() > 1;
+        let conflicts = conflicts::detect(&base, &conflicts::edits(&para.segments));
 
         blocks.push(ParagraphBlock {
             index: para.index,
+            kind: para.kind,
+            note_id: para.note_id.clone(),
+            anchor_index: para.anchor_index,
+            section: para.section,
+            header_footer_type: para.header_footer_type,
+            table: para.table.clone(),
             base_text: base,
             revised_text: revised,
             paragraph_status,
@@ -74,29 +181,154 @@ pub fn build_paragraph_blocks(
             reviewer_versions,
             comments: para_comments,
             track_changes: changes,
-            has_conflicts,
+            conflicts,
+            source_file: Some(file_name.to_string()),
+            para_id: para.para_id.clone(),
+            text_id: para.text_id.clone(),
+            split_from,
+            merged_into,
+            fields: para
+                .fields
+                .iter()
+                .map(|f| Field {
+                    result: track_changes::revised_text(&para.segments[f.segments.clone()]),
+                    ..f.field.clone()
+                })
+                .collect(),
         });
     }
 
     // Build reviewer list
-    let colours = vec![
-        "#EF4444", "#3B82F6", "#10B981", "#F59E0B", "#8B5CF6", "#F97316", "#14B8A6", "#EC4899",
-    ];
-    let reviewers: Vec<Reviewer> = reviewer_changes
+    let reviewers: Vec<Reviewer> = reviewer_counts
         .into_iter()
         .enumerate()
-        .map(|(i, (name, (comment_count, change_count)))| Reviewer {
+        .map(|(i, (name, comment_count, change_count))| Reviewer {
             name,
             file_name: file_name.to_string(),
             comment_count,
             change_count,
-            colour: colours[i % colours.len()].to_string(),
+            colour: REVIEWER_COLOURS[i % REVIEWER_COLOURS.len()].to_string(),
         })
         .collect();
 
+    link_moves(&mut blocks);
     (blocks, reviewers)
 }
 
+/// Point each end of a tracked move at the first change of its other end: the
+/// change with the same move name from the same file. Ends whose counterpart
+/// is missing stay unlinked.
+pub fn link_moves(blocks: &mut [ParagraphBlock]) {
+    let mut first_end: HashMap<(Option<String>, String, MoveEnd), (usize, String)> = HashMap::new();
+    for block in blocks.iter() {
+        for change in &block.track_changes {
+            if let Some(link) = &change.move_link {
+                first_end
+                    .entry((change.source_file.clone(), link.name.clone(), link.end))
+                    .or_insert((block.index, change.id.clone()));
+            }
+        }
+    }
+
+    for block in blocks.iter_mut() {
+        for change in &mut block.track_changes {
+            let source_file = change.source_file.clone();
+            if let Some(link) = &mut change.move_link {
+                let other = match link.end {
+                    MoveEnd::From => MoveEnd::To,
+                    MoveEnd::To => MoveEnd::From,
+                };
+                if let Some((paragraph, id)) =
+                    first_end.get(&(source_file, link.name.clone(), other))
+                {
+                    link.other_paragraph = Some(*paragraph);
+                    link.other_change_id = Some(id.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Flag the changes of segments that lie inside the result of a field Word
+/// updates by itself, with the type of the outermost such field: the page
+/// numbers of a table of contents count as part of it. `changes` are the
+/// segments' changes as `extract_changes` returns them, in segment order.
+fn flag_machine_generated(changes: &mut [TrackChange], para: &ParsedParagraph) {
+    let mut changes = changes.iter_mut();
+    for (i, segment) in para.segments.iter().enumerate() {
+        let count = match segment {
+            Segment::Stable(_) => 0,
+            Segment::Nested { revisions, .. } => revisions.len(),
+            _ => 1,
+        };
+        let field = para.fields.iter().find(|f| {
+            f.segments.contains(&i) && GENERATED_FIELDS.contains(&f.field.field_type.as_str())
+        });
+        for change in changes.by_ref().take(count) {
+            change.machine_generated = field.map(|f| f.field.field_type.clone());
+        }
+    }
+}
+
+/// Remove changes flagged as machine-generated, and everything that only
+/// existed because of them: their reviewers' counts, a reviewer left with no
+/// changes or comments, the reviewer versions of a paragraph they no longer
+/// change, and their part in conflicts
+pub fn drop_machine_generated(result: &mut CollateResult) {
+    for block in &mut result.paragraphs {
+        // (author, id): ids are only unique within the file they came from
+        let mut dropped: Vec<(String, String)> = Vec::new();
+        block.track_changes.retain(|change| {
+            if change.machine_generated.is_none() {
+                return true;
+            }
+            if let Some(reviewer) = result
+                .reviewers
+                .iter_mut()
+                .find(|r| r.name == change.author)
+            {
+                reviewer.change_count = reviewer.change_count.saturating_sub(1);
+            }
+            dropped.push((change.author.clone(), change.id.clone()));
+            false
+        });
+        if dropped.is_empty() {
+            continue;
+        }
+
+        let changes = &block.track_changes;
+        block
+            .reviewer_versions
+            .retain(|v| changes.iter().any(|c| c.author == v.reviewer_name));
+        for conflict in &mut block.conflicts {
+            for edit in &mut conflict.edits {
+                let author = &edit.author;
+                edit.change_ids
+                    .retain(|id| !dropped.iter().any(|(a, i)| a == author && i == id));
+            }
+            conflict.edits.retain(|edit| !edit.change_ids.is_empty());
+        }
+        block.conflicts.retain(|conflict| conflict.edits.len() > 1);
+    }
+    result
+        .reviewers
+        .retain(|r| r.change_count > 0 || r.comment_count > 0);
+}
+
+fn reviewer_entry<'a>(
+    counts: &'a mut Vec<(String, usize, usize)>,
+    name: &str,
+) -> &'a mut (String, usize, usize) {
+    let pos = match counts.iter().position(|(n, _, _)| n == name) {
+        Some(pos) => pos,
+        None => {
+            counts.push((name.to_string(), 0, 0));
+            counts.len() - 1
+        }
+    };
+    &mut counts[pos]
+}
+
 /// Classify a paragraph based on its segments:
 /// - WhollyInserted: ALL text segments are Insertion (no Stable, no Deletion)
 /// - WhollyDeleted: ALL text segments are Deletion (no Stable, no Insertion)
@@ -112,19 +344,31 @@ fn classify_paragraph(segments: &[Segment]) -> (ParagraphStatus, Option<String>)
 
     for seg in segments {
         match seg {
-            Segment::Stable(t) => {
+            Segment::Stable(t) | Segment::FormatChange { text: t, .. } => {
                 if !t.trim().is_empty() {
                     has_stable = true;
                 }
             }
-            Segment::Insertion { author, .. } => {
+            Segment::Insertion { author, .. } | Segment::MoveTo { author, .. } => {
                 has_insertion = true;
                 ins_author = Some(author.clone());
             }
-            Segment::Deletion { author, .. } => {
+            Segment::Deletion { author, .. } | Segment::MoveFrom { author, .. } => {
                 has_deletion = true;
                 del_author = Some(author.clone());
             }
+            // Text added by its oldest revision counts as inserted, text
+            // removed by its newest as deleted
+            Segment::Nested { revisions, .. } => {
+                if let Some(first) = revisions.first().filter(|r| r.adds()) {
+                    has_insertion = true;
+                    ins_author = Some(first.author.clone());
+                }
+                if let Some(last) = revisions.last().filter(|r| !r.adds()) {
+                    has_deletion = true;
+                    del_author = Some(last.author.clone());
+                }
+            }
         }
     }
 
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use crate::align::{self, AlignKey};
+use crate::conflicts::{self, Edit};
+use crate::formats;
+use crate::markup;
+use crate::matcher::{self, REVIEWER_COLOURS};
+use crate::paragraphs::ParsedParagraph;
+use crate::pdf;
+use crate::types::{CollateResult, ParagraphBlock, Reviewer};
+
+/// A merged paragraph plus the edits gathered against its base text from every
+/// copy whose base text agrees with it, so conflicts can be found across copies
+struct MergedBlock {
+    /// Identity that survives reordering, used to re-point note anchors
+    id: usize,
+    /// Id of the block this one is anchored to (notes → referencing paragraph)
+    anchor: Option<usize>,
+    /// Ids of the blocks this one was split from or merged into
+    split_from: Option<usize>,
+    merged_into: Option<usize>,
+    block: ParagraphBlock,
+    key: AlignKey,
+    edits: Vec<Edit>,
+}
+
+/// Parse every file and merge the reviewer copies into the base document.
+///
+/// Each non-base file is aligned, in document order, against the paragraphs
+/// merged so far. A matched paragraph's comments, track changes and reviewer
+/// versions are added to its counterpart; an unmatched one is inserted between
+/// its neighbours. A marked-up PDF is not aligned but has each markup placed
+/// on the paragraph whose text it covers. Paragraphs are renumbered in merged
+/// order at the end.
+pub fn collate(files: &[(&str, &[u8])], base_name: &str) -> Result<CollateResult, String> {
+    let base_pos = files
+        .iter()
+        .position(|(name, _)| *name == base_name)
+        .ok_or_else(|| format!("Base document '{}' is not among the files", base_name))?;
+
+    // Base document first, then the others in the order given
+    let order = std::iter::once(base_pos).chain((0..files.len()).filter(|&i| i != base_pos));
+
+    let mut merged: Vec<MergedBlock> = Vec::new();
+    let mut reviewers: Vec<Reviewer> = Vec::new();
+    let mut document_title = None;
+    let mut warnings: Vec<String> = Vec::new();
+    let mut next_id = 0;
+
+    for i in order {
+        let (name, data) = files[i];
+        if i != base_pos && pdf::is_pdf(data) {
+            let markups = pdf::read_markups(data).map_err(|e| format!("{}: {}", name, e))?;
+            let base_texts: Vec<&str> = merged.iter().map(|m| m.block.base_text.as_str()).collect();
+            let anchored = markup::anchor(&base_texts, &markups.markups);
+            warnings.extend(
+                markups
+                    .warnings
+                    .iter()
+                    .chain(&anchored.warnings)
+                    .map(|w| format!("{}: {}", name, w)),
+            );
+
+            let (targets, paragraphs): (Vec<usize>, Vec<ParsedParagraph>) =
+                anchored.paragraphs.into_iter().unzip();
+            let (blocks, file_reviewers) =
+                matcher::build_paragraph_blocks(&paragraphs, &anchored.comments, name);
+            merge_reviewers(&mut reviewers, file_reviewers);
+            for ((target, block), para) in targets.into_iter().zip(blocks).zip(&paragraphs) {
+                let added = MergedBlock {
+                    id: next_id,
+                    anchor: None,
+                    split_from: None,
+                    merged_into: None,
+                    block,
+                    key: AlignKey::new(para),
+                    edits: conflicts::edits(&para.segments),
+                };
+                next_id += 1;
+                absorb(&mut merged[target], added);
+            }
+            continue;
+        }
+        let document = formats::read(data).map_err(|e| format!("{}: {}", name, e))?;
+        warnings.extend(document.warnings.iter().map(|w| format!("{}: {}", name, w)));
+        let (blocks, file_reviewers) =
+            matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, name);
+        merge_reviewers(&mut reviewers, file_reviewers);
+
+        let first_id = next_id;
+        next_id += blocks.len();
+        let incoming: Vec<MergedBlock> = blocks
+            .into_iter()
+            .zip(&document.paragraphs)
+            .enumerate()
+            .map(|(k, (block, para))| MergedBlock {
+                id: first_id + k,
+                anchor: block.anchor_index.map(|a| first_id + a),
+                split_from: block.split_from.map(|a| first_id + a),
+                merged_into: block.merged_into.map(|a| first_id + a),
+                block,
+                key: AlignKey::new(para),
+                edits: conflicts::edits(&para.segments),
+            })
+            .collect();
+
+        if i == base_pos {
+            document_title = document.title;
+            merged = incoming;
+            continue;
+        }
+
+        let merged_keys: Vec<AlignKey> = merged.iter().map(|m| m.key.clone()).collect();
+        let incoming_keys: Vec<AlignKey> = incoming.iter().map(|m| m.key.clone()).collect();
+        let pairs = align::align(&merged_keys, &incoming_keys);
+
+        let mut previous: Vec<Option<MergedBlock>> = merged.into_iter().map(Some).collect();
+        let mut incoming: Vec<Option<MergedBlock>> = incoming.into_iter().map(Some).collect();
+        let mut absorbed_into: HashMap<usize, usize> = HashMap::new();
+        merged = Vec::with_capacity(previous.len());
+        for pair in pairs {
+            let existing = pair.base.and_then(|b| previous[b].take());
+            let added = pair.other.and_then(|o| incoming[o].take());
+            match (existing, added) {
+                (Some(mut target), Some(added)) => {
+                    absorbed_into.insert(added.id, target.id);
+                    absorb(&mut target, added);
+                    merged.push(target);
+                }
+                (Some(block), None) | (None, Some(block)) => merged.push(block),
+                (None, None) => {}
+            }
+        }
+
+        // Notes kept from this copy now point at the merged paragraph their
+        // reference was folded into, and split or joined paragraphs at the
+        // merged paragraph their other half was folded into
+        for m in &mut merged {
+            for link in [&mut m.anchor, &mut m.split_from, &mut m.merged_into] {
+                if let Some(target) = link.and_then(|a| absorbed_into.get(&a)) {
+                    *link = Some(*target);
+                }
+            }
+        }
+    }
+
+    for (i, reviewer) in reviewers.iter_mut().enumerate() {
+        reviewer.colour = REVIEWER_COLOURS[i % REVIEWER_COLOURS.len()].to_string();
+    }
+
+    let final_index: HashMap<usize, usize> =
+        merged.iter().enumerate().map(|(index, m)| (m.id, index)).collect();
+
+    let mut paragraphs: Vec<ParagraphBlock> = merged
+        .into_iter()
+        .enumerate()
+        .map(|(index, m)| ParagraphBlock {
+            index,
+            anchor_index: m.anchor.and_then(|a| final_index.get(&a).copied()),
+            split_from: m.split_from.and_then(|a| final_index.get(&a).copied()),
+            merged_into: m.merged_into.and_then(|a| final_index.get(&a).copied()),
+            ..m.block
+        })
+        .collect();
+    // Moves were linked by per-file indices; relink in merged order
+    matcher::link_moves(&mut paragraphs);
+
+    Ok(CollateResult {
+        paragraphs,
+        reviewers,
+        document_title,
+        error: None,
+        warnings,
+    })
+}
+
+/// Add a matched reviewer paragraph's items, and any split or join it reports,
+/// to the merged paragraph. Edits are only comparable when both copies agree
+/// on the base text.
+fn absorb(target: &mut MergedBlock, added: MergedBlock) {
+    target.split_from = target.split_from.or(added.split_from);
+    target.merged_into = target.merged_into.or(added.merged_into);
+    let block = added.block;
+    target.block.comments.extend(block.comments);
+    target.block.track_changes.extend(block.track_changes);
+    target
+        .block
+        .reviewer_versions
+        .extend(block.reviewer_versions);
+
+    if block.base_text == target.block.base_text {
+        target.edits.extend(added.edits);
+        target.block.conflicts = conflicts::detect(&target.block.base_text, &target.edits);
+    }
+}
+
+/// Fold one file's reviewers into the combined list, summing counts by name.
+/// A reviewer keeps the file name where they were first seen.
+fn merge_reviewers(reviewers: &mut Vec<Reviewer>, file_reviewers: Vec<Reviewer>) {
+    for r in file_reviewers {
+        match reviewers
+            .iter_mut()
+            .find(|existing| existing.name == r.name)
+        {
+            Some(existing) => {
+                existing.comment_count += r.comment_count;
+                existing.change_count += r.change_count;
+            }
+            None => reviewers.push(r),
+        }
+    }
+}
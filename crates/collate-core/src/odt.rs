@@ -0,0 +1,667 @@
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::NsReader;
+
+use crate::docx;
+use crate::matcher;
+use crate::package::Package;
+use crate::paragraphs::{self, ParsedDocument, ParsedParagraph};
+use crate::track_changes::Segment;
+use crate::types::{BlockKind, CollateResult, Comment, TableCoordinates};
+use crate::xml::{self, local_name, Ns};
+
+const ODT_MIME_TYPES: [&str; 2] = [
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.text-template",
+];
+
+/// A ZIP whose `mimetype` entry names an OpenDocument text document
+pub fn is_odt(data: &[u8]) -> bool {
+    match Package::open(data) {
+        Ok(mut package) => package
+            .read("mimetype")
+            .is_some_and(|m| ODT_MIME_TYPES.contains(&m.trim())),
+        Err(_) => false,
+    }
+}
+
+/// Parse an .odt file from raw bytes
+pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
+    let document = read(data)?;
+    let (paragraph_blocks, reviewers) =
+        matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, file_name);
+
+    Ok(CollateResult {
+        paragraphs: paragraph_blocks,
+        reviewers,
+        document_title: document.title,
+        error: None,
+        warnings: document.warnings,
+    })
+}
+
+/// Read an .odt package into parsed paragraphs, comments and title.
+///
+/// Insertions are the text between `text:change-start` and `text:change-end`;
+/// a deletion is a `text:change` point whose removed text lives in the
+/// `text:changed-region`. Annotations become comments anchored on the text up to
+/// their `office:annotation-end`. Notes are appended after the body, linked to
+/// the paragraph holding their citation, as for .docx footnotes.
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    let mut package = Package::open(data)?;
+    let content_xml = package
+        .read("content.xml")
+        .ok_or_else(|| "content.xml not found — is this a valid .odt file?".to_string())?;
+    let title = package
+        .read("meta.xml")
+        .and_then(|xml| docx::extract_title(&xml));
+
+    let mut body = Body {
+        reader: NsReader::from_str(&content_xml),
+        changes: tracked_changes(&content_xml),
+        comments: HashMap::new(),
+        notes: Vec::new(),
+        open_insertions: Vec::new(),
+        active_comments: Vec::new(),
+        comment_text: HashMap::new(),
+        tables: 0,
+    };
+    let mut paragraphs = body.story(BlockKind::Body);
+
+    // Notes follow the body; their anchors already point at body indices
+    for mut note in std::mem::take(&mut body.notes) {
+        note.index = paragraphs.len();
+        paragraphs.push(note);
+    }
+    paragraphs::label_columns(&mut paragraphs);
+
+    let mut warnings = Vec::new();
+    let format_changes = body
+        .changes
+        .values()
+        .filter(|c| c.kind == ChangeKind::Other)
+        .count();
+    if format_changes > 0 {
+        warnings.push(format!(
+            "{} formatting change(s) in this .odt file are not read; only insertions and deletions are",
+            format_changes
+        ));
+    }
+
+    Ok(ParsedDocument {
+        paragraphs,
+        comments: body.comments,
+        title,
+        warnings,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChangeKind {
+    Insertion,
+    Deletion,
+    /// Attribute and format changes: the text itself is unchanged
+    Other,
+}
+
+/// One `text:changed-region`
+#[derive(Debug, Clone)]
+struct TrackedChange {
+    kind: ChangeKind,
+    author: String,
+    date: Option<String>,
+    /// For deletions: the removed text, paragraphs joined with a space
+    deleted_text: String,
+}
+
+/// Collect `text:tracked-changes`, keyed by change id
+fn tracked_changes(content_xml: &str) -> HashMap<String, TrackedChange> {
+    let mut changes = HashMap::new();
+    let mut reader = NsReader::from_str(content_xml);
+    let mut buf = Vec::new();
+
+    let mut current: Option<(String, TrackedChange)> = None;
+    let mut field: Option<&[u8]> = None;
+    let mut deleted_paragraphs: Vec<String> = Vec::new();
+    let mut in_deleted_paragraph = false;
+    // office:change-info may carry its own text:p (a note on the change)
+    let mut in_change_info = false;
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) => match (ns, e.local_name().as_ref()) {
+                (Ns::OdfText, b"changed-region") => {
+                    let id = attribute(e, b"id").unwrap_or_default();
+                    current = Some((
+                        id,
+                        TrackedChange {
+                            kind: ChangeKind::Other,
+                            author: String::new(),
+                            date: None,
+                            deleted_text: String::new(),
+                        },
+                    ));
+                    deleted_paragraphs.clear();
+                }
+                (Ns::OdfText, b"insertion") => {
+                    if let Some((_, change)) = current.as_mut() {
+                        change.kind = ChangeKind::Insertion;
+                    }
+                }
+                (Ns::OdfText, b"deletion") => {
+                    if let Some((_, change)) = current.as_mut() {
+                        change.kind = ChangeKind::Deletion;
+                    }
+                }
+                (Ns::OdfOffice, b"change-info") => in_change_info = true,
+                (Ns::DublinCore, b"creator") => field = Some(b"creator"),
+                (Ns::DublinCore, b"date") => field = Some(b"date"),
+                (Ns::OdfText, b"p" | b"h") if current.is_some() && !in_change_info => {
+                    in_deleted_paragraph = true;
+                    deleted_paragraphs.push(String::new());
+                }
+                _ => {}
+            },
+            Event::Empty(ref e) if in_deleted_paragraph => {
+                if let Some(text) = deleted_paragraphs.last_mut() {
+                    push_spacing(text, ns, e);
+                }
+            }
+            Event::Text(ref e) => {
+                let text = e.unescape().map(|t| t.to_string()).unwrap_or_default();
+                match (field, current.as_mut()) {
+                    (Some(b"creator"), Some((_, change))) => change.author.push_str(text.trim()),
+                    (Some(_), Some((_, change))) => change.date = Some(text.trim().to_string()),
+                    (None, Some(_)) if in_deleted_paragraph => {
+                        if let Some(paragraph) = deleted_paragraphs.last_mut() {
+                            paragraph.push_str(&collapse_whitespace(&text));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref e) => match (ns, e.local_name().as_ref()) {
+                (Ns::OdfOffice, b"change-info") => in_change_info = false,
+                (Ns::DublinCore, b"creator" | b"date") => field = None,
+                (Ns::OdfText, b"p" | b"h") => in_deleted_paragraph = false,
+                (Ns::OdfText, b"changed-region") => {
+                    if let Some((id, mut change)) = current.take() {
+                        change.deleted_text = deleted_paragraphs
+                            .iter()
+                            .map(|p| p.as_str())
+                            .filter(|p| !p.trim().is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        changes.insert(id, change);
+                    }
+                }
+                // Everything after the change list is document content
+                (Ns::OdfText, b"tracked-changes") => break,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    changes
+}
+
+/// Position inside one open `table:table`
+struct TableCursor {
+    index: usize,
+    row: Option<usize>,
+    column: Option<usize>,
+    in_header_rows: bool,
+}
+
+/// Walks `office:text`, carrying the state that crosses paragraph boundaries
+struct Body<'x> {
+    reader: NsReader<&'x [u8]>,
+    changes: HashMap<String, TrackedChange>,
+    comments: HashMap<String, Comment>,
+    /// Footnote and endnote paragraphs, anchored to body paragraph indices
+    notes: Vec<ParsedParagraph>,
+    /// Insertions opened by `text:change-start` and not yet closed
+    open_insertions: Vec<String>,
+    /// Annotations whose anchored range is still open
+    active_comments: Vec<String>,
+    comment_text: HashMap<String, String>,
+    tables: usize,
+}
+
+impl Body<'_> {
+    /// Read paragraphs until the end of the document, or until the element
+    /// that started this story (a text box or note body) closes. Text boxes
+    /// follow their host paragraph; notes are collected separately.
+    fn story(&mut self, kind: BlockKind) -> Vec<ParsedParagraph> {
+        let mut paragraphs: Vec<ParsedParagraph> = Vec::new();
+        let mut buf = Vec::new();
+        let mut depth: usize = 0;
+
+        let mut in_paragraph = false;
+        let mut paragraph_depth: usize = 0;
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut comment_ids: Vec<String> = Vec::new();
+        let mut comment_anchor_texts: HashMap<String, String> = HashMap::new();
+        let mut text_boxes: Vec<ParsedParagraph> = Vec::new();
+        let mut pending_notes: Vec<ParsedParagraph> = Vec::new();
+        let mut tables: Vec<TableCursor> = Vec::new();
+        let mut table: Option<TableCoordinates> = None;
+
+        while let Ok((resolved, event)) = self.reader.read_resolved_event_into(&mut buf) {
+            let (ns, event) = (xml::ns(&resolved), event.into_owned());
+            match event {
+                Event::Start(ref e) => {
+                    depth += 1;
+                    match (ns, e.local_name().as_ref()) {
+                        (Ns::OdfText, b"tracked-changes")
+                        | (Ns::OdfText, b"note-citation")
+                        | (Ns::OdfOffice, b"forms") => {
+                            let _ = self.reader.read_to_end_into(e.name(), &mut Vec::new());
+                            depth -= 1;
+                        }
+                        (Ns::OdfTable, b"table") => {
+                            tables.push(TableCursor {
+                                index: self.tables,
+                                row: None,
+                                column: None,
+                                in_header_rows: false,
+                            });
+                            self.tables += 1;
+                        }
+                        (Ns::OdfTable, b"table-header-rows") => {
+                            if let Some(t) = tables.last_mut() {
+                                t.in_header_rows = true;
+                            }
+                        }
+                        (Ns::OdfTable, b"table-row") => {
+                            if let Some(t) = tables.last_mut() {
+                                t.row = Some(t.row.map_or(0, |r| r + 1));
+                                t.column = None;
+                            }
+                        }
+                        (Ns::OdfTable, b"table-cell" | b"covered-table-cell") => {
+                            if let Some(t) = tables.last_mut() {
+                                t.column = Some(t.column.map_or(0, |c| c + 1));
+                            }
+                        }
+                        (Ns::OdfText, b"p" | b"h") if !in_paragraph => {
+                            in_paragraph = true;
+                            paragraph_depth = depth;
+                            segments.clear();
+                            comment_ids.clear();
+                            comment_anchor_texts.clear();
+                            text_boxes.clear();
+                            pending_notes.clear();
+                            table = tables.last().map(|t| TableCoordinates {
+                                table_index: t.index,
+                                row: t.row.unwrap_or(0),
+                                column: t.column.unwrap_or(0),
+                                is_header: t.in_header_rows,
+                                column_header: None,
+                            });
+                        }
+                        (Ns::OdfDraw, b"text-box") => {
+                            text_boxes.extend(self.story(BlockKind::TextBox));
+                            depth -= 1;
+                        }
+                        (Ns::OdfText, b"note") if in_paragraph => {
+                            let note_kind = match attribute(e, b"note-class").as_deref() {
+                                Some("endnote") => BlockKind::Endnote,
+                                _ => BlockKind::Footnote,
+                            };
+                            let note_id = attribute(e, b"id");
+                            for mut note in self.note(note_kind) {
+                                note.note_id = note_id.clone();
+                                pending_notes.push(note);
+                            }
+                            depth -= 1;
+                        }
+                        (Ns::OdfOffice, b"annotation") if in_paragraph => {
+                            // Only named annotations span a range up to their annotation-end
+                            let ranged = attribute(e, b"name").is_some();
+                            let id = self.annotation(e);
+                            comment_ids.push(id.clone());
+                            comment_anchor_texts.insert(id.clone(), String::new());
+                            if ranged {
+                                self.active_comments.push(id.clone());
+                                self.comment_text.insert(id, String::new());
+                            }
+                            depth -= 1;
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Empty(ref e) => match (ns, e.local_name().as_ref()) {
+                    (Ns::OdfText, b"change-start") => {
+                        if let Some(id) = attribute(e, b"change-id") {
+                            if self.change_kind(&id) == Some(ChangeKind::Insertion) {
+                                self.open_insertions.push(id);
+                            }
+                        }
+                    }
+                    (Ns::OdfText, b"change-end") => {
+                        if let Some(id) = attribute(e, b"change-id") {
+                            self.open_insertions.retain(|open| *open != id);
+                        }
+                    }
+                    (Ns::OdfText, b"change") if in_paragraph => {
+                        let deletion = attribute(e, b"change-id").and_then(|id| {
+                            self.changes
+                                .get(&id)
+                                .filter(|c| c.kind == ChangeKind::Deletion)
+                                .map(|c| (id, c.clone()))
+                        });
+                        if let Some((id, change)) = deletion {
+                            if !change.deleted_text.is_empty() {
+                                segments.push(Segment::Deletion {
+                                    id,
+                                    author: change.author,
+                                    date: change.date,
+                                    text: change.deleted_text,
+                                });
+                            }
+                        }
+                    }
+                    (Ns::OdfOffice, b"annotation-end") => {
+                        if let Some(name) = attribute(e, b"name") {
+                            self.close_comment(&name, &mut comment_anchor_texts);
+                        }
+                    }
+                    (Ns::OdfOffice, b"annotation") if in_paragraph => {
+                        // An empty annotation has no body; still record it
+                        let id = self.annotation_info(e, String::new());
+                        comment_ids.push(id.clone());
+                        comment_anchor_texts.insert(id, String::new());
+                    }
+                    _ if in_paragraph => {
+                        let mut spacing = String::new();
+                        push_spacing(&mut spacing, ns, e);
+                        if !spacing.is_empty() {
+                            self.push_text(&mut segments, &spacing);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(ref e) if in_paragraph => {
+                    if let Ok(txt) = e.unescape() {
+                        let mut text = collapse_whitespace(&txt);
+                        if segments.is_empty() {
+                            text = text.trim_start().to_string();
+                        }
+                        if !text.is_empty() {
+                            self.push_text(&mut segments, &text);
+                        }
+                    }
+                }
+                Event::End(ref e) => {
+                    depth = depth.saturating_sub(1);
+                    match (ns, e.local_name().as_ref()) {
+                        (Ns::OdfText, b"p" | b"h")
+                            if in_paragraph && depth + 1 == paragraph_depth =>
+                        {
+                            // Anchors for comments still open continue in the next paragraph
+                            for id in &self.active_comments {
+                                if let Some(text) = self.comment_text.get(id) {
+                                    if comment_ids.contains(id) {
+                                        comment_anchor_texts.insert(id.clone(), text.clone());
+                                    }
+                                }
+                            }
+
+                            let has_content = segments.iter().any(Segment::has_content);
+                            let mut host = None;
+                            if has_content {
+                                if let Some(Segment::Stable(t)) = segments.last_mut() {
+                                    let trimmed = t.trim_end().len();
+                                    t.truncate(trimmed);
+                                }
+                                host = Some(paragraphs.len());
+                                paragraphs.push(ParsedParagraph {
+                                    index: paragraphs.len(),
+                                    segments: std::mem::take(&mut segments),
+                                    comment_ids: std::mem::take(&mut comment_ids),
+                                    comment_anchor_texts: std::mem::take(&mut comment_anchor_texts),
+                                    kind,
+                                    table: table.take(),
+                                    ..Default::default()
+                                });
+                            }
+
+                            let offset = paragraphs.len();
+                            for mut text_box in text_boxes.drain(..) {
+                                text_box.index = paragraphs.len();
+                                text_box.anchor_index = match text_box.anchor_index {
+                                    Some(a) => Some(a + offset),
+                                    None => host,
+                                };
+                                paragraphs.push(text_box);
+                            }
+                            for mut note in pending_notes.drain(..) {
+                                note.anchor_index = host;
+                                self.notes.push(note);
+                            }
+                            in_paragraph = false;
+                        }
+                        (Ns::OdfTable, b"table") => {
+                            tables.pop();
+                        }
+                        (Ns::OdfTable, b"table-header-rows") => {
+                            if let Some(t) = tables.last_mut() {
+                                t.in_header_rows = false;
+                            }
+                        }
+                        (Ns::OdfDraw, b"text-box") | (Ns::OdfText, b"note-body") => break,
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        paragraphs
+    }
+
+    /// Read a `text:note` up to its end, returning the paragraphs of its body
+    fn note(&mut self, kind: BlockKind) -> Vec<ParsedParagraph> {
+        let mut buf = Vec::new();
+        let mut paragraphs = Vec::new();
+        while let Ok((resolved, event)) = self.reader.read_resolved_event_into(&mut buf) {
+            let (ns, event) = (xml::ns(&resolved), event.into_owned());
+            match event {
+                Event::Start(ref e) if ns == Ns::OdfText => match e.local_name().as_ref() {
+                    b"note-citation" => {
+                        let _ = self.reader.read_to_end_into(e.name(), &mut Vec::new());
+                    }
+                    b"note-body" => paragraphs = self.story(kind),
+                    _ => {}
+                },
+                Event::End(ref e) if ns == Ns::OdfText && e.local_name().as_ref() == b"note" => {
+                    break
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        paragraphs
+    }
+
+    /// Read an `office:annotation` up to its end and record it as a comment,
+    /// returning the comment id
+    fn annotation(&mut self, start: &BytesStart) -> String {
+        let mut buf = Vec::new();
+        let mut author = String::new();
+        let mut date = None;
+        let mut initials = None;
+        let mut paragraphs: Vec<String> = Vec::new();
+        let mut field: Option<&[u8]> = None;
+
+        while let Ok((resolved, event)) = self.reader.read_resolved_event_into(&mut buf) {
+            let (ns, event) = (xml::ns(&resolved), event.into_owned());
+            match event {
+                Event::Start(ref e) => match (ns, e.local_name().as_ref()) {
+                    (Ns::DublinCore, b"creator") => field = Some(b"creator"),
+                    (Ns::DublinCore, b"date") => field = Some(b"date"),
+                    (Ns::OdfMeta, b"creator-initials") => field = Some(b"initials"),
+                    (Ns::OdfText, b"p" | b"h") => paragraphs.push(String::new()),
+                    _ => {}
+                },
+                Event::Empty(ref e) => {
+                    if let Some(p) = paragraphs.last_mut() {
+                        push_spacing(p, ns, e);
+                    }
+                }
+                Event::Text(ref e) => {
+                    let text = e.unescape().map(|t| t.to_string()).unwrap_or_default();
+                    match field {
+                        Some(b"creator") => author.push_str(text.trim()),
+                        Some(b"date") => date = Some(text.trim().to_string()),
+                        Some(_) => initials = Some(text.trim().to_string()),
+                        None => {
+                            if let Some(p) = paragraphs.last_mut() {
+                                p.push_str(&collapse_whitespace(&text));
+                            }
+                        }
+                    }
+                }
+                Event::End(ref e) => match (ns, e.local_name().as_ref()) {
+                    (Ns::OdfOffice, b"annotation") => break,
+                    (Ns::DublinCore, _) | (Ns::OdfMeta, _) => field = None,
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        let text = paragraphs
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let id = self.annotation_info(start, text);
+        if let Some(comment) = self.comments.get_mut(&id) {
+            comment.author = author;
+            comment.date = date;
+            comment.initials = initials;
+        }
+        id
+    }
+
+    /// Register a comment for an annotation element, named after its
+    /// `office:name` (which `office:annotation-end` refers to) when it has one
+    fn annotation_info(&mut self, start: &BytesStart, text: String) -> String {
+        let id = attribute(start, b"name")
+            .unwrap_or_else(|| format!("annotation-{}", self.comments.len() + 1));
+        self.comments.insert(
+            id.clone(),
+            Comment {
+                id: id.clone(),
+                author: String::new(),
+                date: None,
+                text,
+                anchor_text: String::new(),
+                initials: None,
+                source_file: None,
+            },
+        );
+        id
+    }
+
+    fn close_comment(&mut self, id: &str, anchors: &mut HashMap<String, String>) {
+        self.active_comments.retain(|open| open != id);
+        if let Some(text) = self.comment_text.remove(id) {
+            if anchors.contains_key(id) {
+                anchors.insert(id.to_string(), text);
+            }
+        }
+    }
+
+    fn change_kind(&self, id: &str) -> Option<ChangeKind> {
+        self.changes.get(id).map(|c| c.kind)
+    }
+
+    /// Append text to the paragraph as stable text or as part of the innermost
+    /// open insertion, and to every open annotation range
+    fn push_text(&mut self, segments: &mut Vec<Segment>, text: &str) {
+        for id in &self.active_comments {
+            if let Some(accum) = self.comment_text.get_mut(id) {
+                accum.push_str(text);
+            }
+        }
+
+        let insertion = self
+            .open_insertions
+            .last()
+            .and_then(|id| self.changes.get(id).map(|c| (id, c)));
+        match (insertion, segments.last_mut()) {
+            (None, Some(Segment::Stable(s))) => s.push_str(text),
+            (None, _) => segments.push(Segment::Stable(text.to_string())),
+            (
+                Some((id, _)),
+                Some(Segment::Insertion {
+                    id: last, text: t, ..
+                }),
+            ) if last == id => t.push_str(text),
+            (Some((id, change)), _) => segments.push(Segment::Insertion {
+                id: id.clone(),
+                author: change.author.clone(),
+                date: change.date.clone(),
+                text: text.to_string(),
+            }),
+        }
+    }
+}
+
+/// Most spaces one `text:s` yields; the count comes from the file
+const MAX_SPACES: usize = 1024;
+
+/// Text produced by ODF's spacing elements (`text:s`, `text:tab`, `text:line-break`)
+fn push_spacing(text: &mut String, ns: Ns, e: &BytesStart) {
+    if ns != Ns::OdfText {
+        return;
+    }
+    match e.local_name().as_ref() {
+        b"s" => {
+            let count: usize = attribute(e, b"c").and_then(|c| c.parse().ok()).unwrap_or(1);
+            text.push_str(&" ".repeat(count.min(MAX_SPACES)));
+        }
+        b"tab" => text.push('\t'),
+        b"line-break" => text.push('\n'),
+        _ => {}
+    }
+}
+
+/// ODF collapses runs of white space in paragraph content to a single space
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_space {
+                result.push(' ');
+            }
+            last_space = true;
+        } else {
+            result.push(c);
+            last_space = false;
+        }
+    }
+    result
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == name)
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
@@ -0,0 +1,354 @@
+use std::io::{Cursor, Read};
+
+use quick_xml::events::Event;
+use quick_xml::NsReader;
+use zip::ZipArchive;
+
+use crate::xml::{self, Ns};
+
+/// Content types a WordprocessingML main document part may have
+/// (.docx, .dotx, .docm, .dotm)
+const MAIN_DOCUMENT_TYPES: [&str; 4] = [
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.template.main+xml",
+    "application/vnd.ms-word.document.macroEnabled.main+xml",
+    "application/vnd.ms-word.template.macroEnabledTemplate.main+xml",
+];
+
+/// An Open Packaging Conventions package: the ZIP container of a .docx, or
+/// the same parts written out as a single Flat OPC XML file
+pub enum Package<'a> {
+    Zip(ZipArchive<Cursor<&'a [u8]>>),
+    Flat(Vec<FlatPart>),
+}
+
+/// One `pkg:part` of a Flat OPC file. Only XML parts are kept.
+pub struct FlatPart {
+    /// Part name without the leading `/`
+    name: String,
+    content_type: String,
+    xml: String,
+}
+
+/// One `Relationship` from a .rels file, with its target resolved to a part
+/// name inside the package (no leading `/`)
+#[derive(Debug, Clone)]
+pub struct Relationship {
+    pub id: String,
+    pub rel_type: String,
+    pub target: String,
+}
+
+impl Relationship {
+    /// Short officeDocument relationship type (`comments`, `header`, ...),
+    /// Transitional or Strict
+    pub fn short_type(&self) -> Option<&str> {
+        xml::relationship_type(&self.rel_type)
+    }
+}
+
+/// The per-part overrides of `[Content_Types].xml`
+#[derive(Debug, Default)]
+pub struct ContentTypes {
+    overrides: Vec<(String, String)>,
+}
+
+impl ContentTypes {
+    /// First overridden part with the given content type
+    pub fn part_of_type(&self, content_type: &str) -> Option<&str> {
+        self.overrides
+            .iter()
+            .find(|(_, ct)| ct == content_type)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl<'a> Package<'a> {
+    /// Open a ZIP package, or a Flat OPC document (`pkg:package`) when the
+    /// data is XML
+    pub fn open(data: &'a [u8]) -> Result<Self, String> {
+        if let Some(parts) = flat_parts(data) {
+            return Ok(Package::Flat(parts));
+        }
+        let archive = ZipArchive::new(Cursor::new(data))
+            .map_err(|e| format!("Failed to open ZIP archive: {}", e))?;
+        Ok(Package::Zip(archive))
+    }
+
+    /// Read a part as text. `part` is a part name without the leading `/`.
+    pub fn read(&mut self, part: &str) -> Option<String> {
+        match self {
+            Package::Zip(archive) => {
+                let mut file = archive.by_name(part).ok()?;
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).ok()?;
+                Some(contents)
+            }
+            Package::Flat(parts) => parts.iter().find(|p| p.name == part).map(|p| p.xml.clone()),
+        }
+    }
+
+    pub fn contains(&self, part: &str) -> bool {
+        match self {
+            Package::Zip(archive) => archive.index_for_name(part).is_some(),
+            Package::Flat(parts) => parts.iter().any(|p| p.name == part),
+        }
+    }
+
+    /// Relationships of a part (`""` for the package itself), read from the
+    /// matching `_rels/*.rels` file. Missing .rels files give no relationships.
+    pub fn relationships(&mut self, part: &str) -> Vec<Relationship> {
+        let (dir, name) = split_part(part);
+        let rels_path = if dir.is_empty() {
+            format!("_rels/{}.rels", name)
+        } else {
+            format!("{}/_rels/{}.rels", dir, name)
+        };
+        self.read(&rels_path)
+            .map(|xml| parse_relationships(&xml, dir))
+            .unwrap_or_default()
+    }
+
+    /// Content type overrides, from `[Content_Types].xml` or, for Flat OPC,
+    /// from each part's `pkg:contentType`
+    pub fn content_types(&mut self) -> ContentTypes {
+        match self {
+            Package::Zip(_) => self
+                .read("[Content_Types].xml")
+                .map(|xml| parse_content_types(&xml))
+                .unwrap_or_default(),
+            Package::Flat(parts) => ContentTypes {
+                overrides: parts
+                    .iter()
+                    .map(|p| (p.name.clone(), p.content_type.clone()))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Locate the main document part through the package's officeDocument
+    /// relationship. Packages without `_rels/.rels` fall back to the content
+    /// type overrides, then to `word/document.xml`.
+    pub fn main_document(&mut self, content_types: &ContentTypes) -> Result<String, String> {
+        if self.contains("_rels/.rels") {
+            let relationships = self.relationships("");
+            let rel = relationships
+                .iter()
+                .find(|r| r.short_type() == Some("officeDocument"))
+                .ok_or_else(|| {
+                    "_rels/.rels has no officeDocument relationship — is this a valid .docx file?"
+                        .to_string()
+                })?;
+            if !self.contains(&rel.target) {
+                return Err(format!(
+                    "The officeDocument relationship '{}' in _rels/.rels points to '{}', which is not in the package",
+                    rel.id, rel.target
+                ));
+            }
+            return Ok(rel.target.clone());
+        }
+
+        if let Some(part) = MAIN_DOCUMENT_TYPES
+            .iter()
+            .find_map(|ct| content_types.part_of_type(ct))
+            .filter(|part| self.contains(part))
+        {
+            return Ok(part.to_string());
+        }
+        if self.contains("word/document.xml") {
+            return Ok("word/document.xml".to_string());
+        }
+        Err("No officeDocument relationship (_rels/.rels) and no word/document.xml — is this a valid .docx file?".to_string())
+    }
+
+    /// Find the target of the first relationship of the given type, falling
+    /// back to the content type overrides and then to `fallback`, for packages
+    /// written without relationships
+    pub fn related_part(
+        &self,
+        relationships: &[Relationship],
+        short_type: &str,
+        content_type: &str,
+        content_types: &ContentTypes,
+        fallback: &str,
+    ) -> Option<String> {
+        if let Some(rel) = relationships
+            .iter()
+            .find(|r| r.short_type() == Some(short_type))
+        {
+            return Some(rel.target.clone());
+        }
+        if let Some(part) = content_types.part_of_type(content_type) {
+            return Some(part.to_string());
+        }
+        Some(fallback.to_string()).filter(|part| self.contains(part))
+    }
+}
+
+/// Split a Flat OPC document into its XML parts. Returns `None` when the data
+/// is not a `pkg:package` document.
+fn flat_parts(data: &[u8]) -> Option<Vec<FlatPart>> {
+    let text = std::str::from_utf8(data).ok()?;
+    let text = text.trim_start_matches('\u{feff}');
+    if !text.trim_start().starts_with('<') {
+        return None;
+    }
+
+    let mut reader = NsReader::from_str(text);
+    let mut buf = Vec::new();
+    let mut parts = Vec::new();
+    let mut is_package = false;
+    let mut current: Option<(String, String)> = None;
+
+    loop {
+        let (ns, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok((resolved, event)) => (xml::ns(&resolved), event.into_owned()),
+            Err(_) => return None,
+        };
+        match event {
+            Event::Start(ref e) if ns == Ns::FlatPackage => match e.local_name().as_ref() {
+                b"package" => is_package = true,
+                b"part" => {
+                    let mut name = String::new();
+                    let mut content_type = String::new();
+                    for attr in e.attributes().flatten() {
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match xml::local_name(attr.key.as_ref()) {
+                            b"name" => name = val.trim_start_matches('/').to_string(),
+                            b"contentType" => content_type = val,
+                            _ => {}
+                        }
+                    }
+                    current = Some((name, content_type));
+                }
+                b"xmlData" => {
+                    let span = reader.read_to_end(e.name()).ok()?;
+                    if let Some((name, content_type)) = current.take() {
+                        parts.push(FlatPart {
+                            name,
+                            content_type,
+                            xml: text[span.start as usize..span.end as usize].to_string(),
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Event::Start(_) if !is_package => return None,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Some(parts).filter(|_| is_package)
+}
+
+/// Part name of `file_name` in the same directory as `part`
+pub fn sibling(part: &str, file_name: &str) -> String {
+    match split_part(part).0 {
+        "" => file_name.to_string(),
+        dir => format!("{}/{}", dir, file_name),
+    }
+}
+
+fn split_part(part: &str) -> (&str, &str) {
+    part.rsplit_once('/').unwrap_or(("", part))
+}
+
+/// Parse a .rels file. Targets are resolved against `base_dir`, the directory
+/// of the source part; external targets (hyperlinks) are dropped.
+fn parse_relationships(rels_xml: &str, base_dir: &str) -> Vec<Relationship> {
+    let mut relationships = Vec::new();
+    let mut reader = NsReader::from_str(rels_xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e)
+                if ns == Ns::PackageRelationships
+                    && e.local_name().as_ref() == b"Relationship" =>
+            {
+                let (mut id, mut rel_type, mut target) = (String::new(), String::new(), None);
+                let mut external = false;
+                for attr in e.attributes().flatten() {
+                    let val = String::from_utf8_lossy(&attr.value).to_string();
+                    match attr.key.as_ref() {
+                        b"Id" => id = val,
+                        b"Type" => rel_type = val,
+                        b"Target" => target = Some(val),
+                        b"TargetMode" => external = val == "External",
+                        _ => {}
+                    }
+                }
+                if let (Some(target), false) = (target, external) {
+                    relationships.push(Relationship {
+                        id,
+                        rel_type,
+                        target: resolve_target(base_dir, &target),
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    relationships
+}
+
+fn parse_content_types(xml_text: &str) -> ContentTypes {
+    let mut types = ContentTypes::default();
+    let mut reader = NsReader::from_str(xml_text);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e)
+                if ns == Ns::ContentTypes && e.local_name().as_ref() == b"Override" =>
+            {
+                let mut part_name = None;
+                let mut content_type = None;
+                for attr in e.attributes().flatten() {
+                    let val = String::from_utf8_lossy(&attr.value).to_string();
+                    match attr.key.as_ref() {
+                        b"PartName" => part_name = Some(val.trim_start_matches('/').to_string()),
+                        b"ContentType" => content_type = Some(val),
+                        _ => {}
+                    }
+                }
+                if let (Some(part_name), Some(content_type)) = (part_name, content_type) {
+                    types.overrides.push((part_name, content_type));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    types
+}
+
+/// Resolve a relationship target against the source part's directory,
+/// following `..` segments; absolute targets start at the package root
+fn resolve_target(base_dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = match target.strip_prefix('/') {
+        Some(_) => Vec::new(),
+        None => base_dir.split('/').filter(|s| !s.is_empty()).collect(),
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
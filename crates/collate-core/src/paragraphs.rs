@@ -1,24 +1,316 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
 use quick_xml::events::Event;
-use quick_xml::Reader;
+use quick_xml::NsReader;
 
-use crate::track_changes::Segment;
+use crate::track_changes::{
+    self, FormatRevision, LayoutRevision, LayoutScope, PropertyChange, Revision, RevisionKind,
+    Segment, TableRevision, TableScope,
+};
+use crate::types::{BlockKind, Comment, Field, HeaderFooterType, TableCoordinates};
+use crate::xml::{self, local_name, Ns};
+
+/// Everything read from one document before paragraph blocks are built
+pub struct ParsedDocument {
+    pub paragraphs: Vec<ParsedParagraph>,
+    pub comments: HashMap<String, Comment>,
+    pub title: Option<String>,
+    /// Parts of the document the reader could not recover
+    pub warnings: Vec<String>,
+}
 
 /// Represents a parsed paragraph with its segments and comment anchors
+#[derive(Default)]
 pub struct ParsedParagraph {
     pub index: usize,
     pub segments: Vec<Segment>,
     pub comment_ids: Vec<String>,
     pub comment_anchor_texts: HashMap<String, String>,
+    /// `w14:paraId` — stable paragraph identity across copies of a document (Word 2010+)
+    pub para_id: Option<String>,
+    /// `w14:textId` — changes whenever the paragraph's text is edited
+    pub text_id: Option<String>,
+    /// Which story the paragraph belongs to (body, footnotes, endnotes)
+    pub kind: BlockKind,
+    /// For footnote/endnote paragraphs: the `w:id` of the note they belong to
+    pub note_id: Option<String>,
+    /// Ids of footnotes referenced from this paragraph (`w:footnoteReference`)
+    pub footnote_refs: Vec<String>,
+    /// Ids of endnotes referenced from this paragraph (`w:endnoteReference`)
+    pub endnote_refs: Vec<String>,
+    /// For note paragraphs: index of the body paragraph holding the reference.
+    /// For text boxes: index of the paragraph the box is anchored in.
+    pub anchor_index: Option<usize>,
+    /// For header/footer paragraphs: section that uses the part
+    pub section: Option<usize>,
+    /// For header/footer paragraphs: which pages show the part
+    pub header_footer_type: Option<HeaderFooterType>,
+    /// For header/footer paragraphs: the package part they were read from,
+    /// which tells apart parts no section refers to
+    pub part: Option<String>,
+    /// Table cell the paragraph sits in (innermost table when nested)
+    pub table: Option<TableCoordinates>,
+    /// Tracked change to the paragraph's own properties (`w:pPrChange`)
+    pub format_change: Option<FormatRevision>,
+    /// Tracked insertion or deletion of the paragraph mark (`w:ins` / `w:del`
+    /// in `w:pPr/w:rPr`): the paragraph was split after this one's text, or
+    /// joined to the next
+    pub mark_revision: Option<Revision>,
+    /// Tracked insertions and deletions of the table row or cell this
+    /// paragraph opens
+    pub table_revisions: Vec<TableRevision>,
+    /// Tracked changes to numbering, section, table, row or cell properties:
+    /// reported on the paragraph they belong to, the last paragraph of the
+    /// section, or the first paragraph of the table, row or cell
+    pub layout_revisions: Vec<LayoutRevision>,
+    /// Fields whose results show in the paragraph
+    pub fields: Vec<ParsedField>,
+}
+
+/// A field read in a paragraph: its code, and the segments its result covers
+/// in that paragraph. `field.result` is left empty.
+pub struct ParsedField {
+    pub field: Field,
+    pub segments: Range<usize>,
+}
+
+/// Position inside one open `w:tbl` while parsing
+struct TableCursor {
+    index: usize,
+    /// Current row; `None` before the first `w:tr`
+    row: Option<usize>,
+    is_header: bool,
+    /// Grid column of the current cell
+    column: usize,
+    /// Grid columns spanned by the current cell (`w:gridSpan`)
+    span: usize,
+    /// Grid column where the next cell starts
+    next_column: usize,
+    /// Tracked insertion or deletion of the current row and cell, with the
+    /// number of paragraphs read before each started
+    row_revision: Option<(Revision, usize)>,
+    cell_revision: Option<(Revision, usize)>,
+    /// Tracked property changes of the table, current row and current cell,
+    /// likewise
+    table_layout: Option<(LayoutRevision, usize)>,
+    row_layout: Option<(LayoutRevision, usize)>,
+    cell_layout: Option<(LayoutRevision, usize)>,
+}
+
+/// One open field while parsing
+struct OpenField {
+    /// Field code read so far from `w:instrText`
+    instruction: String,
+    /// The separator has been read, so text now belongs to the result
+    in_result: bool,
+    /// Parsed code, once the result starts
+    field: Option<Field>,
+    /// Index of the field's entry among the current paragraph's fields
+    entry: Option<usize>,
+}
+
+/// Open fields at the current position, outermost first. Like the field
+/// characters of a .doc file, a `w:fldChar` begin starts the field code, a
+/// separate starts the displayed result and an end closes the field.
+#[derive(Default)]
+struct FieldCursor {
+    open: Vec<OpenField>,
+    /// Inside `w:instrText` / `w:delInstrText`
+    in_instruction: bool,
+    /// Fields of the current paragraph
+    paragraph: Vec<ParsedField>,
+    /// Segments before this index lie on the other side of a field result
+    /// boundary and take no more text
+    boundary: usize,
+}
+
+impl FieldCursor {
+    /// Whether text read now is displayed: not field code, and in the result
+    /// of every open field
+    fn shows_text(&self) -> bool {
+        !self.in_instruction && self.open.iter().all(|f| f.in_result)
+    }
+
+    fn begin(&mut self) {
+        self.open.push(OpenField {
+            instruction: String::new(),
+            in_result: false,
+            field: None,
+            entry: None,
+        });
+    }
+
+    /// Start the innermost field's result at segment `at`
+    fn separate(&mut self, at: usize) {
+        let visible = self.open.iter().rev().skip(1).all(|f| f.in_result);
+        if let Some(open) = self.open.last_mut().filter(|f| !f.in_result) {
+            open.in_result = true;
+            let field = field(&open.instruction);
+            if visible {
+                open.entry = Some(self.paragraph.len());
+                self.paragraph.push(ParsedField {
+                    field: field.clone(),
+                    segments: at..at,
+                });
+            }
+            open.field = Some(field);
+        }
+        self.boundary = at;
+    }
+
+    /// Close the innermost field at segment `at`. A field without a
+    /// separator has no result but is recorded all the same.
+    fn end(&mut self, at: usize) {
+        if self.open.last().is_some_and(|f| !f.in_result) {
+            self.separate(at);
+        }
+        if let Some(entry) = self.open.pop().and_then(|f| f.entry) {
+            self.paragraph[entry].segments.end = at;
+        }
+        self.boundary = at;
+    }
+
+    /// A `w:fldSimple`: its code is an attribute and its content the result
+    fn simple(&mut self, instruction: String, at: usize) {
+        self.begin();
+        if let Some(open) = self.open.last_mut() {
+            open.instruction = instruction;
+        }
+        self.separate(at);
+    }
+
+    /// Carry results still open into a new paragraph
+    fn start_paragraph(&mut self) {
+        self.paragraph.clear();
+        self.boundary = 0;
+        for open in &mut self.open {
+            if !open.in_result {
+                break;
+            }
+            if let Some(field) = &open.field {
+                open.entry = Some(self.paragraph.len());
+                self.paragraph.push(ParsedField {
+                    field: field.clone(),
+                    segments: 0..0,
+                });
+            }
+        }
+    }
+
+    /// The current paragraph's fields, with results still open ending at
+    /// segment `at`
+    fn finish_paragraph(&mut self, at: usize) -> Vec<ParsedField> {
+        for open in &mut self.open {
+            if let Some(entry) = open.entry.take() {
+                self.paragraph[entry].segments.end = at;
+            }
+        }
+        std::mem::take(&mut self.paragraph)
+    }
+}
+
+/// Field type and target from a field code. Quoted arguments keep their
+/// spaces; switches (`\h`, `\o "1-3"`) are not targets.
+fn field(instruction: &str) -> Field {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut quoted = false;
+    let mut token = String::new();
+    for c in instruction.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Field {
+        field_type: tokens.first().map(|t| t.to_uppercase()).unwrap_or_default(),
+        target: tokens.get(1).filter(|t| !t.starts_with('\\')).cloned(),
+        instruction: instruction.trim().to_string(),
+        result: String::new(),
+    }
 }
 
 /// Parse document.xml and extract all paragraphs with their track changes and comment anchors
 pub fn parse_document(xml: &str) -> Vec<ParsedParagraph> {
-    let mut paragraphs = Vec::new();
-    let mut reader = Reader::from_str(xml);
+    parse_part(xml, BlockKind::Body)
+}
+
+/// Parse footnotes.xml or endnotes.xml. Each paragraph records the id of the
+/// note it belongs to; separator and continuation notes are skipped.
+pub fn parse_notes(xml: &str, kind: BlockKind) -> Vec<ParsedParagraph> {
+    parse_part(xml, kind)
+}
+
+/// Link note paragraphs to the body paragraphs that reference them and append
+/// them after the existing paragraphs, continuing the index sequence. Text
+/// boxes inside notes stay anchored to their note paragraph.
+pub fn append_notes(paragraphs: &mut Vec<ParsedParagraph>, notes: Vec<ParsedParagraph>) {
+    let offset = paragraphs.len();
+    for mut note in notes {
+        if note.kind == BlockKind::TextBox {
+            note.anchor_index = note.anchor_index.map(|a| a + offset);
+            note.index = paragraphs.len();
+            paragraphs.push(note);
+            continue;
+        }
+        let id = note.note_id.as_deref().unwrap_or_default();
+        note.anchor_index = paragraphs
+            .iter()
+            .find(|p| {
+                p.kind == BlockKind::Body
+                    && match note.kind {
+                        BlockKind::Footnote => p.footnote_refs.iter().any(|r| r == id),
+                        BlockKind::Endnote => p.endnote_refs.iter().any(|r| r == id),
+                        _ => false,
+                    }
+            })
+            .map(|p| p.index);
+        note.index = paragraphs.len();
+        paragraphs.push(note);
+    }
+}
+
+/// Parse a header or footer part (`w:hdr` / `w:ftr`) named `part` and append
+/// its paragraphs, labelled with the section and page type the part is used for
+pub fn append_header_footer(
+    paragraphs: &mut Vec<ParsedParagraph>,
+    xml: &str,
+    part: &str,
+    kind: BlockKind,
+    section: Option<usize>,
+    header_footer_type: Option<HeaderFooterType>,
+) {
+    let offset = paragraphs.len();
+    for mut para in parse_part(xml, kind) {
+        para.index = paragraphs.len();
+        para.anchor_index = para.anchor_index.map(|a| a + offset);
+        para.section = section;
+        para.header_footer_type = header_footer_type;
+        para.part = Some(part.to_string());
+        paragraphs.push(para);
+    }
+}
+
+fn parse_part(xml: &str, kind: BlockKind) -> Vec<ParsedParagraph> {
+    let mut reader = NsReader::from_str(xml);
     reader.config_mut().trim_text(true);
 
+    parse_story(&mut reader, kind)
+}
+
+/// Read paragraphs until the end of the input, or until the `w:txbxContent`
+/// this call was started for closes. Text boxes met inside a paragraph are read
+/// by a nested call and emitted right after their host paragraph.
+fn parse_story(reader: &mut NsReader<&[u8]>, kind: BlockKind) -> Vec<ParsedParagraph> {
+    let mut paragraphs: Vec<ParsedParagraph> = Vec::new();
     let mut buf = Vec::new();
     let mut para_index: usize = 0;
 
@@ -27,89 +319,272 @@ pub fn parse_document(xml: &str) -> Vec<ParsedParagraph> {
     let mut segments: Vec<Segment> = Vec::new();
     let mut comment_ids: Vec<String> = Vec::new();
     let mut comment_anchor_texts: HashMap<String, String> = HashMap::new();
+    let mut para_id: Option<String> = None;
+    let mut text_id: Option<String> = None;
+    let mut footnote_refs: Vec<String> = Vec::new();
+    let mut endnote_refs: Vec<String> = Vec::new();
 
-    // Track change state
-    let mut in_ins = false;
-    let mut in_del = false;
-    let mut ins_id = String::new();
-    let mut ins_author = String::new();
-    let mut ins_date: Option<String> = None;
-    let mut ins_text = String::new();
-    let mut del_id = String::new();
-    let mut del_author = String::new();
-    let mut del_date: Option<String> = None;
-    let mut del_text = String::new();
+    // Table state: one cursor per open table, innermost last
+    let mut tables: Vec<TableCursor> = Vec::new();
+    let mut table_count: usize = 0;
+    let mut table: Option<TableCoordinates> = None;
+
+    // Footnote/endnote container state
+    let mut note_id: Option<String> = None;
+    let mut skip_note = false;
+
+    // Track change state: the open `w:ins` / `w:del` / `w:moveFrom` / `w:moveTo`
+    // elements, outermost first, and the text read under them since the last
+    // one opened or closed
+    let mut revisions: Vec<Revision> = Vec::new();
+    let mut revision_text = String::new();
+    // Names of the open `w:moveFromRangeStart` / `w:moveToRangeStart` ranges
+    let mut move_from_name: Option<String> = None;
+    let mut move_to_name: Option<String> = None;
+    // Format revisions of the current run (`w:rPrChange`) and paragraph (`w:pPrChange`)
+    let mut run_format: Option<FormatRevision> = None;
+    let mut paragraph_format: Option<FormatRevision> = None;
+    let mut mark_revision: Option<Revision> = None;
+    let mut layout_revisions: Vec<LayoutRevision> = Vec::new();
+    // Fields stay open across paragraphs: a table of contents is one field
+    let mut fields = FieldCursor::default();
 
     // Comment anchor tracking
     let mut active_comment_ids: Vec<String> = Vec::new(); // Currently open comment ranges
     let mut comment_text_accum: HashMap<String, String> = HashMap::new(); // Accumulate text for each comment range
 
-    // Nesting depth for paragraph
-    let mut para_depth: usize = 0;
+    // Text boxes and shape text found in the current paragraph
+    let mut text_boxes: Vec<ParsedParagraph> = Vec::new();
+    // Paragraphs of text boxes, which are labelled as stories of their own
+    let mut nested: Vec<Range<usize>> = Vec::new();
+    // Depth inside w:drawing / w:pict / w:object, whose own text (positions,
+    // sizes) is not document text
+    let mut drawing_depth: usize = 0;
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = e.name();
-                let name_ref = name.as_ref();
-                let local = local_name(name_ref);
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) => {
+                let name = e.local_name();
+                let local = name.as_ref();
 
-                match local {
-                    b"p" if !in_paragraph => {
+                match (ns, local) {
+                    (Ns::Word, b"footnote" | b"endnote") if !in_paragraph => {
+                        note_id = None;
+                        skip_note = false;
+                        for attr in e.attributes().flatten() {
+                            let key = local_name(attr.key.as_ref());
+                            let val = String::from_utf8_lossy(&attr.value).to_string();
+                            match key {
+                                b"id" => note_id = Some(val),
+                                // separator, continuationSeparator, continuationNotice
+                                b"type" => skip_note = val != "normal",
+                                _ => {}
+                            }
+                        }
+                    }
+                    (Ns::Word, b"tbl") => {
+                        tables.push(TableCursor {
+                            index: table_count,
+                            row: None,
+                            is_header: false,
+                            column: 0,
+                            span: 1,
+                            next_column: 0,
+                            row_revision: None,
+                            cell_revision: None,
+                            table_layout: None,
+                            row_layout: None,
+                            cell_layout: None,
+                        });
+                        table_count += 1;
+                    }
+                    (Ns::Word, b"tr") => {
+                        if let Some(t) = tables.last_mut() {
+                            t.row = Some(t.row.map_or(0, |r| r + 1));
+                            t.is_header = false;
+                            t.next_column = 0;
+                            t.row_revision = None;
+                            t.row_layout = None;
+                        }
+                    }
+                    (Ns::Word, b"tc") => {
+                        if let Some(t) = tables.last_mut() {
+                            t.column = t.next_column;
+                            t.span = 1;
+                            t.cell_revision = None;
+                            t.cell_layout = None;
+                        }
+                    }
+                    (Ns::Word, b"tblPr" | b"trPr" | b"tcPr") if !in_paragraph => {
+                        let properties = read_properties(reader);
+                        if let Some(t) = tables.last_mut() {
+                            let value = |name: &str| {
+                                properties
+                                    .current
+                                    .iter()
+                                    .find(|(n, _)| n == name)
+                                    .map(|(_, v)| v.as_str())
+                            };
+                            let start = paragraphs.len();
+                            let scope = match local {
+                                b"tblPr" => LayoutScope::Table,
+                                b"trPr" => LayoutScope::Row,
+                                _ => LayoutScope::Cell,
+                            };
+                            let layout = properties
+                                .format
+                                .map(|revision| (LayoutRevision { revision, scope }, start));
+                            let revision = properties.revision.map(|r| (r, start));
+                            match scope {
+                                LayoutScope::Table => t.table_layout = layout,
+                                LayoutScope::Row => {
+                                    t.is_header = value("tblHeader")
+                                        .is_some_and(|v| !matches!(v, "0" | "false" | "off"));
+                                    t.next_column += value("gridBefore")
+                                        .and_then(|v| v.parse().ok())
+                                        .unwrap_or(0);
+                                    t.row_revision = revision;
+                                    t.row_layout = layout;
+                                }
+                                _ => {
+                                    t.span = value("gridSpan")
+                                        .and_then(|v| v.parse().ok())
+                                        .unwrap_or(1)
+                                        .max(1);
+                                    t.cell_revision = revision;
+                                    t.cell_layout = layout;
+                                }
+                            }
+                        }
+                    }
+                    (Ns::Word, b"sectPr") if !in_paragraph => {
+                        // The last section's properties follow its last paragraph
+                        let revision = read_properties(reader).format;
+                        if let (Some(revision), Some(last)) = (revision, paragraphs.last_mut()) {
+                            last.layout_revisions.push(LayoutRevision {
+                                revision,
+                                scope: LayoutScope::Section,
+                            });
+                        }
+                    }
+                    (Ns::MarkupCompatibility, b"Fallback") => {
+                        // mc:Fallback repeats the mc:Choice content for older readers
+                        let end = e.to_end().into_owned();
+                        let _ = reader.read_to_end_into(end.name(), &mut Vec::new());
+                    }
+                    (Ns::Word, b"txbxContent") => {
+                        text_boxes.extend(parse_story(reader, BlockKind::TextBox));
+                    }
+                    (Ns::Word, b"drawing" | b"pict" | b"object") if in_paragraph => {
+                        drawing_depth += 1;
+                    }
+                    (Ns::DrawingMl, b"p") if in_paragraph && drawing_depth > 0 => {
+                        // DrawingML paragraph (a:p) in a shape or diagram
+                        let text = drawing_text(reader);
+                        if !text.trim().is_empty() {
+                            text_boxes.push(ParsedParagraph {
+                                segments: vec![Segment::Stable(text)],
+                                kind: BlockKind::TextBox,
+                                ..Default::default()
+                            });
+                        }
+                    }
+                    (Ns::Word, b"p") if !in_paragraph => {
                         in_paragraph = true;
-                        para_depth = 1;
+                        table = tables.last().map(|t| TableCoordinates {
+                            table_index: t.index,
+                            row: t.row.unwrap_or(0),
+                            column: t.column,
+                            is_header: t.is_header,
+                            column_header: None,
+                        });
+                        text_boxes.clear();
                         segments.clear();
                         comment_ids.clear();
                         comment_anchor_texts.clear();
-                    }
-                    b"p" if in_paragraph => {
-                        // Nested paragraph (e.g., inside a table cell's paragraph)
-                        para_depth += 1;
-                    }
-                    b"ins" if in_paragraph => {
-                        in_ins = true;
-                        ins_text.clear();
-                        ins_id = String::new();
-                        ins_author = String::new();
-                        ins_date = None;
+                        footnote_refs.clear();
+                        endnote_refs.clear();
+                        para_id = None;
+                        text_id = None;
+                        paragraph_format = None;
+                        mark_revision = None;
+                        layout_revisions.clear();
+                        fields.start_paragraph();
                         for attr in e.attributes().flatten() {
-                            let key = local_name(attr.key.as_ref());
+                            let (attr_ns, key) = reader.resolve_attribute(attr.key);
+                            if xml::ns(&attr_ns) != Ns::Word2010 {
+                                continue;
+                            }
                             let val = String::from_utf8_lossy(&attr.value).to_string();
-                            match key {
-                                b"id" => ins_id = val,
-                                b"author" => ins_author = val,
-                                b"date" => ins_date = Some(val),
+                            match key.as_ref() {
+                                b"paraId" => para_id = Some(val),
+                                b"textId" => text_id = Some(val),
                                 _ => {}
                             }
                         }
                     }
-                    b"del" if in_paragraph => {
-                        in_del = true;
-                        del_text.clear();
-                        del_id = String::new();
-                        del_author = String::new();
-                        del_date = None;
+                    (Ns::Word, b"pPr") if in_paragraph => {
+                        let properties = read_properties(reader);
+                        paragraph_format = properties.format;
+                        mark_revision = properties.revision;
+                        layout_revisions = properties.layout;
+                    }
+                    (Ns::Word, b"r") if in_paragraph => run_format = None,
+                    (Ns::Word, b"rPr") if in_paragraph => {
+                        run_format = read_properties(reader).format;
+                    }
+                    (Ns::Word, b"instrText" | b"delInstrText") if in_paragraph => {
+                        // Word splits codes anywhere, even inside an
+                        // argument, so pieces are read untrimmed
+                        fields.in_instruction = true;
+                        reader.config_mut().trim_text(false);
+                    }
+                    (Ns::Word, b"fldChar") if in_paragraph => {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        field_char(e, &mut fields, segments.len());
+                    }
+                    (Ns::Word, b"fldSimple") if in_paragraph => {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        fields.simple(field_instruction(e), segments.len());
+                    }
+                    (Ns::Word, b"ins" | b"del" | b"moveFrom" | b"moveTo") if in_paragraph => {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        // A move outside a named range cannot be paired, so
+                        // it is kept as a plain deletion or insertion
+                        let kind = match (local, &move_from_name, &move_to_name) {
+                            (b"moveFrom", Some(name), _) => RevisionKind::MoveFrom(name.clone()),
+                            (b"moveTo", _, Some(name)) => RevisionKind::MoveTo(name.clone()),
+                            (b"del" | b"moveFrom", _, _) => RevisionKind::Deletion,
+                            _ => RevisionKind::Insertion,
+                        };
+                        let mut revision = Revision {
+                            kind,
+                            id: String::new(),
+                            author: String::new(),
+                            date: None,
+                        };
                         for attr in e.attributes().flatten() {
                             let key = local_name(attr.key.as_ref());
                             let val = String::from_utf8_lossy(&attr.value).to_string();
                             match key {
-                                b"id" => del_id = val,
-                                b"author" => del_author = val,
-                                b"date" => del_date = Some(val),
+                                b"id" => revision.id = val,
+                                b"author" => revision.author = val,
+                                b"date" => revision.date = Some(val),
                                 _ => {}
                             }
                         }
+                        revisions.push(revision);
                     }
                     _ => {}
                 }
             }
-            Ok(Event::Empty(ref e)) => {
-                let name = e.name();
-                let name_ref = name.as_ref();
-                let local = local_name(name_ref);
+            Event::Empty(ref e) => {
+                let name = e.local_name();
+                let local = name.as_ref();
 
-                match local {
-                    b"commentRangeStart" if in_paragraph => {
+                match (ns, local) {
+                    (Ns::Word, b"commentRangeStart") if in_paragraph => {
                         for attr in e.attributes().flatten() {
                             let key = local_name(attr.key.as_ref());
                             if key == b"id" {
@@ -120,7 +595,41 @@ pub fn parse_document(xml: &str) -> Vec<ParsedParagraph> {
                             }
                         }
                     }
-                    b"commentRangeEnd" if in_paragraph => {
+                    (Ns::Word, b"footnoteReference" | b"endnoteReference") if in_paragraph => {
+                        for attr in e.attributes().flatten() {
+                            if local_name(attr.key.as_ref()) == b"id" {
+                                let id = String::from_utf8_lossy(&attr.value).to_string();
+                                if local == b"footnoteReference" {
+                                    footnote_refs.push(id);
+                                } else {
+                                    endnote_refs.push(id);
+                                }
+                            }
+                        }
+                    }
+                    (Ns::Word, b"fldChar") if in_paragraph => {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        field_char(e, &mut fields, segments.len());
+                    }
+                    (Ns::Word, b"fldSimple") if in_paragraph => {
+                        fields.simple(field_instruction(e), segments.len());
+                        fields.end(segments.len());
+                    }
+                    (Ns::Word, b"moveFromRangeStart" | b"moveToRangeStart") => {
+                        let name = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| local_name(a.key.as_ref()) == b"name")
+                            .map(|a| String::from_utf8_lossy(&a.value).to_string());
+                        if local == b"moveFromRangeStart" {
+                            move_from_name = name;
+                        } else {
+                            move_to_name = name;
+                        }
+                    }
+                    (Ns::Word, b"moveFromRangeEnd") => move_from_name = None,
+                    (Ns::Word, b"moveToRangeEnd") => move_to_name = None,
+                    (Ns::Word, b"commentRangeEnd") if in_paragraph => {
                         for attr in e.attributes().flatten() {
                             let key = local_name(attr.key.as_ref());
                             if key == b"id" {
@@ -135,105 +644,666 @@ pub fn parse_document(xml: &str) -> Vec<ParsedParagraph> {
                     _ => {}
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if in_paragraph {
-                    if let Ok(txt) = e.unescape() {
-                        let text = txt.to_string();
+            Event::Text(ref e) if in_paragraph && drawing_depth == 0 => {
+                if let Ok(txt) = e.unescape() {
+                    let text = txt.to_string();
+                    if fields.in_instruction {
+                        if let Some(open) = fields.open.last_mut() {
+                            open.instruction.push_str(&text);
+                        }
+                        buf.clear();
+                        continue;
+                    }
+                    if !fields.shows_text() {
+                        buf.clear();
+                        continue;
+                    }
+                    // Text on both sides of a field result boundary is kept
+                    // in separate segments
+                    let can_merge = segments.len() > fields.boundary;
 
-                        // Accumulate text for any active comment ranges
-                        for cid in &active_comment_ids {
-                            if let Some(accum) = comment_text_accum.get_mut(cid) {
-                                accum.push_str(&text);
-                            }
+                    // Accumulate text for any active comment ranges
+                    for cid in &active_comment_ids {
+                        if let Some(accum) = comment_text_accum.get_mut(cid) {
+                            accum.push_str(&text);
                         }
+                    }
 
-                        if in_ins {
-                            ins_text.push_str(&text);
-                        } else if in_del {
-                            del_text.push_str(&text);
+                    if !revisions.is_empty() {
+                        revision_text.push_str(&text);
+                    } else if let Some(revision) = &run_format {
+                        // Runs sharing one format revision form a single segment
+                        match segments.last_mut() {
+                            Some(Segment::FormatChange {
+                                revision: previous,
+                                text: t,
+                            }) if previous == revision && can_merge => t.push_str(&text),
+                            _ => segments.push(Segment::FormatChange {
+                                revision: revision.clone(),
+                                text,
+                            }),
+                        }
+                    } else {
+                        // Stable text - merge consecutive stable segments
+                        if let (Some(Segment::Stable(ref mut s)), true) =
+                            (segments.last_mut(), can_merge)
+                        {
+                            s.push_str(&text);
                         } else {
-                            // Stable text - merge consecutive stable segments
-                            if let Some(Segment::Stable(ref mut s)) = segments.last_mut() {
-                                s.push_str(&text);
-                            } else {
-                                segments.push(Segment::Stable(text));
-                            }
+                            segments.push(Segment::Stable(text));
                         }
                     }
                 }
             }
-            Ok(Event::End(ref e)) => {
-                let name = e.name();
-                let name_ref = name.as_ref();
-                let local = local_name(name_ref);
-
-                match local {
-                    b"p" if in_paragraph => {
-                        para_depth -= 1;
-                        if para_depth == 0 {
-                            // End of paragraph — only add if it has content
-                            let has_content = segments.iter().any(|s| match s {
-                                Segment::Stable(t) => !t.trim().is_empty(),
-                                Segment::Insertion { text, .. } => !text.trim().is_empty(),
-                                Segment::Deletion { text, .. } => !text.trim().is_empty(),
+            Event::End(ref e) => {
+                let name = e.local_name();
+                let local = name.as_ref();
+
+                match (ns, local) {
+                    (Ns::Word, b"p") if in_paragraph => {
+                        // End of paragraph — only add if it has content
+                        let has_content = segments.iter().any(Segment::has_content);
+
+                        let paragraph_fields = fields.finish_paragraph(segments.len());
+                        let mut host = None;
+                        if has_content && !skip_note {
+                            paragraphs.push(ParsedParagraph {
+                                index: para_index,
+                                segments: segments.clone(),
+                                comment_ids: comment_ids.clone(),
+                                comment_anchor_texts: comment_anchor_texts.clone(),
+                                para_id: para_id.take(),
+                                text_id: text_id.take(),
+                                kind,
+                                table: table.take(),
+                                note_id: note_id.clone(),
+                                footnote_refs: footnote_refs.clone(),
+                                endnote_refs: endnote_refs.clone(),
+                                format_change: paragraph_format.take(),
+                                mark_revision: mark_revision.take(),
+                                layout_revisions: std::mem::take(&mut layout_revisions),
+                                fields: paragraph_fields,
+                                ..Default::default()
                             });
+                            host = Some(para_index);
+                            para_index += 1;
+                        }
 
-                            if has_content {
-                                paragraphs.push(ParsedParagraph {
-                                    index: para_index,
-                                    segments: segments.clone(),
-                                    comment_ids: comment_ids.clone(),
-                                    comment_anchor_texts: comment_anchor_texts.clone(),
-                                });
+                        // Text boxes follow their host; anchors inside nested
+                        // text boxes shift with them
+                        if !skip_note {
+                            let offset = para_index;
+                            let start = paragraphs.len();
+                            for mut text_box in text_boxes.drain(..) {
+                                text_box.index = para_index;
+                                text_box.anchor_index = match text_box.anchor_index {
+                                    Some(a) => Some(a + offset),
+                                    None => host,
+                                };
+                                text_box.note_id = note_id.clone();
+                                paragraphs.push(text_box);
                                 para_index += 1;
                             }
+                            nested.push(start..paragraphs.len());
+                        }
 
-                            in_paragraph = false;
-                            segments.clear();
-                            comment_ids.clear();
-                            comment_anchor_texts.clear();
+                        in_paragraph = false;
+                        segments.clear();
+                        comment_ids.clear();
+                        comment_anchor_texts.clear();
+                    }
+                    (Ns::Word, b"r") => run_format = None,
+                    (Ns::Word, b"instrText" | b"delInstrText") => {
+                        fields.in_instruction = false;
+                        reader.config_mut().trim_text(true);
+                    }
+                    (Ns::Word, b"fldSimple") if in_paragraph => {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        fields.end(segments.len());
+                    }
+                    (Ns::Word, b"drawing" | b"pict" | b"object") if drawing_depth > 0 => {
+                        drawing_depth -= 1;
+                    }
+                    (Ns::Word, b"txbxContent") => break,
+                    (Ns::Word, b"ins" | b"del" | b"moveFrom" | b"moveTo")
+                        if revisions.last().is_some_and(|r| closes(r, local)) =>
+                    {
+                        flush_revision(&mut segments, &revisions, &mut revision_text);
+                        revisions.pop();
+                    }
+                    (Ns::Word, b"footnote" | b"endnote") if !in_paragraph => {
+                        note_id = None;
+                        skip_note = false;
+                    }
+                    (Ns::Word, b"tc") => {
+                        if let Some(t) = tables.last_mut() {
+                            let cell = (t.index, t.row, Some(t.column));
+                            if let Some((revision, start)) = t.cell_revision.take() {
+                                record_table_revision(
+                                    &mut paragraphs[start..],
+                                    kind,
+                                    cell,
+                                    revision,
+                                    TableScope::Cell,
+                                );
+                            }
+                            if let Some((layout, start)) = t.cell_layout.take() {
+                                record_layout_revision(
+                                    &mut paragraphs[start..],
+                                    kind,
+                                    cell,
+                                    layout,
+                                );
+                            }
+                            t.next_column = t.column + t.span;
                         }
                     }
-                    b"ins" if in_ins => {
-                        if !ins_text.is_empty() {
-                            segments.push(Segment::Insertion {
-                                id: ins_id.clone(),
-                                author: ins_author.clone(),
-                                date: ins_date.clone(),
-                                text: ins_text.clone(),
-                            });
+                    (Ns::Word, b"tr") => {
+                        if let Some(t) = tables.last_mut() {
+                            let row = (t.index, t.row, None);
+                            if let Some((revision, start)) = t.row_revision.take() {
+                                record_table_revision(
+                                    &mut paragraphs[start..],
+                                    kind,
+                                    row,
+                                    revision,
+                                    TableScope::Row,
+                                );
+                            }
+                            if let Some((layout, start)) = t.row_layout.take() {
+                                record_layout_revision(&mut paragraphs[start..], kind, row, layout);
+                            }
                         }
-                        in_ins = false;
                     }
-                    b"del" if in_del => {
-                        if !del_text.is_empty() {
-                            segments.push(Segment::Deletion {
-                                id: del_id.clone(),
-                                author: del_author.clone(),
-                                date: del_date.clone(),
-                                text: del_text.clone(),
-                            });
+                    (Ns::Word, b"tbl") => {
+                        if let Some(t) = tables.pop() {
+                            if let Some((layout, start)) = t.table_layout {
+                                let whole = (t.index, None, None);
+                                record_layout_revision(
+                                    &mut paragraphs[start..],
+                                    kind,
+                                    whole,
+                                    layout,
+                                );
+                            }
                         }
-                        in_del = false;
                     }
                     _ => {}
                 }
             }
-            Ok(Event::Eof) => break,
-            Err(_) => break,
+            Event::Eof => break,
             _ => {}
         }
         buf.clear();
     }
 
+    label_story_columns(&mut paragraphs, &nested);
     paragraphs
 }
 
-/// Extract local name from a potentially namespace-prefixed element name
-fn local_name(name: &[u8]) -> &[u8] {
-    if let Some(pos) = name.iter().position(|&b| b == b':') {
-        &name[pos + 1..]
-    } else {
-        name
+/// A table, or one row or cell of it: table index, row and grid column
+type TableSpan = (usize, Option<usize>, Option<usize>);
+
+/// Whether a paragraph of the story `kind` is inside `span`
+fn inside(para: &ParsedParagraph, kind: BlockKind, (table, row, column): TableSpan) -> bool {
+    para.kind == kind
+        && para.table.as_ref().is_some_and(|t| {
+            t.table_index == table
+                && row.is_none_or(|r| t.row == r)
+                && column.is_none_or(|c| t.column == c)
+        })
+}
+
+/// Attach a row or cell revision to the first of `paragraphs` inside that row
+/// or cell, with the text of all of them: revised text for an insertion, base
+/// text for a deletion. A row or cell without text has no paragraph to carry
+/// its revision.
+fn record_table_revision(
+    paragraphs: &mut [ParsedParagraph],
+    kind: BlockKind,
+    span: TableSpan,
+    revision: Revision,
+    scope: TableScope,
+) {
+    let mut text = String::new();
+    let mut last_column = None;
+    for para in paragraphs.iter().filter(|p| inside(p, kind, span)) {
+        let column = para.table.as_ref().map(|t| t.column);
+        if last_column.is_some() {
+            text.push_str(if column == last_column { " " } else { " | " });
+        }
+        last_column = column;
+        if revision.adds() {
+            text.push_str(&track_changes::revised_text(&para.segments));
+        } else {
+            text.push_str(&track_changes::base_text(&para.segments));
+        }
+    }
+    if let Some(first) = paragraphs.iter_mut().find(|p| inside(p, kind, span)) {
+        first.table_revisions.push(TableRevision {
+            revision,
+            scope,
+            text,
+        });
+    }
+}
+
+/// Attach a table, row or cell property revision to the first of `paragraphs`
+/// inside it
+fn record_layout_revision(
+    paragraphs: &mut [ParsedParagraph],
+    kind: BlockKind,
+    span: TableSpan,
+    layout: LayoutRevision,
+) {
+    if let Some(first) = paragraphs.iter_mut().find(|p| inside(p, kind, span)) {
+        first.layout_revisions.push(layout);
+    }
+}
+
+/// Emit the text read under the open revisions as one segment
+fn flush_revision(segments: &mut Vec<Segment>, revisions: &[Revision], text: &mut String) {
+    if !revisions.is_empty() && !text.is_empty() {
+        segments.push(Segment::revised(revisions.to_vec(), std::mem::take(text)));
+    }
+}
+
+/// Advance the field cursor past a `w:fldChar` at segment `at`
+fn field_char(e: &quick_xml::events::BytesStart, fields: &mut FieldCursor, at: usize) {
+    let kind = e
+        .attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == b"fldCharType")
+        .map(|a| a.value.into_owned());
+    match kind.as_deref() {
+        Some(b"begin") => fields.begin(),
+        Some(b"separate") => fields.separate(at),
+        Some(b"end") => fields.end(at),
+        _ => {}
+    }
+}
+
+/// The field code of a `w:fldSimple`
+fn field_instruction(e: &quick_xml::events::BytesStart) -> String {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == b"instr")
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+        .unwrap_or_default()
+}
+
+/// Whether the end tag `local` closes the element `revision` was read from.
+/// Unnamed moves are read as plain insertions and deletions.
+fn closes(revision: &Revision, local: &[u8]) -> bool {
+    matches!(
+        (&revision.kind, local),
+        (RevisionKind::Insertion, b"ins" | b"moveTo")
+            | (RevisionKind::Deletion, b"del" | b"moveFrom")
+            | (RevisionKind::MoveFrom(_), b"moveFrom")
+            | (RevisionKind::MoveTo(_), b"moveTo")
+    )
+}
+
+/// Properties ignored when comparing formatting: complex-script twins of
+/// properties already compared, proofing marks, table style conditions Word
+/// recomputes, and section header/footer links
+const UNCOMPARED_PROPERTIES: [&[u8]; 9] = [
+    b"bCs",
+    b"iCs",
+    b"szCs",
+    b"lang",
+    b"noProof",
+    b"webHidden",
+    b"cnfStyle",
+    b"headerReference",
+    b"footerReference",
+];
+
+/// What a properties element (`w:rPr`, `w:pPr`, `w:sectPr`, `w:tblPr`,
+/// `w:trPr`, `w:tcPr`) holds
+#[derive(Default)]
+struct Properties {
+    /// Properties in effect, as element name and value
+    current: Vec<(String, String)>,
+    /// Format revision (`w:rPrChange`, `w:pPrChange`, `w:sectPrChange`, ...)
+    format: Option<FormatRevision>,
+    /// Tracked insertion or deletion of what the properties belong to: the
+    /// paragraph mark (in the `w:rPr` of a `w:pPr`), a row (`w:ins` / `w:del`
+    /// in `w:trPr`) or a cell (`w:cellIns` / `w:cellDel` in `w:tcPr`)
+    revision: Option<Revision>,
+    /// Layout revisions inside a `w:pPr`: its section properties
+    /// (`w:sectPrChange`) and list numbering (`w:numberingChange`)
+    layout: Vec<LayoutRevision>,
+}
+
+/// Read a properties element up to its closing tag. Its tracked format
+/// revision, if it has one, lists the properties that differ from the old
+/// ones kept in the change record. The paragraph mark's run properties inside
+/// a `w:pPr` are not compared; only a tracked insertion or deletion of the
+/// mark is read from them. Section properties inside a `w:pPr` are read as a
+/// layout revision of their own.
+fn read_properties(reader: &mut NsReader<&[u8]>) -> Properties {
+    #[derive(PartialEq)]
+    enum Target {
+        Current,
+        Old,
+        Mark,
+    }
+
+    let mut buf = Vec::new();
+    let mut depth: usize = 1;
+    let mut target = Target::Current;
+    let mut properties = Properties::default();
+    let mut old: Vec<(String, String)> = Vec::new();
+    // `w:numberingChange` and the numbering text it records as the original
+    let mut numbering: Option<(FormatRevision, String)> = None;
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        let (e, is_start) = match event {
+            Event::Start(ref e) => (e, true),
+            Event::Empty(ref e) => (e, false),
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                buf.clear();
+                continue;
+            }
+            Event::Eof => break,
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        let name = e.local_name();
+        let local = name.as_ref();
+
+        if ns == Ns::Word {
+            let value = property_value(e);
+            let property = (String::from_utf8_lossy(local).into_owned(), value.clone());
+            match depth {
+                1 => match local {
+                    b"rPrChange" | b"pPrChange" | b"sectPrChange" | b"tblPrChange"
+                    | b"trPrChange" | b"tcPrChange" => {
+                        target = Target::Old;
+                        properties.format = Some(format_revision(e));
+                    }
+                    b"ins" | b"del" | b"cellIns" | b"cellDel" => {
+                        properties.revision = Some(recorded_revision(e, local));
+                    }
+                    b"rPr" => target = Target::Mark,
+                    b"sectPr" => {
+                        if is_start {
+                            let section = read_properties(reader);
+                            if let Some(revision) = section.format {
+                                properties.layout.push(LayoutRevision {
+                                    revision,
+                                    scope: LayoutScope::Section,
+                                });
+                            }
+                            buf.clear();
+                            continue;
+                        }
+                    }
+                    _ => {
+                        target = Target::Current;
+                        properties.current.push(property);
+                    }
+                },
+                // The old properties element inside the change record
+                2 if target == Target::Old => {}
+                3 if target == Target::Old => old.push(property),
+                2 if target == Target::Mark && matches!(local, b"ins" | b"del") => {
+                    properties.revision = Some(recorded_revision(e, local));
+                }
+                2 if target == Target::Current && local == b"numberingChange" => {
+                    let original = e
+                        .attributes()
+                        .flatten()
+                        .find(|a| local_name(a.key.as_ref()) == b"original")
+                        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+                        .unwrap_or_default();
+                    numbering = Some((format_revision(e), original));
+                }
+                // Children of a property (w:numPr/w:ilvl, ...) add to its value
+                _ => {
+                    let list = match target {
+                        Target::Current => Some(&mut properties.current),
+                        Target::Old => Some(&mut old),
+                        Target::Mark => None,
+                    };
+                    if let Some((_, parent)) = list.and_then(|l| l.last_mut()) {
+                        let value = if value.contains('=') {
+                            value
+                        } else {
+                            format!("{}={}", property.0, value)
+                        };
+                        if !parent.is_empty() {
+                            parent.push(' ');
+                        }
+                        parent.push_str(&value);
+                    }
+                }
+            }
+        }
+        if is_start {
+            depth += 1;
+        }
+        buf.clear();
+    }
+
+    let lookup = |list: &[(String, String)], name: &str| {
+        list.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    };
+    if let Some((mut revision, original)) = numbering {
+        revision.properties.push(PropertyChange {
+            name: "numbering".to_string(),
+            before: Some(original).filter(|o| !o.is_empty()),
+            after: lookup(&properties.current, "numPr"),
+        });
+        properties.layout.push(LayoutRevision {
+            revision,
+            scope: LayoutScope::Numbering,
+        });
+    }
+
+    if let Some(revision) = &mut properties.format {
+        let compared = |name: &String| !UNCOMPARED_PROPERTIES.contains(&name.as_bytes());
+        let mut names: Vec<&String> = properties.current.iter().map(|(n, _)| n).collect();
+        names.extend(old.iter().map(|(n, _)| n));
+        let mut seen = std::collections::HashSet::new();
+        for name in names.into_iter().filter(|n| compared(n)) {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let (before, after) = (lookup(&old, name), lookup(&properties.current, name));
+            if before != after {
+                revision.properties.push(PropertyChange {
+                    name: name.clone(),
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+    properties
+}
+
+/// The author, date and id of a change record (`w:rPrChange`, ...) as an
+/// empty format revision
+fn format_revision(e: &quick_xml::events::BytesStart) -> FormatRevision {
+    let mut revision = FormatRevision {
+        id: String::new(),
+        author: String::new(),
+        date: None,
+        properties: Vec::new(),
+    };
+    for attr in e.attributes().flatten() {
+        let val = String::from_utf8_lossy(&attr.value).to_string();
+        match local_name(attr.key.as_ref()) {
+            b"id" => revision.id = val,
+            b"author" => revision.author = val,
+            b"date" => revision.date = Some(val),
+            _ => {}
+        }
+    }
+    revision
+}
+
+/// A tracked insertion or deletion recorded as an empty element among
+/// properties (`w:ins`, `w:del`, `w:cellIns`, `w:cellDel`)
+fn recorded_revision(e: &quick_xml::events::BytesStart, local: &[u8]) -> Revision {
+    let revision = format_revision(e);
+    Revision {
+        kind: if matches!(local, b"ins" | b"cellIns") {
+            RevisionKind::Insertion
+        } else {
+            RevisionKind::Deletion
+        },
+        id: revision.id,
+        author: revision.author,
+        date: revision.date,
+    }
+}
+
+/// The value of a property element: its `w:val`, or `attribute=value` pairs
+/// when it has other attributes
+fn property_value(e: &quick_xml::events::BytesStart) -> String {
+    let attrs: Vec<(String, String)> = e
+        .attributes()
+        .flatten()
+        .filter(|a| !a.key.as_ref().starts_with(b"xmlns"))
+        .map(|a| {
+            (
+                String::from_utf8_lossy(local_name(a.key.as_ref())).into_owned(),
+                String::from_utf8_lossy(&a.value).into_owned(),
+            )
+        })
+        .collect();
+    match attrs.as_slice() {
+        [(key, value)] if key == "val" => value.clone(),
+        _ => attrs
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Collect the text of a DrawingML paragraph up to its closing tag
+fn drawing_text(reader: &mut NsReader<&[u8]>) -> String {
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut depth: usize = 1;
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) if ns == Ns::DrawingMl && e.local_name().as_ref() == b"p" => {
+                depth += 1
+            }
+            Event::End(ref e) if ns == Ns::DrawingMl && e.local_name().as_ref() == b"p" => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Event::Text(ref e) => {
+                if let Ok(txt) = e.unescape() {
+                    text.push_str(&txt);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    text
+}
+
+/// Replace style ids in format revisions (`pStyle`, `rStyle`) with style names
+pub fn name_styles(paragraphs: &mut [ParsedParagraph], names: &HashMap<String, String>) {
+    let rename = |revision: &mut FormatRevision| {
+        for property in &mut revision.properties {
+            if property.name != "pStyle" && property.name != "rStyle" {
+                continue;
+            }
+            for value in [&mut property.before, &mut property.after]
+                .into_iter()
+                .flatten()
+            {
+                if let Some(name) = names.get(value.as_str()) {
+                    *value = name.clone();
+                }
+            }
+        }
+    };
+    for para in paragraphs {
+        if let Some(revision) = &mut para.format_change {
+            rename(revision);
+        }
+        for segment in &mut para.segments {
+            if let Segment::FormatChange { revision, .. } = segment {
+                rename(revision);
+            }
+        }
+    }
+}
+
+/// Fill in `column_header` for table paragraphs from the text of the header
+/// rows in the same column, or of the first row when no row is marked as header
+pub fn label_columns(paragraphs: &mut [ParsedParagraph]) {
+    label_story_columns(paragraphs, &[]);
+}
+
+/// `label_columns` for one story, leaving out the paragraphs in `nested`:
+/// each text box numbers its tables from 0 again, so it is labelled on its own
+fn label_story_columns(paragraphs: &mut [ParsedParagraph], nested: &[Range<usize>]) {
+    let own = |i: usize| !nested.iter().any(|r| r.contains(&i));
+    let mut headers: HashMap<(BlockKind, usize, usize), String> = HashMap::new();
+    let marked: Vec<(BlockKind, usize)> = paragraphs
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| own(*i))
+        .filter_map(|(_, p)| p.table.as_ref().map(|t| (p.kind, t)))
+        .filter(|(_, t)| t.is_header)
+        .map(|(kind, t)| (kind, t.table_index))
+        .collect();
+
+    for (_, para) in paragraphs.iter().enumerate().filter(|(i, _)| own(*i)) {
+        let t = match &para.table {
+            Some(t) => t,
+            None => continue,
+        };
+        let is_label = if marked.contains(&(para.kind, t.table_index)) {
+            t.is_header
+        } else {
+            t.row == 0
+        };
+        if !is_label {
+            continue;
+        }
+        let text = track_changes::revised_text(&para.segments);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let label = headers
+            .entry((para.kind, t.table_index, t.column))
+            .or_default();
+        if !label.is_empty() {
+            label.push(' ');
+        }
+        label.push_str(text);
+    }
+
+    for (_, para) in paragraphs.iter_mut().enumerate().filter(|(i, _)| own(*i)) {
+        let kind = para.kind;
+        if let Some(t) = para.table.as_mut() {
+            t.column_header = headers.get(&(kind, t.table_index, t.column)).cloned();
+        }
     }
 }
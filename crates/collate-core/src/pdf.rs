@@ -0,0 +1,1633 @@
+//! Reviewer markups in PDF files.
+//!
+//! A reviewer who marks up a print of the draft leaves their edits as
+//! annotations laid over the page text. This module reads those annotations and
+//! recovers the text beneath each one from the page content streams; `markup`
+//! anchors them to the paragraphs of the base document.
+//!
+//! Objects are found by scanning the file for `N G obj` rather than through the
+//! cross-reference table, so files with damaged or missing xref sections still
+//! open. Encrypted files are rejected.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::doc;
+use crate::matcher;
+use crate::paragraphs::{ParsedDocument, ParsedParagraph};
+use crate::track_changes::Segment;
+use crate::types::{CollateResult, Comment};
+
+/// Characters of page text kept on each side of a markup to tell repeated
+/// phrases apart when anchoring
+const CONTEXT_CHARS: usize = 60;
+/// Nesting limit for page trees and reference chains in malformed files
+const MAX_DEPTH: usize = 32;
+
+type Dict = HashMap<String, Object>;
+
+#[derive(Debug, Clone)]
+enum Object {
+    Null,
+    /// Boolean; no key read here needs its value
+    Bool,
+    Number(f64),
+    Name(String),
+    Str(Vec<u8>),
+    Array(Vec<Object>),
+    Dict(Dict),
+    /// Indirect reference; the generation number is not needed
+    Ref(u32),
+    /// Stream dictionary and its raw (still encoded) data
+    Stream(Dict, Vec<u8>),
+}
+
+impl Object {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Object::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> &[Object] {
+        match self {
+            Object::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Object::Dict(d) | Object::Stream(d, _) => Some(d),
+            _ => None,
+        }
+    }
+}
+
+/// The kinds of annotation read as reviewer markups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+    /// Sticky note (`/Text`)
+    Note,
+    /// Text typed onto the page (`/FreeText`)
+    FreeText,
+    Highlight,
+    Underline,
+    /// Text struck out for deletion
+    StrikeOut,
+    /// Insertion point; the inserted text is the annotation's contents
+    Caret,
+    /// A caret grouped with a strike-out: the struck text is replaced by the
+    /// caret's contents
+    Replace,
+}
+
+/// One reviewer markup and the page text it covers
+#[derive(Debug, Clone)]
+pub struct Markup {
+    pub id: String,
+    pub kind: MarkupKind,
+    pub author: String,
+    pub date: Option<String>,
+    /// The note text, or the text inserted by a caret or replacement
+    pub contents: String,
+    /// 0-based page number
+    pub page: usize,
+    /// Page text under the markup; empty for notes and carets, which mark a point
+    pub marked_text: String,
+    pub context_before: String,
+    pub context_after: String,
+}
+
+/// Everything read from a marked-up PDF
+pub struct Markups {
+    pub markups: Vec<Markup>,
+    pub title: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Whether the data is a PDF file. Some producers put junk before the header,
+/// which readers tolerate within the first kilobyte.
+pub fn is_pdf(data: &[u8]) -> bool {
+    data[..data.len().min(1024)]
+        .windows(5)
+        .any(|w| w == b"%PDF-")
+}
+
+/// Parse a PDF on its own: one paragraph per markup, holding the text under it
+pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
+    let document = read(data)?;
+    let (paragraph_blocks, reviewers) =
+        matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, file_name);
+
+    Ok(CollateResult {
+        paragraphs: paragraph_blocks,
+        reviewers,
+        document_title: document.title,
+        error: None,
+        warnings: document.warnings,
+    })
+}
+
+/// Read a PDF's markups as paragraphs. Without a base document to anchor them
+/// to, each markup becomes a paragraph of the text it covers.
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    let Markups {
+        markups,
+        title,
+        warnings,
+    } = read_markups(data)?;
+    let mut paragraphs = Vec::new();
+    let mut comments = HashMap::new();
+
+    for markup in markups {
+        let mut paragraph = ParsedParagraph {
+            index: paragraphs.len(),
+            ..Default::default()
+        };
+        let deletion = || Segment::Deletion {
+            id: markup.id.clone(),
+            author: markup.author.clone(),
+            date: markup.date.clone(),
+            text: markup.marked_text.clone(),
+        };
+        let insertion = || Segment::Insertion {
+            id: markup.id.clone(),
+            author: markup.author.clone(),
+            date: markup.date.clone(),
+            text: markup.contents.clone(),
+        };
+        match markup.kind {
+            MarkupKind::StrikeOut => paragraph.segments.push(deletion()),
+            MarkupKind::Caret if !markup.contents.is_empty() => {
+                paragraph.segments.push(insertion())
+            }
+            MarkupKind::Replace => paragraph.segments.extend([deletion(), insertion()]),
+            _ => {
+                paragraph
+                    .segments
+                    .push(Segment::Stable(markup.marked_text.clone()));
+                paragraph.comment_ids.push(markup.id.clone());
+                paragraph
+                    .comment_anchor_texts
+                    .insert(markup.id.clone(), markup.marked_text.clone());
+                comments.insert(markup.id.clone(), comment(&markup));
+            }
+        }
+        paragraphs.push(paragraph);
+    }
+
+    Ok(ParsedDocument {
+        paragraphs,
+        comments,
+        title,
+        warnings,
+    })
+}
+
+/// The comment a note-like markup stands for
+pub fn comment(markup: &Markup) -> Comment {
+    Comment {
+        id: markup.id.clone(),
+        author: markup.author.clone(),
+        date: markup.date.clone(),
+        text: markup.contents.clone(),
+        anchor_text: markup.marked_text.clone(),
+        initials: None,
+        source_file: None,
+    }
+}
+
+/// Read every reviewer markup in the file, in page order
+pub fn read_markups(data: &[u8]) -> Result<Markups, String> {
+    if !is_pdf(data) {
+        return Err("Not a PDF file".to_string());
+    }
+    let pdf = Pdf::open(data);
+    if pdf.encrypted {
+        return Err(
+            "This PDF is encrypted — save an unprotected copy and upload it again".to_string(),
+        );
+    }
+    let catalog = pdf
+        .catalog()
+        .ok_or("PDF document catalog not found — the file is damaged")?;
+
+    let title = pdf
+        .trailer
+        .get("Info")
+        .map(|info| pdf.resolve(info))
+        .and_then(Object::as_dict)
+        .and_then(|info| pdf.text_string(info, "Title"))
+        .filter(|t| !t.trim().is_empty());
+
+    let mut warnings = Vec::new();
+    let mut markups: Vec<Annotation> = Vec::new();
+    let pages = pdf.pages(catalog);
+    for (page_number, page) in pages.iter().enumerate() {
+        let annotations: Vec<(Option<u32>, &Dict)> = pdf
+            .get(page.dict, "Annots")
+            .as_array()
+            .iter()
+            .filter_map(|a| {
+                let number = match a {
+                    Object::Ref(n) => Some(*n),
+                    _ => None,
+                };
+                pdf.resolve(a).as_dict().map(|d| (number, d))
+            })
+            .collect();
+        if annotations.is_empty() {
+            continue;
+        }
+
+        let (glyphs, unreadable) = pdf.page_glyphs(page);
+        if unreadable {
+            warnings.push(format!(
+                "Page {}: some page text could not be decoded",
+                page_number + 1
+            ));
+        }
+        let text = PageText::new(&glyphs);
+
+        for (k, (number, annotation)) in annotations.into_iter().enumerate() {
+            let kind = match pdf.get(annotation, "Subtype").as_name() {
+                Some("Text") => MarkupKind::Note,
+                Some("FreeText") => MarkupKind::FreeText,
+                Some("Highlight") => MarkupKind::Highlight,
+                Some("Underline") => MarkupKind::Underline,
+                Some("StrikeOut") => MarkupKind::StrikeOut,
+                Some("Caret") => MarkupKind::Caret,
+                Some("Squiggly") => {
+                    warnings.push(format!(
+                        "Page {}: squiggly underline read as a highlight",
+                        page_number + 1
+                    ));
+                    MarkupKind::Highlight
+                }
+                _ => continue,
+            };
+
+            let (marked_text, context_before, context_after) = match kind {
+                MarkupKind::Highlight | MarkupKind::Underline | MarkupKind::StrikeOut => {
+                    text.under(&pdf.quads(annotation))
+                }
+                _ => text.at(pdf.rect(annotation)),
+            };
+            let reply_to = match pdf.get(annotation, "IRT") {
+                Object::Ref(n) => Some((*n, pdf.get(annotation, "RT").as_name() == Some("Group"))),
+                _ => None,
+            };
+
+            let markup = Markup {
+                id: match number {
+                    Some(n) => format!("pdf-{}", n),
+                    None => format!("pdf-p{}-{}", page_number + 1, k),
+                },
+                kind,
+                author: pdf.text_string(annotation, "T").unwrap_or_default(),
+                date: pdf
+                    .text_string(annotation, "M")
+                    .or_else(|| pdf.text_string(annotation, "CreationDate"))
+                    .and_then(|d| pdf_date(&d)),
+                contents: pdf
+                    .text_string(annotation, "Contents")
+                    .unwrap_or_default()
+                    .replace("\r\n", "\n")
+                    .replace('\r', "\n"),
+                page: page_number,
+                marked_text,
+                context_before,
+                context_after,
+            };
+            markups.push(Annotation {
+                number,
+                markup,
+                reply_to,
+            });
+        }
+    }
+
+    Ok(Markups {
+        markups: group(markups),
+        title,
+        warnings,
+    })
+}
+
+/// A markup as read, before `/IRT` links are resolved
+struct Annotation {
+    /// Object number, by which replies refer to the annotation
+    number: Option<u32>,
+    markup: Markup,
+    /// Object number of the annotation this one answers (`/IRT`), and whether
+    /// the two form a group (`/RT /Group`) rather than a reply
+    reply_to: Option<(u32, bool)>,
+}
+
+/// Resolve `/IRT` links. A caret and a strike-out grouped together (`/RT
+/// /Group`) become one replacement; a reply takes its parent's position.
+fn group(markups: Vec<Annotation>) -> Vec<Markup> {
+    let index: HashMap<u32, usize> = markups
+        .iter()
+        .enumerate()
+        .filter_map(|(i, a)| a.number.map(|n| (n, i)))
+        .collect();
+    let mut result: Vec<Option<Markup>> = markups.iter().map(|a| Some(a.markup.clone())).collect();
+
+    for (i, annotation) in markups.iter().enumerate() {
+        let Some((parent_number, grouped)) = annotation.reply_to else {
+            continue;
+        };
+        let Some(&parent) = index.get(&parent_number) else {
+            continue;
+        };
+        let (Some(child), Some(target)) = (result[i].clone(), result[parent].as_mut()) else {
+            continue;
+        };
+        let kinds = (child.kind, target.kind);
+        if grouped
+            && matches!(
+                kinds,
+                (MarkupKind::StrikeOut, MarkupKind::Caret)
+                    | (MarkupKind::Caret, MarkupKind::StrikeOut)
+            )
+        {
+            let (struck, caret) = if child.kind == MarkupKind::StrikeOut {
+                (child, target.clone())
+            } else {
+                (target.clone(), child)
+            };
+            *target = Markup {
+                kind: MarkupKind::Replace,
+                contents: if caret.contents.is_empty() {
+                    struck.contents
+                } else {
+                    caret.contents
+                },
+                ..struck
+            };
+            result[i] = None;
+        } else if !grouped {
+            let (marked_text, context_before, context_after) = (
+                target.marked_text.clone(),
+                target.context_before.clone(),
+                target.context_after.clone(),
+            );
+            if let Some(reply) = result[i].as_mut() {
+                reply.kind = MarkupKind::Note;
+                reply.marked_text = marked_text;
+                reply.context_before = context_before;
+                reply.context_after = context_after;
+            }
+        }
+    }
+
+    result.into_iter().flatten().collect()
+}
+
+/// Convert a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`) to ISO 8601
+fn pdf_date(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("D:");
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let part =
+        |from: usize, default: &str| digits.get(from..from + 2).unwrap_or(default).to_string();
+    let offset = match value[digits.len()..].chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let rest: String = value[digits.len() + 1..]
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect();
+            format!(
+                "{}{}:{}",
+                sign,
+                rest.get(0..2).unwrap_or("00"),
+                rest.get(2..4).unwrap_or("00")
+            )
+        }
+        _ => "Z".to_string(),
+    };
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}{}",
+        &digits[..4],
+        part(4, "01"),
+        part(6, "01"),
+        part(8, "00"),
+        part(10, "00"),
+        part(12, "00"),
+        offset
+    ))
+}
+
+// ─── Objects ─────────────────────────────────────────────────────────
+
+/// The objects of a PDF file, keyed by object number
+struct Pdf {
+    objects: HashMap<u32, Object>,
+    /// The last trailer dictionary (or cross-reference stream dictionary)
+    trailer: Dict,
+    encrypted: bool,
+}
+
+struct Page<'a> {
+    dict: &'a Dict,
+    /// Resources, inherited from the page tree when the page has none
+    resources: Option<&'a Dict>,
+}
+
+impl Pdf {
+    fn open(data: &[u8]) -> Self {
+        let mut pdf = Pdf {
+            objects: HashMap::new(),
+            trailer: Dict::new(),
+            encrypted: false,
+        };
+        let mut trailers: Vec<(usize, Dict)> = Vec::new();
+
+        // Later definitions of an object (incremental updates) replace earlier
+        // ones. Headers inside stream data are not objects.
+        let mut stream_end = 0;
+        for (number, start) in object_headers(data) {
+            if start < stream_end {
+                continue;
+            }
+            let mut lexer = Lexer::new(data, start);
+            let Some(object) = lexer.object() else {
+                continue;
+            };
+            let object = match object {
+                Object::Dict(dict) if lexer.keyword_ahead(b"stream") => {
+                    let raw = lexer.stream_data(&dict);
+                    stream_end = lexer.pos;
+                    Object::Stream(dict, raw)
+                }
+                other => other,
+            };
+            if let Object::Stream(dict, _) = &object {
+                if dict.get("Type").and_then(Object::as_name) == Some("XRef") {
+                    trailers.push((start, dict.clone()));
+                }
+            }
+            pdf.objects.insert(number, object);
+        }
+
+        let mut search = 0;
+        while let Some(found) = find(data, b"trailer", search) {
+            let mut lexer = Lexer::new(data, found + b"trailer".len());
+            if let Some(Object::Dict(dict)) = lexer.object() {
+                trailers.push((found, dict));
+            }
+            search = found + 1;
+        }
+        trailers.sort_by_key(|(position, _)| *position);
+        pdf.encrypted = trailers.iter().any(|(_, t)| t.contains_key("Encrypt"));
+        // Later trailers only repeat what they change, so fold them in order
+        for (_, trailer) in trailers {
+            pdf.trailer.extend(trailer);
+        }
+
+        // Objects packed into object streams fill in whatever was not defined directly
+        let streams: Vec<(usize, usize, Vec<u8>)> = pdf
+            .objects
+            .values()
+            .filter_map(|object| match object {
+                Object::Stream(dict, _)
+                    if dict.get("Type").and_then(Object::as_name) == Some("ObjStm") =>
+                {
+                    Some((
+                        dict.get("N").and_then(Object::as_number)? as usize,
+                        dict.get("First").and_then(Object::as_number)? as usize,
+                        pdf.decode(object)?,
+                    ))
+                }
+                _ => None,
+            })
+            .collect();
+        for (count, first, data) in streams {
+            let mut header = Lexer::new(&data, 0);
+            let mut entries = Vec::new();
+            for _ in 0..count {
+                match (header.object(), header.object()) {
+                    (Some(Object::Number(number)), Some(Object::Number(offset))) => {
+                        entries.push((number as u32, offset as usize))
+                    }
+                    _ => break,
+                }
+            }
+            for (number, offset) in entries {
+                if let Some(object) = Lexer::new(&data, first + offset).object() {
+                    pdf.objects.entry(number).or_insert(object);
+                }
+            }
+        }
+
+        pdf
+    }
+
+    /// Follow references to the object they point at
+    fn resolve<'a>(&'a self, object: &'a Object) -> &'a Object {
+        let mut current = object;
+        for _ in 0..MAX_DEPTH {
+            match current {
+                Object::Ref(n) => current = self.objects.get(n).unwrap_or(&Object::Null),
+                _ => return current,
+            }
+        }
+        &Object::Null
+    }
+
+    fn get<'a>(&'a self, dict: &'a Dict, key: &str) -> &'a Object {
+        dict.get(key).map_or(&Object::Null, |o| self.resolve(o))
+    }
+
+    fn number(&self, dict: &Dict, key: &str) -> Option<f64> {
+        self.get(dict, key).as_number()
+    }
+
+    /// A text string: UTF-16BE or UTF-8 with a byte order mark, else PDFDocEncoding
+    fn text_string(&self, dict: &Dict, key: &str) -> Option<String> {
+        match self.get(dict, key) {
+            Object::Str(bytes) => Some(decode_text_string(bytes)),
+            _ => None,
+        }
+    }
+
+    fn catalog(&self) -> Option<&Dict> {
+        self.trailer
+            .get("Root")
+            .map(|root| self.resolve(root))
+            .and_then(Object::as_dict)
+            .or_else(|| {
+                self.objects
+                    .values()
+                    .filter_map(Object::as_dict)
+                    .find(|d| d.get("Type").and_then(Object::as_name) == Some("Catalog"))
+            })
+    }
+
+    /// Pages in document order, found by walking the page tree
+    fn pages<'a>(&'a self, catalog: &'a Dict) -> Vec<Page<'a>> {
+        let mut pages = Vec::new();
+        let mut stack: Vec<(&Dict, Option<&Dict>, usize)> = Vec::new();
+        if let Some(root) = self.get(catalog, "Pages").as_dict() {
+            stack.push((root, None, 0));
+        }
+        while let Some((node, inherited, depth)) = stack.pop() {
+            let resources = self.get(node, "Resources").as_dict().or(inherited);
+            match self.get(node, "Kids") {
+                Object::Array(kids) if depth < MAX_DEPTH => {
+                    for kid in kids.iter().rev() {
+                        if let Some(kid) = self.resolve(kid).as_dict() {
+                            stack.push((kid, resources, depth + 1));
+                        }
+                    }
+                }
+                Object::Array(_) => {}
+                _ => pages.push(Page {
+                    dict: node,
+                    resources,
+                }),
+            }
+        }
+        pages
+    }
+
+    /// Decoded stream data, or `None` for filters that are not supported
+    fn decode(&self, object: &Object) -> Option<Vec<u8>> {
+        let Object::Stream(dict, raw) = self.resolve(object) else {
+            return None;
+        };
+        let filters: Vec<&str> = match self.get(dict, "Filter") {
+            Object::Name(name) => vec![name.as_str()],
+            Object::Array(items) => items
+                .iter()
+                .filter_map(|f| self.resolve(f).as_name())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut data = raw.clone();
+        for filter in filters {
+            data = match filter {
+                "FlateDecode" | "Fl" => {
+                    // A truncated stream still yields the text before the damage
+                    let mut out = Vec::new();
+                    let _ = ZlibDecoder::new(data.as_slice()).read_to_end(&mut out);
+                    out
+                }
+                "ASCIIHexDecode" | "AHx" => hex_bytes(&data),
+                _ => return None,
+            };
+        }
+        Some(data)
+    }
+
+    /// Corner points of a markup's quadrilaterals, falling back to its `/Rect`
+    fn quads(&self, annotation: &Dict) -> Vec<Rect> {
+        let points: Vec<f64> = self
+            .get(annotation, "QuadPoints")
+            .as_array()
+            .iter()
+            .filter_map(|p| self.resolve(p).as_number())
+            .collect();
+        let quads: Vec<Rect> = points
+            .chunks_exact(8)
+            .map(|q| Rect::around(q.chunks_exact(2).map(|p| (p[0], p[1]))))
+            .collect();
+        if quads.is_empty() {
+            self.rect(annotation).into_iter().collect()
+        } else {
+            quads
+        }
+    }
+
+    fn rect(&self, annotation: &Dict) -> Option<Rect> {
+        let r: Vec<f64> = self
+            .get(annotation, "Rect")
+            .as_array()
+            .iter()
+            .filter_map(|n| self.resolve(n).as_number())
+            .collect();
+        (r.len() == 4).then(|| Rect::around([(r[0], r[1]), (r[2], r[3])].into_iter()))
+    }
+}
+
+/// Positions of `N G obj` headers, paired with the object number and the
+/// offset just past `obj`
+fn object_headers(data: &[u8]) -> Vec<(u32, usize)> {
+    let mut headers = Vec::new();
+    let mut search = 0;
+    while let Some(found) = find(data, b"obj", search) {
+        search = found + 3;
+        if data.get(found + 3).is_some_and(|&b| is_regular(b)) {
+            continue;
+        }
+        // Walk back over "<number> <generation> "
+        let mut i = found;
+        let mut fields = [(0, 0); 2];
+        let mut ok = true;
+        for field in fields.iter_mut().rev() {
+            let end = i;
+            while i > 0 && is_whitespace(data[i - 1]) {
+                i -= 1;
+            }
+            let digits_end = i;
+            while i > 0 && data[i - 1].is_ascii_digit() {
+                i -= 1;
+            }
+            if i == digits_end || digits_end == end {
+                ok = false;
+                break;
+            }
+            *field = (i, digits_end);
+        }
+        if !ok || (i > 0 && is_regular(data[i - 1])) {
+            continue;
+        }
+        let (from, to) = fields[0];
+        if let Some(number) = std::str::from_utf8(&data[from..to])
+            .ok()
+            .and_then(|s| s.parse().ok())
+        {
+            headers.push((number, found + 3));
+        }
+    }
+    headers
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn is_regular(b: u8) -> bool {
+    !is_whitespace(b) && !is_delimiter(b)
+}
+
+fn hex_bytes(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes.iter().map(|&b| pdf_doc_char(b)).collect()
+    }
+}
+
+/// PDFDocEncoding, which is Latin-1 apart from the 0x80–0xA0 block
+fn pdf_doc_char(byte: u8) -> char {
+    const HIGH: [char; 33] = [
+        '•', '†', '‡', '…', '—', '–', 'ƒ', '⁄', '‹', '›', '−', '‰', '„', '“', '”', '‘', '’', '‚',
+        '™', 'ﬁ', 'ﬂ', 'Ł', 'Œ', 'Š', 'Ÿ', 'Ž', 'ı', 'ł', 'œ', 'š', 'ž', '\u{FFFD}', '€',
+    ];
+    match byte {
+        0x80..=0xA0 => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+// ─── Lexer ───────────────────────────────────────────────────────────
+
+enum Token {
+    Object(Object),
+    /// Operator or other bare keyword
+    Keyword(String),
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Lexer { data, pos }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek() {
+            if is_whitespace(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while self.peek().is_some_and(|b| b != b'\r' && b != b'\n') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Whether the next token is the given keyword (not consumed)
+    fn keyword_ahead(&mut self, keyword: &[u8]) -> bool {
+        self.skip_whitespace();
+        self.data
+            .get(self.pos..)
+            .is_some_and(|d| d.starts_with(keyword))
+    }
+
+    /// Raw bytes of the stream starting at the `stream` keyword. `/Length` is
+    /// trusted only when `endstream` follows it; otherwise the data runs to the
+    /// next `endstream`.
+    fn stream_data(&mut self, dict: &Dict) -> Vec<u8> {
+        self.pos += b"stream".len();
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        if let Some(length) = dict.get("Length").and_then(Object::as_number) {
+            let end = start.saturating_add(length as usize);
+            let mut after = Lexer::new(self.data, end.min(self.data.len()));
+            if end <= self.data.len() && after.keyword_ahead(b"endstream") {
+                self.pos = end;
+                return self.data[start..end].to_vec();
+            }
+        }
+        let mut end = find(self.data, b"endstream", start).unwrap_or(self.data.len());
+        self.pos = end;
+        if end > start && self.data[end - 1] == b'\n' {
+            end -= 1;
+        }
+        if end > start && self.data[end - 1] == b'\r' {
+            end -= 1;
+        }
+        self.data[start..end].to_vec()
+    }
+
+    /// The next object, or `None` at a keyword, a closing delimiter or the end
+    fn object(&mut self) -> Option<Object> {
+        let start = self.pos;
+        match self.token(0)? {
+            Token::Object(object) => Some(object),
+            Token::Keyword(_) => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    fn token(&mut self, depth: usize) -> Option<Token> {
+        self.skip_whitespace();
+        let b = self.peek()?;
+        let object = match b {
+            b'/' => {
+                self.pos += 1;
+                Object::Name(self.name())
+            }
+            b'(' => {
+                self.pos += 1;
+                Object::Str(self.literal_string())
+            }
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut dict = Dict::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b'>') => {
+                            self.pos = (self.pos + 2).min(self.data.len());
+                            break;
+                        }
+                        Some(b'/') => {
+                            self.pos += 1;
+                            let key = self.name();
+                            match self.token(depth + 1) {
+                                Some(Token::Object(value)) => {
+                                    dict.insert(key, value);
+                                }
+                                Some(Token::Keyword(_)) => {}
+                                None => break,
+                            }
+                        }
+                        Some(_) if depth < MAX_DEPTH => {
+                            // Stray token inside a dictionary: skip it
+                            self.token(depth + 1)?;
+                        }
+                        _ => break,
+                    }
+                }
+                Object::Dict(dict)
+            }
+            b'<' => {
+                self.pos += 1;
+                let end = find(self.data, b">", self.pos).unwrap_or(self.data.len());
+                let bytes = hex_bytes(&self.data[self.pos..end]);
+                self.pos = (end + 1).min(self.data.len());
+                Object::Str(bytes)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(_) if depth < MAX_DEPTH => match self.token(depth + 1) {
+                            Some(Token::Object(item)) => items.push(item),
+                            Some(Token::Keyword(_)) => {}
+                            None => break,
+                        },
+                        _ => break,
+                    }
+                }
+                Object::Array(items)
+            }
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.peek().is_some_and(|b| b.is_ascii_digit() || b == b'.') {
+                    self.pos += 1;
+                }
+                let text = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or("0");
+                let number: f64 = text.parse().unwrap_or(0.0);
+                if text.bytes().all(|b| b.is_ascii_digit()) {
+                    if let Some(reference) = self.reference(number as u32) {
+                        return Some(Token::Object(reference));
+                    }
+                }
+                Object::Number(number)
+            }
+            b')' | b'>' | b']' | b'{' | b'}' => {
+                self.pos += 1;
+                return Some(Token::Keyword((b as char).to_string()));
+            }
+            _ => {
+                let start = self.pos;
+                while self.peek().is_some_and(is_regular) {
+                    self.pos += 1;
+                }
+                let word = String::from_utf8_lossy(&self.data[start..self.pos]).into_owned();
+                return Some(match word.as_str() {
+                    "true" | "false" => Token::Object(Object::Bool),
+                    "null" => Token::Object(Object::Null),
+                    _ => Token::Keyword(word),
+                });
+            }
+        };
+        Some(Token::Object(object))
+    }
+
+    /// `G R` after an object number makes an indirect reference
+    fn reference(&mut self, number: u32) -> Option<Object> {
+        let save = self.pos;
+        self.skip_whitespace();
+        let generation_start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos > generation_start {
+            self.skip_whitespace();
+            if self.peek() == Some(b'R')
+                && !self.data.get(self.pos + 1).is_some_and(|&b| is_regular(b))
+            {
+                self.pos += 1;
+                return Some(Object::Ref(number));
+            }
+        }
+        self.pos = save;
+        None
+    }
+
+    fn name(&mut self) -> String {
+        let mut bytes = Vec::new();
+        while let Some(b) = self.peek().filter(|&b| is_regular(b)) {
+            self.pos += 1;
+            if b == b'#' {
+                let hex = self.data.get(self.pos..self.pos + 2).unwrap_or_default();
+                if let Some(value) = std::str::from_utf8(hex)
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                {
+                    bytes.push(value);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            bytes.push(b);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    bytes.push(b);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    bytes.push(b);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0C),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(value as u8);
+                        }
+                        // A backslash at the end of a line continues the string
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => bytes.push(other),
+                    }
+                }
+                _ => bytes.push(b),
+            }
+        }
+        bytes
+    }
+}
+
+// ─── Page text ───────────────────────────────────────────────────────
+
+/// An axis-aligned box in default user space
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl Rect {
+    fn around(points: impl Iterator<Item = (f64, f64)>) -> Rect {
+        let mut rect = Rect {
+            x0: f64::MAX,
+            y0: f64::MAX,
+            x1: f64::MIN,
+            y1: f64::MIN,
+        };
+        for (x, y) in points {
+            rect.x0 = rect.x0.min(x);
+            rect.y0 = rect.y0.min(y);
+            rect.x1 = rect.x1.max(x);
+            rect.y1 = rect.y1.max(y);
+        }
+        rect
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        ((self.x0 + self.x1) / 2.0, (self.y0 + self.y1) / 2.0)
+    }
+
+    fn height(&self) -> f64 {
+        self.y1 - self.y0
+    }
+}
+
+/// One shown character code, with the text it maps to and where it was drawn
+struct Glyph {
+    text: String,
+    rect: Rect,
+    /// Font size in user space, used to judge gaps between glyphs
+    size: f64,
+}
+
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn apply(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+/// What is needed of a font to turn shown strings into positioned text
+struct Font {
+    /// Composite (Type0) fonts use two-byte codes
+    two_byte: bool,
+    to_unicode: HashMap<u32, String>,
+    /// Glyph widths in thousandths of an em
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+}
+
+/// Graphics state parameters that `q`/`Q` save and restore
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Matrix,
+    font: Option<String>,
+    size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    /// Horizontal scaling as a fraction (`Tz` / 100)
+    scale: f64,
+    leading: f64,
+    rise: f64,
+}
+
+impl Pdf {
+    /// Glyphs drawn by a page's content streams, in drawing order, and whether
+    /// any content could not be decoded
+    fn page_glyphs(&self, page: &Page) -> (Vec<Glyph>, bool) {
+        let mut content = Vec::new();
+        let mut unreadable = false;
+        let streams: Vec<&Object> = match self.get(page.dict, "Contents") {
+            Object::Array(items) => items.iter().collect(),
+            Object::Null => Vec::new(),
+            _ => vec![page.dict.get("Contents").unwrap_or(&Object::Null)],
+        };
+        for stream in streams {
+            match self.decode(stream) {
+                Some(data) => {
+                    content.extend(data);
+                    content.push(b'\n');
+                }
+                None => unreadable = true,
+            }
+        }
+
+        let mut fonts: HashMap<String, Font> = HashMap::new();
+        let mut state = GraphicsState {
+            ctm: IDENTITY,
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        };
+        let mut saved: Vec<GraphicsState> = Vec::new();
+        let (mut tm, mut tlm) = (IDENTITY, IDENTITY);
+        let mut glyphs = Vec::new();
+        let mut operands: Vec<Object> = Vec::new();
+        let mut lexer = Lexer::new(&content, 0);
+
+        while let Some(token) = lexer.token(0) {
+            let operator = match token {
+                Token::Object(object) => {
+                    operands.push(object);
+                    continue;
+                }
+                Token::Keyword(operator) => operator,
+            };
+            let number = |i: usize| operands.get(i).and_then(Object::as_number).unwrap_or(0.0);
+            let matrix = || -> Matrix { std::array::from_fn(number) };
+
+            match operator.as_str() {
+                "q" => saved.push(state.clone()),
+                "Q" => {
+                    if let Some(previous) = saved.pop() {
+                        state = previous;
+                    }
+                }
+                "cm" => state.ctm = multiply(&matrix(), &state.ctm),
+                "BT" => {
+                    tm = IDENTITY;
+                    tlm = IDENTITY;
+                }
+                "Tf" => {
+                    let name = operands
+                        .first()
+                        .and_then(Object::as_name)
+                        .map(str::to_string);
+                    if let Some(name) = &name {
+                        if !fonts.contains_key(name) {
+                            let font = self.font(page.resources, name);
+                            fonts.insert(name.clone(), font);
+                        }
+                    }
+                    state.font = name;
+                    state.size = number(1);
+                }
+                "Tc" => state.char_spacing = number(0),
+                "Tw" => state.word_spacing = number(0),
+                "Tz" => state.scale = number(0) / 100.0,
+                "TL" => state.leading = number(0),
+                "Ts" => state.rise = number(0),
+                "Td" | "TD" => {
+                    if operator == "TD" {
+                        state.leading = -number(1);
+                    }
+                    tlm = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &tlm);
+                    tm = tlm;
+                }
+                "Tm" => {
+                    tlm = matrix();
+                    tm = tlm;
+                }
+                "T*" | "'" | "\"" => {
+                    if operator == "\"" {
+                        state.word_spacing = number(0);
+                        state.char_spacing = number(1);
+                    }
+                    tlm = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &tlm);
+                    tm = tlm;
+                    if let Some(Object::Str(bytes)) = operands.last() {
+                        let font = state.font.as_ref().and_then(|f| fonts.get(f));
+                        show(bytes, font, &state, &mut tm, &mut glyphs);
+                    }
+                }
+                "Tj" | "TJ" => {
+                    let font = state.font.as_ref().and_then(|f| fonts.get(f));
+                    let parts: Vec<&Object> = match operands.last() {
+                        Some(Object::Array(items)) => items.iter().collect(),
+                        Some(other) => vec![other],
+                        None => Vec::new(),
+                    };
+                    for part in parts {
+                        match part {
+                            Object::Str(bytes) => show(bytes, font, &state, &mut tm, &mut glyphs),
+                            Object::Number(adjust) => {
+                                let tx = -adjust / 1000.0 * state.size * state.scale;
+                                tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &tm);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                // Inline image data is binary; skip to its end marker
+                "ID" => {
+                    let mut search = lexer.pos + 1;
+                    lexer.pos = content.len();
+                    while let Some(found) = find(&content, b"EI", search) {
+                        let before = found == 0 || is_whitespace(content[found - 1]);
+                        let after = content.get(found + 2).is_none_or(|&b| is_whitespace(b));
+                        if before && after {
+                            lexer.pos = found + 2;
+                            break;
+                        }
+                        search = found + 2;
+                    }
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+
+        (glyphs, unreadable)
+    }
+
+    /// Load a font from the page resources; unknown fonts get one-byte codes
+    /// and a half-em width
+    fn font(&self, resources: Option<&Dict>, name: &str) -> Font {
+        let dict = resources
+            .and_then(|r| self.get(r, "Font").as_dict())
+            .and_then(|fonts| self.get(fonts, name).as_dict());
+        let mut font = Font {
+            two_byte: false,
+            to_unicode: HashMap::new(),
+            widths: HashMap::new(),
+            default_width: 500.0,
+        };
+        let Some(dict) = dict else {
+            return font;
+        };
+
+        if self.get(dict, "Subtype").as_name() == Some("Type0") {
+            font.two_byte = true;
+            font.default_width = 1000.0;
+            let descendant = self
+                .get(dict, "DescendantFonts")
+                .as_array()
+                .first()
+                .and_then(|d| self.resolve(d).as_dict());
+            if let Some(descendant) = descendant {
+                if let Some(dw) = self.number(descendant, "DW") {
+                    font.default_width = dw;
+                }
+                // Entries are either `c [w1 w2 ...]` or `c_first c_last w`
+                let w: Vec<&Object> = self
+                    .get(descendant, "W")
+                    .as_array()
+                    .iter()
+                    .map(|o| self.resolve(o))
+                    .collect();
+                let mut i = 0;
+                while i + 1 < w.len() {
+                    let first = w[i].as_number().unwrap_or(0.0) as u32;
+                    match w[i + 1] {
+                        Object::Array(widths) => {
+                            for (k, width) in widths.iter().enumerate() {
+                                if let Some(width) = self.resolve(width).as_number() {
+                                    font.widths.insert(first + k as u32, width);
+                                }
+                            }
+                            i += 2;
+                        }
+                        last => {
+                            let last = last.as_number().unwrap_or(0.0) as u32;
+                            let width = w.get(i + 2).and_then(|o| o.as_number()).unwrap_or(0.0);
+                            for code in first..=last.min(first + 0xFFFF) {
+                                font.widths.insert(code, width);
+                            }
+                            i += 3;
+                        }
+                    }
+                }
+            }
+        } else {
+            let first = self.number(dict, "FirstChar").unwrap_or(0.0) as u32;
+            for (k, width) in self.get(dict, "Widths").as_array().iter().enumerate() {
+                if let Some(width) = self.resolve(width).as_number() {
+                    font.widths.insert(first + k as u32, width);
+                }
+            }
+            if let Some(missing) = self
+                .get(dict, "FontDescriptor")
+                .as_dict()
+                .and_then(|d| self.number(d, "MissingWidth"))
+            {
+                font.default_width = missing;
+            }
+        }
+
+        if let Some(cmap) = dict.get("ToUnicode").and_then(|c| self.decode(c)) {
+            font.to_unicode = to_unicode(&cmap);
+        }
+        font
+    }
+}
+
+/// Lay out one shown string, advancing the text matrix
+fn show(
+    bytes: &[u8],
+    font: Option<&Font>,
+    state: &GraphicsState,
+    tm: &mut Matrix,
+    glyphs: &mut Vec<Glyph>,
+) {
+    let two_byte = font.is_some_and(|f| f.two_byte);
+    let codes: Vec<u32> = if two_byte {
+        bytes
+            .chunks(2)
+            .map(|c| (c[0] as u32) << 8 | c.get(1).copied().unwrap_or(0) as u32)
+            .collect()
+    } else {
+        bytes.iter().map(|&b| b as u32).collect()
+    };
+
+    for code in codes {
+        let text = match font.and_then(|f| f.to_unicode.get(&code)) {
+            Some(text) => text.clone(),
+            None if two_byte => "\u{FFFD}".to_string(),
+            None => char::from_u32(doc::cp1252(code as u8) as u32)
+                .unwrap_or('\u{FFFD}')
+                .to_string(),
+        };
+        let width = font
+            .map(|f| f.widths.get(&code).copied().unwrap_or(f.default_width))
+            .unwrap_or(500.0)
+            / 1000.0;
+
+        let trm = multiply(
+            &multiply(
+                &[
+                    state.size * state.scale,
+                    0.0,
+                    0.0,
+                    state.size,
+                    0.0,
+                    state.rise,
+                ],
+                tm,
+            ),
+            &state.ctm,
+        );
+        let corners = [
+            apply(&trm, 0.0, -0.2),
+            apply(&trm, width, 0.8),
+            apply(&trm, 0.0, 0.8),
+            apply(&trm, width, -0.2),
+        ];
+        let rect = Rect::around(corners.into_iter());
+        glyphs.push(Glyph {
+            text,
+            rect,
+            size: rect.height(),
+        });
+
+        let spacing = if !two_byte && code == 32 {
+            state.word_spacing
+        } else {
+            0.0
+        };
+        let tx = (width * state.size + state.char_spacing + spacing) * state.scale;
+        *tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], tm);
+    }
+}
+
+/// Parse the `bfchar` and `bfrange` mappings of a ToUnicode CMap
+fn to_unicode(cmap: &[u8]) -> HashMap<u32, String> {
+    let mut map = HashMap::new();
+    let mut lexer = Lexer::new(cmap, 0);
+    let mut operands: Vec<Object> = Vec::new();
+    let code = |bytes: &[u8]| bytes.iter().fold(0u32, |acc, &b| acc << 8 | b as u32);
+    let utf16 = |bytes: &[u8]| {
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    while let Some(token) = lexer.token(0) {
+        match token {
+            Token::Object(object) => operands.push(object),
+            Token::Keyword(keyword) => {
+                match keyword.as_str() {
+                    "endbfchar" => {
+                        for pair in operands.chunks_exact(2) {
+                            if let (Object::Str(src), Object::Str(dst)) = (&pair[0], &pair[1]) {
+                                map.insert(code(src), utf16(dst));
+                            }
+                        }
+                    }
+                    "endbfrange" => {
+                        for range in operands.chunks_exact(3) {
+                            let (Object::Str(lo), Object::Str(hi)) = (&range[0], &range[1]) else {
+                                continue;
+                            };
+                            let (lo, hi) = (code(lo), code(hi).min(code(lo) + 0xFFFF));
+                            match &range[2] {
+                                Object::Str(dst) if !dst.is_empty() => {
+                                    // Ranges increment the last UTF-16 unit
+                                    let mut units: Vec<u16> = dst
+                                        .chunks(2)
+                                        .map(|c| {
+                                            u16::from_be_bytes([
+                                                c[0],
+                                                c.get(1).copied().unwrap_or(0),
+                                            ])
+                                        })
+                                        .collect();
+                                    for c in lo..=hi {
+                                        map.insert(c, String::from_utf16_lossy(&units));
+                                        if let Some(last) = units.last_mut() {
+                                            *last = last.wrapping_add(1);
+                                        }
+                                    }
+                                }
+                                Object::Array(items) => {
+                                    for (c, item) in (lo..=hi).zip(items) {
+                                        if let Object::Str(dst) = item {
+                                            map.insert(c, utf16(dst));
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                operands.clear();
+            }
+        }
+    }
+    map
+}
+
+/// A page's text as one string, with the span of each glyph in it
+struct PageText<'a> {
+    glyphs: &'a [Glyph],
+    chars: Vec<char>,
+    /// Character range of each glyph in `chars`
+    spans: Vec<(usize, usize)>,
+}
+
+impl<'a> PageText<'a> {
+    /// Join glyphs in drawing order. A space goes between glyphs on different
+    /// lines or separated by a visible gap, unless one is already there.
+    fn new(glyphs: &'a [Glyph]) -> Self {
+        let mut chars: Vec<char> = Vec::new();
+        let mut spans = Vec::with_capacity(glyphs.len());
+        let mut previous: Option<&Glyph> = None;
+        for glyph in glyphs {
+            if let Some(prev) = previous {
+                let (_, y) = glyph.rect.centre();
+                let (_, prev_y) = prev.rect.centre();
+                let new_line = (y - prev_y).abs() > prev.size.min(glyph.size) / 2.0;
+                let gap = glyph.rect.x0 - prev.rect.x1 > glyph.size * 0.15;
+                let spaced = chars.last().is_some_and(|c| c.is_whitespace())
+                    || glyph.text.starts_with(char::is_whitespace);
+                if (new_line || gap) && !spaced {
+                    chars.push(' ');
+                }
+            }
+            let start = chars.len();
+            chars.extend(glyph.text.chars());
+            spans.push((start, chars.len()));
+            previous = Some(glyph);
+        }
+        PageText {
+            glyphs,
+            chars,
+            spans,
+        }
+    }
+
+    /// Text between two character positions with whitespace runs collapsed.
+    /// Spaces at either end are kept so contexts show where words break.
+    fn slice(&self, from: usize, to: usize) -> String {
+        let mut text = String::new();
+        for &c in &self.chars[from.min(to)..to] {
+            if !c.is_whitespace() {
+                text.push(c);
+            } else if !text.ends_with(' ') {
+                text.push(' ');
+            }
+        }
+        text
+    }
+
+    fn context(&self, from: usize, to: usize) -> (String, String) {
+        let before = self.slice(from.saturating_sub(CONTEXT_CHARS), from);
+        let after = self.slice(to, (to + CONTEXT_CHARS).min(self.chars.len()));
+        (before, after)
+    }
+
+    /// Text of the glyphs whose centres fall inside the quads, as one run from
+    /// the first such glyph to the last, with the text around it
+    fn under(&self, quads: &[Rect]) -> (String, String, String) {
+        let inside: Vec<usize> = self
+            .glyphs
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| {
+                let (x, y) = g.rect.centre();
+                quads
+                    .iter()
+                    .any(|q| x >= q.x0 && x <= q.x1 && y >= q.y0 && y <= q.y1)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let (Some(&first), Some(&last)) = (inside.first(), inside.last()) else {
+            return Default::default();
+        };
+        let (from, to) = (self.spans[first].0, self.spans[last].1);
+        let (before, after) = self.context(from, to);
+        (self.slice(from, to).trim().to_string(), before, after)
+    }
+
+    /// The point in the text nearest a note or caret: the closer edge of the
+    /// nearest glyph on the same line, or of the nearest glyph overall
+    fn at(&self, rect: Option<Rect>) -> (String, String, String) {
+        let Some(rect) = rect else {
+            return Default::default();
+        };
+        let (x, y) = rect.centre();
+        let distance = |g: &Glyph| {
+            let dx = if x < g.rect.x0 {
+                g.rect.x0 - x
+            } else if x > g.rect.x1 {
+                x - g.rect.x1
+            } else {
+                0.0
+            };
+            let dy = if y < g.rect.y0 {
+                g.rect.y0 - y
+            } else if y > g.rect.y1 {
+                y - g.rect.y1
+            } else {
+                0.0
+            };
+            // Prefer glyphs on the same line over closer ones above or below
+            dx + dy * 10.0
+        };
+        let nearest = self
+            .glyphs
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)));
+        let Some((i, glyph)) = nearest else {
+            return Default::default();
+        };
+        let (centre_x, _) = glyph.rect.centre();
+        let point = if x < centre_x {
+            self.spans[i].0
+        } else {
+            self.spans[i].1
+        };
+        let (before, after) = self.context(point, point);
+        (String::new(), before, after)
+    }
+}
@@ -0,0 +1,754 @@
+//! Rich Text Format documents.
+//!
+//! Revision marks are character properties (`\revised`, `\deleted`,
+//! `\revauth`/`\revauthdel` into `\revtbl`, `\revdttm`/`\revdttmdel`).
+//! Comments are `\annotation` destinations, anchored on the range between
+//! `\atrfstart` and `\atrfend` with the same tag.
+
+use std::collections::HashMap;
+
+use crate::doc::{self, CharRevision, Mark};
+use crate::matcher;
+use crate::paragraphs::{self, ParsedDocument, ParsedParagraph};
+use crate::track_changes::Segment;
+use crate::types::{BlockKind, CollateResult, Comment, HeaderFooterType, TableCoordinates};
+
+/// An RTF file starts with its version group
+pub fn is_rtf(data: &[u8]) -> bool {
+    data.strip_prefix(b"\xEF\xBB\xBF")
+        .unwrap_or(data)
+        .starts_with(b"{\\rtf")
+}
+
+/// Parse an .rtf file from raw bytes
+pub fn parse(data: &[u8], file_name: &str) -> Result<CollateResult, String> {
+    let document = read(data)?;
+    let (paragraph_blocks, reviewers) =
+        matcher::build_paragraph_blocks(&document.paragraphs, &document.comments, file_name);
+
+    Ok(CollateResult {
+        paragraphs: paragraph_blocks,
+        reviewers,
+        document_title: document.title,
+        error: None,
+        warnings: document.warnings,
+    })
+}
+
+/// Read an .rtf file into parsed paragraphs, comments and title. Headers and
+/// footers follow the body, then footnotes and endnotes, as for .docx.
+pub fn read(data: &[u8]) -> Result<ParsedDocument, String> {
+    if !is_rtf(data) {
+        return Err("Not an RTF document — the file does not start with {\\rtf".to_string());
+    }
+    let mut parser = Parser::new();
+    let mut tokens = Tokens { data, pos: 0 };
+    while let Some(token) = tokens.next() {
+        parser.token(token);
+    }
+    Ok(parser.finish())
+}
+
+/// One lexical element of RTF
+enum Token<'d> {
+    GroupStart,
+    GroupEnd,
+    /// A control word and its numeric parameter
+    Control(&'d str, Option<i32>),
+    /// A control symbol such as `\~` or `\*`
+    Symbol(u8),
+    /// A character written as `\'hh`
+    Hex(u8),
+    Text(u8),
+}
+
+struct Tokens<'d> {
+    data: &'d [u8],
+    pos: usize,
+}
+
+impl<'d> Tokens<'d> {
+    fn next(&mut self) -> Option<Token<'d>> {
+        loop {
+            let byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            return Some(match byte {
+                b'{' => Token::GroupStart,
+                b'}' => Token::GroupEnd,
+                // Line breaks in the source carry no meaning
+                b'\r' | b'\n' => continue,
+                b'\\' => self.control()?,
+                b => Token::Text(b),
+            });
+        }
+    }
+
+    fn control(&mut self) -> Option<Token<'d>> {
+        let first = *self.data.get(self.pos)?;
+        if !first.is_ascii_alphabetic() {
+            self.pos += 1;
+            if first == b'\'' {
+                let hex = self.data.get(self.pos..self.pos + 2)?;
+                self.pos += 2;
+                let value = std::str::from_utf8(hex)
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())?;
+                return Some(Token::Hex(value));
+            }
+            // A backslash before a line break is a paragraph mark
+            if first == b'\r' || first == b'\n' {
+                return Some(Token::Control("par", None));
+            }
+            return Some(Token::Symbol(first));
+        }
+
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_alphabetic())
+        {
+            self.pos += 1;
+        }
+        let word = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or("");
+        let digits_start = self.pos;
+        if self.data.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        while self.data.get(self.pos).is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let parameter = std::str::from_utf8(&self.data[digits_start..self.pos])
+            .ok()
+            .and_then(|p| p.parse::<i64>().ok())
+            .map(|p| p as i32);
+        // A single space delimits the control word and is not text
+        if self.data.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+        // Binary data is skipped unread
+        if word == "bin" {
+            self.pos += parameter.unwrap_or(0).max(0) as usize;
+        }
+        Some(Token::Control(word, parameter))
+    }
+}
+
+/// Where the text of a group goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Destination {
+    /// The current story (body, header, note or annotation)
+    Text,
+    Skip,
+    /// `\info`: metadata, of which only the title is read
+    Info,
+    RevisionTable,
+    Title,
+    /// A short value collected as text and used when its group closes
+    Field(FieldKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldKind {
+    AnnotationInitials,
+    AnnotationAuthor,
+    AnnotationTag,
+    AnnotationDate,
+    AnchorStart,
+    AnchorEnd,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    destination: Destination,
+    /// Character revision properties, scoped to the group like other formatting
+    revision: CharRevision,
+    /// `\ucN`: fallback characters following each `\uN`
+    unicode_skip: usize,
+    /// `\intbl`: the paragraph is inside a table
+    in_table: bool,
+    /// Set by `\*`: the next control word starts an optional destination
+    optional: bool,
+}
+
+/// Position in the table a story is currently inside
+struct TableCursor {
+    index: usize,
+    row: usize,
+    column: usize,
+}
+
+/// Paragraphs being collected for one story
+struct Story {
+    kind: BlockKind,
+    /// Group depth at which the story's destination opened
+    depth: usize,
+    paragraphs: Vec<ParsedParagraph>,
+    runs: Vec<(Mark, String)>,
+    comment_ids: Vec<String>,
+    comment_anchor_texts: HashMap<String, String>,
+    footnote_refs: Vec<String>,
+    endnote_refs: Vec<String>,
+    table: Option<TableCursor>,
+    /// `\trhdr` in the current row's definition (which precedes its cells)
+    header_row: bool,
+    section: Option<usize>,
+    header_footer_type: Option<HeaderFooterType>,
+}
+
+impl Story {
+    fn new(kind: BlockKind, depth: usize) -> Self {
+        Story {
+            kind,
+            depth,
+            paragraphs: Vec::new(),
+            runs: Vec::new(),
+            comment_ids: Vec::new(),
+            comment_anchor_texts: HashMap::new(),
+            footnote_refs: Vec::new(),
+            endnote_refs: Vec::new(),
+            table: None,
+            header_row: false,
+            section: None,
+            header_footer_type: None,
+        }
+    }
+
+    fn push(&mut self, mark: Mark, text: &str) {
+        match self.runs.last_mut() {
+            Some((last, run)) if *last == mark => run.push_str(text),
+            _ => self.runs.push((mark, text.to_string())),
+        }
+    }
+
+    /// Index the paragraph being collected will get if it has content
+    fn next_index(&self) -> usize {
+        self.paragraphs.len()
+    }
+}
+
+/// An annotation whose `\annotation` group is being read
+#[derive(Default)]
+struct PendingAnnotation {
+    initials: Option<String>,
+    author: String,
+    tag: Option<String>,
+    date: Option<String>,
+}
+
+struct Parser {
+    groups: Vec<Group>,
+    stories: Vec<Story>,
+    /// Finished header, footer and note stories, in document order
+    finished: Vec<Story>,
+    authors: Vec<String>,
+    title: Option<String>,
+    field: String,
+    /// Fallback characters still to skip after a `\u`
+    skip: usize,
+    /// High surrogate waiting for its pair
+    high_surrogate: Option<u16>,
+    annotation: PendingAnnotation,
+    comments: HashMap<String, Comment>,
+    /// Anchor text collected per `\atrfstart` tag; open ones are still growing
+    anchors: HashMap<String, String>,
+    open_anchors: Vec<String>,
+    sections: usize,
+    tables: usize,
+    footnotes: usize,
+    endnotes: usize,
+    revision_ids: usize,
+    skipped_shapes: bool,
+    skipped_format_changes: bool,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Parser {
+            groups: vec![Group {
+                destination: Destination::Text,
+                revision: CharRevision::default(),
+                unicode_skip: 1,
+                in_table: false,
+                optional: false,
+            }],
+            stories: vec![Story::new(BlockKind::Body, 0)],
+            finished: Vec::new(),
+            authors: Vec::new(),
+            title: None,
+            field: String::new(),
+            skip: 0,
+            high_surrogate: None,
+            annotation: PendingAnnotation::default(),
+            comments: HashMap::new(),
+            anchors: HashMap::new(),
+            open_anchors: Vec::new(),
+            sections: 0,
+            tables: 0,
+            footnotes: 0,
+            endnotes: 0,
+            revision_ids: 0,
+            skipped_shapes: false,
+            skipped_format_changes: false,
+        }
+    }
+
+    fn group(&mut self) -> &mut Group {
+        self.groups
+            .last_mut()
+            .expect("the document group is never popped")
+    }
+
+    fn token(&mut self, token: Token) {
+        match token {
+            Token::GroupStart => {
+                let mut group = self.group().clone();
+                group.optional = false;
+                self.groups.push(group);
+                self.skip = 0;
+            }
+            Token::GroupEnd => self.end_group(),
+            Token::Symbol(b'*') => self.group().optional = true,
+            Token::Symbol(symbol) => {
+                let text = match symbol {
+                    b'\\' | b'{' | b'}' => (symbol as char).to_string(),
+                    b'~' => "\u{a0}".to_string(),
+                    b'_' => "-".to_string(),
+                    _ => return,
+                };
+                self.text(&text);
+            }
+            Token::Hex(byte) => {
+                if self.skip > 0 {
+                    self.skip -= 1;
+                } else if let Some(c) = char::from_u32(doc::cp1252(byte) as u32) {
+                    self.text(&c.to_string());
+                }
+            }
+            Token::Text(byte) => {
+                if self.skip > 0 {
+                    self.skip -= 1;
+                } else if byte.is_ascii() {
+                    self.text(&(byte as char).to_string());
+                } else if let Some(c) = char::from_u32(doc::cp1252(byte) as u32) {
+                    self.text(&c.to_string());
+                }
+            }
+            Token::Control(word, parameter) => self.control(word, parameter),
+        }
+    }
+
+    fn control(&mut self, word: &str, parameter: Option<i32>) {
+        let optional = std::mem::take(&mut self.group().optional);
+        match self.group().destination {
+            Destination::Skip => return,
+            Destination::Info if word != "title" => return,
+            _ => {}
+        }
+        if self.skip > 0 && word != "u" {
+            self.skip -= 1;
+            return;
+        }
+        let value = parameter.unwrap_or(1);
+        let depth = self.groups.len();
+        match word {
+            // Destinations
+            "fonttbl" | "colortbl" | "stylesheet" | "listtable" | "listoverridetable" | "pict"
+            | "fldinst" | "listtext" | "pntext" | "nonshppict" | "themedata"
+            | "colorschememapping" | "datastore" | "latentstyles" | "xmlnstbl" | "rsidtbl"
+            | "generator" | "mmathPr" | "bkmkstart" | "bkmkend" | "object" | "userprops"
+            | "docvar" | "atnparent" | "atnicn" => self.group().destination = Destination::Skip,
+            "shp" | "shpgrp" => {
+                self.skipped_shapes = true;
+                self.group().destination = Destination::Skip;
+            }
+            "oldcprops" | "oldpprops" | "oldtprops" | "oldsprops" => {
+                self.skipped_format_changes = true;
+                self.group().destination = Destination::Skip;
+            }
+            "revtbl" => {
+                self.group().destination = Destination::RevisionTable;
+                self.field.clear();
+            }
+            "info" => self.group().destination = Destination::Info,
+            "title" => {
+                self.group().destination = Destination::Title;
+                self.field.clear();
+            }
+            "atnid" => self.field_destination(FieldKind::AnnotationInitials),
+            "atnauthor" => self.field_destination(FieldKind::AnnotationAuthor),
+            "atnref" => self.field_destination(FieldKind::AnnotationTag),
+            "atndate" => self.field_destination(FieldKind::AnnotationDate),
+            "atrfstart" => self.field_destination(FieldKind::AnchorStart),
+            "atrfend" => self.field_destination(FieldKind::AnchorEnd),
+            "annotation" => {
+                self.group().destination = Destination::Text;
+                self.stories.push(Story::new(BlockKind::Body, depth));
+            }
+            "footnote" => {
+                self.group().destination = Destination::Text;
+                self.stories.push(Story::new(BlockKind::Footnote, depth));
+            }
+            // Inside \footnote: the note is an endnote
+            "ftnalt" => {
+                if let Some(story) = self.stories.last_mut() {
+                    if story.kind == BlockKind::Footnote {
+                        story.kind = BlockKind::Endnote;
+                    }
+                }
+            }
+            "header" | "headerl" | "headerr" | "headerf" | "footer" | "footerl" | "footerr"
+            | "footerf" => {
+                let kind = if word.starts_with("header") {
+                    BlockKind::Header
+                } else {
+                    BlockKind::Footer
+                };
+                let header_footer_type = match word.as_bytes().last() {
+                    Some(b'f') => HeaderFooterType::First,
+                    Some(b'l') => HeaderFooterType::Even,
+                    _ => HeaderFooterType::Default,
+                };
+                self.group().destination = Destination::Text;
+                let mut story = Story::new(kind, depth);
+                story.section = Some(self.sections);
+                story.header_footer_type = Some(header_footer_type);
+                self.stories.push(story);
+            }
+            _ if optional => self.group().destination = Destination::Skip,
+
+            // Revision marks
+            "revised" => self.group().revision.inserted = value != 0,
+            "deleted" => self.group().revision.deleted = value != 0,
+            "revauth" => self.group().revision.author = Some(value.max(0) as usize),
+            "revdttm" => self.group().revision.date = value as u32,
+            "revauthdel" => self.group().revision.delete_author = Some(value.max(0) as usize),
+            "revdttmdel" => self.group().revision.delete_date = Some(value as u32),
+            "crauth" | "crdate" => self.skipped_format_changes = true,
+            "plain" => self.group().revision = CharRevision::default(),
+
+            // Unicode
+            "uc" => self.group().unicode_skip = value.max(0) as usize,
+            "u" => {
+                let unit = value as i16 as u16;
+                self.unicode(unit);
+                self.skip = self.group().unicode_skip;
+            }
+
+            // Paragraphs, sections and tables
+            "par" | "sect" => {
+                self.end_paragraph(false);
+                if word == "sect" {
+                    self.sections += 1;
+                }
+            }
+            "pard" => self.group().in_table = false,
+            "intbl" => self.group().in_table = true,
+            "cell" | "nestcell" => {
+                self.group().in_table = true;
+                self.end_paragraph(true);
+            }
+            "row" | "nestrow" => {
+                if let Some(table) = self.stories.last_mut().and_then(|s| s.table.as_mut()) {
+                    table.row += 1;
+                    table.column = 0;
+                }
+            }
+            "trowd" | "trhdr" => {
+                if let Some(story) = self.stories.last_mut() {
+                    story.header_row = word == "trhdr";
+                }
+            }
+
+            // Special characters
+            "tab" => self.text("\t"),
+            "line" => self.text("\n"),
+            "emdash" => self.text("\u{2014}"),
+            "endash" => self.text("\u{2013}"),
+            "lquote" => self.text("\u{2018}"),
+            "rquote" => self.text("\u{2019}"),
+            "ldblquote" => self.text("\u{201c}"),
+            "rdblquote" => self.text("\u{201d}"),
+            "bullet" => self.text("\u{2022}"),
+            // \chftn (the note number) and \chatn (the comment mark) need no
+            // text: the \footnote or \annotation group follows them
+            _ => {}
+        }
+    }
+
+    fn field_destination(&mut self, kind: FieldKind) {
+        self.group().destination = Destination::Field(kind);
+        self.field.clear();
+    }
+
+    fn unicode(&mut self, unit: u16) {
+        match (self.high_surrogate.take(), unit) {
+            (_, 0xD800..=0xDBFF) => self.high_surrogate = Some(unit),
+            (Some(high), 0xDC00..=0xDFFF) => {
+                let text = String::from_utf16_lossy(&[high, unit]);
+                self.text(&text);
+            }
+            (_, unit) => {
+                let text = String::from_utf16_lossy(&[unit]);
+                self.text(&text);
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let group = self
+            .groups
+            .last()
+            .expect("the document group is never popped");
+        match group.destination {
+            Destination::Skip | Destination::Info => {}
+            Destination::RevisionTable | Destination::Title | Destination::Field(_) => {
+                self.field.push_str(text)
+            }
+            Destination::Text => {
+                let mark = group.revision.mark();
+                // Comment anchors are ranges of body text
+                if self.stories.len() == 1 {
+                    for tag in &self.open_anchors {
+                        if let Some(anchor) = self.anchors.get_mut(tag) {
+                            anchor.push_str(text);
+                        }
+                    }
+                }
+                let story = self
+                    .stories
+                    .last_mut()
+                    .expect("the body story is never popped");
+                story.push(mark, text);
+            }
+        }
+    }
+
+    fn end_group(&mut self) {
+        if self.groups.len() == 1 {
+            return;
+        }
+        let closed = self.groups.pop().expect("checked above");
+        let parent = self.group().destination;
+        self.skip = 0;
+
+        match closed.destination {
+            Destination::RevisionTable if parent != Destination::RevisionTable => {
+                self.authors = self
+                    .field
+                    .split(';')
+                    .map(|a| a.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .collect();
+            }
+            Destination::Title if parent != Destination::Title => {
+                let title = self.field.trim();
+                if !title.is_empty() {
+                    self.title = Some(title.to_string());
+                }
+            }
+            Destination::Field(kind) if parent != Destination::Field(kind) => {
+                self.field_value(kind);
+            }
+            _ => {}
+        }
+
+        // The group that opened a story has closed
+        let depth = self.groups.len();
+        if self.stories.len() > 1 && self.stories.last().is_some_and(|s| s.depth > depth) {
+            self.end_paragraph(false);
+            let story = self.stories.pop().expect("checked above");
+            self.end_story(story);
+        }
+    }
+
+    fn field_value(&mut self, kind: FieldKind) {
+        let value = self.field.trim().to_string();
+        match kind {
+            FieldKind::AnnotationInitials => {
+                self.annotation.initials = Some(value).filter(|v| !v.is_empty())
+            }
+            FieldKind::AnnotationAuthor => self.annotation.author = value,
+            FieldKind::AnnotationTag => self.annotation.tag = Some(value),
+            FieldKind::AnnotationDate => {
+                self.annotation.date = value.parse::<i64>().ok().and_then(|d| doc::dttm(d as u32))
+            }
+            FieldKind::AnchorStart => {
+                self.anchors.insert(value.clone(), String::new());
+                self.open_anchors.push(value);
+            }
+            FieldKind::AnchorEnd => self.open_anchors.retain(|tag| *tag != value),
+        }
+    }
+
+    /// Attach a finished annotation or note to the paragraph being collected
+    /// in the enclosing story; keep headers, footers and notes for the end
+    fn end_story(&mut self, story: Story) {
+        let host = self
+            .stories
+            .last_mut()
+            .expect("the body story is never popped");
+        match story.kind {
+            BlockKind::Body => {
+                // An annotation: its paragraphs are the comment text
+                let text = story
+                    .paragraphs
+                    .iter()
+                    .map(|p| crate::track_changes::revised_text(&p.segments))
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let annotation = std::mem::take(&mut self.annotation);
+                let id = self.comments.len().to_string();
+                let anchor = annotation
+                    .tag
+                    .as_ref()
+                    .and_then(|tag| self.anchors.get(tag))
+                    .cloned()
+                    .unwrap_or_default();
+                host.comment_ids.push(id.clone());
+                host.comment_anchor_texts.insert(id.clone(), anchor);
+                self.comments.insert(
+                    id.clone(),
+                    Comment {
+                        id,
+                        author: annotation.author,
+                        date: annotation.date,
+                        text,
+                        anchor_text: String::new(),
+                        initials: annotation.initials,
+                        source_file: None,
+                    },
+                );
+            }
+            BlockKind::Footnote | BlockKind::Endnote => {
+                let counter = if story.kind == BlockKind::Footnote {
+                    &mut self.footnotes
+                } else {
+                    &mut self.endnotes
+                };
+                *counter += 1;
+                let note_id = counter.to_string();
+                let anchor = (host.kind == BlockKind::Body).then(|| host.next_index());
+                if story.kind == BlockKind::Footnote {
+                    host.footnote_refs.push(note_id.clone());
+                } else {
+                    host.endnote_refs.push(note_id.clone());
+                }
+                let mut story = story;
+                for paragraph in &mut story.paragraphs {
+                    paragraph.note_id = Some(note_id.clone());
+                    paragraph.anchor_index = anchor;
+                }
+                self.finished.push(story);
+            }
+            _ => self.finished.push(story),
+        }
+    }
+
+    /// Close the paragraph being collected in the current story
+    fn end_paragraph(&mut self, cell_end: bool) {
+        let in_table = self.groups.last().is_some_and(|g| g.in_table);
+        let story = self
+            .stories
+            .last_mut()
+            .expect("the body story is never popped");
+        let runs = std::mem::take(&mut story.runs);
+
+        if !in_table {
+            story.table = None;
+        } else if story.table.is_none() {
+            story.table = Some(TableCursor {
+                index: self.tables,
+                row: 0,
+                column: 0,
+            });
+            self.tables += 1;
+        }
+        let table = story.table.as_ref().map(|t| TableCoordinates {
+            table_index: t.index,
+            row: t.row,
+            column: t.column,
+            is_header: story.header_row,
+            column_header: None,
+        });
+        if cell_end {
+            if let Some(t) = story.table.as_mut() {
+                t.column += 1;
+            }
+        }
+
+        let revision_ids = &mut self.revision_ids;
+        let segments: Vec<Segment> = runs
+            .into_iter()
+            .map(|(mark, text)| {
+                mark.segment(text, &self.authors, || {
+                    *revision_ids += 1;
+                    revision_ids.to_string()
+                })
+            })
+            .collect();
+        if !segments.iter().any(Segment::has_content) {
+            return;
+        }
+        story.paragraphs.push(ParsedParagraph {
+            index: story.paragraphs.len(),
+            segments,
+            comment_ids: std::mem::take(&mut story.comment_ids),
+            comment_anchor_texts: std::mem::take(&mut story.comment_anchor_texts),
+            footnote_refs: std::mem::take(&mut story.footnote_refs),
+            endnote_refs: std::mem::take(&mut story.endnote_refs),
+            kind: story.kind,
+            section: story.section,
+            header_footer_type: story.header_footer_type,
+            table,
+            ..Default::default()
+        });
+    }
+
+    fn finish(mut self) -> ParsedDocument {
+        // Text after the last \par is still a paragraph
+        while self.stories.len() > 1 {
+            self.end_paragraph(false);
+            let story = self.stories.pop().expect("checked above");
+            self.end_story(story);
+        }
+        self.end_paragraph(false);
+        let body = self.stories.pop().expect("the body story is never popped");
+        let mut paragraphs = body.paragraphs;
+
+        // Headers and footers, then footnotes, then endnotes
+        for kinds in [
+            &[BlockKind::Header, BlockKind::Footer][..],
+            &[BlockKind::Footnote],
+            &[BlockKind::Endnote],
+        ] {
+            for story in self.finished.iter_mut().filter(|s| kinds.contains(&s.kind)) {
+                for mut paragraph in story.paragraphs.drain(..) {
+                    paragraph.index = paragraphs.len();
+                    paragraphs.push(paragraph);
+                }
+            }
+        }
+        paragraphs::label_columns(&mut paragraphs);
+
+        let mut warnings = Vec::new();
+        if self.skipped_shapes {
+            warnings.push("Shapes and text boxes in RTF files are not read".to_string());
+        }
+        if self.skipped_format_changes {
+            warnings.push(
+                "Formatting revisions in RTF files are not read; only insertions and deletions are"
+                    .to_string(),
+            );
+        }
+
+        ParsedDocument {
+            paragraphs,
+            comments: self.comments,
+            title: self.title,
+            warnings,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::NsReader;
+
+use crate::types::{BlockKind, HeaderFooterType};
+use crate::xml::{self, local_name, Ns};
+
+/// A header or footer part referenced from one section's properties
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartReference {
+    /// 0-based section index, in document order
+    pub section: usize,
+    /// `Header` or `Footer`
+    pub kind: BlockKind,
+    pub header_footer_type: HeaderFooterType,
+    /// `r:id` of the relationship pointing at the part
+    pub rel_id: String,
+}
+
+/// Collect the `w:headerReference` / `w:footerReference` elements of every
+/// section in document.xml. Each `w:sectPr` closes a section, whether it sits in
+/// a paragraph's properties or at the end of the body; the previous properties
+/// recorded inside `w:sectPrChange` are ignored.
+pub fn references(document_xml: &str) -> Vec<PartReference> {
+    let mut refs = Vec::new();
+    let mut reader = NsReader::from_str(document_xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut section: usize = 0;
+    let mut in_sect_pr = false;
+    let mut change_depth: usize = 0;
+
+    while let Ok((resolved, event)) = reader.read_resolved_event_into(&mut buf) {
+        let ns = xml::ns(&resolved);
+        match event {
+            Event::Start(ref e) => match (ns, e.local_name().as_ref()) {
+                (Ns::Word, b"sectPrChange") => change_depth += 1,
+                (Ns::Word, b"sectPr") if change_depth == 0 => in_sect_pr = true,
+                _ => {}
+            },
+            Event::Empty(ref e) if change_depth == 0 => {
+                let kind = match (ns, e.local_name().as_ref()) {
+                    (Ns::Word, b"headerReference") if in_sect_pr => Some(BlockKind::Header),
+                    (Ns::Word, b"footerReference") if in_sect_pr => Some(BlockKind::Footer),
+                    // A section with no properties of its own still counts
+                    (Ns::Word, b"sectPr") => {
+                        section += 1;
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    if let Some(reference) = part_reference(e, section, kind) {
+                        refs.push(reference);
+                    }
+                }
+            }
+            Event::End(ref e) => match (ns, e.local_name().as_ref()) {
+                (Ns::Word, b"sectPrChange") => change_depth = change_depth.saturating_sub(1),
+                (Ns::Word, b"sectPr") if change_depth == 0 && in_sect_pr => {
+                    in_sect_pr = false;
+                    section += 1;
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    refs
+}
+
+fn part_reference(e: &BytesStart, section: usize, kind: BlockKind) -> Option<PartReference> {
+    let mut header_footer_type = HeaderFooterType::Default;
+    let mut rel_id = None;
+    for attr in e.attributes().flatten() {
+        let val = String::from_utf8_lossy(&attr.value).to_string();
+        match local_name(attr.key.as_ref()) {
+            b"type" => {
+                header_footer_type = match val.as_str() {
+                    "first" => HeaderFooterType::First,
+                    "even" => HeaderFooterType::Even,
+                    _ => HeaderFooterType::Default,
+                }
+            }
+            b"id" => rel_id = Some(val),
+            _ => {}
+        }
+    }
+    Some(PartReference {
+        section,
+        kind,
+        header_footer_type,
+        rel_id: rel_id?,
+    })
+}
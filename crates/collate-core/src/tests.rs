@@ -4,10 +4,18 @@ use std::io::{Cursor, Write};
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 
+use crate::align;
 use crate::comments;
+use crate::conflicts;
+use crate::doc;
 use crate::docx;
+use crate::formats;
 use crate::matcher;
+use crate::merge;
+use crate::odt;
 use crate::paragraphs;
+use crate::pdf;
+use crate::rtf;
 use crate::track_changes::{self, Segment};
 use crate::types::*;
 
@@ -22,7 +30,7 @@ fn create_docx_bytes(files: &[(&str, &str)]) -> Vec<u8> {
     let options: SimpleFileOptions = SimpleFileOptions::default();
 
     for (name, content) in files {
-        zip.start_file(name.to_string(), options.clone()).unwrap();
+        zip.start_file(name.to_string(), options).unwrap();
         zip.write_all(content.as_bytes()).unwrap();
     }
 
@@ -33,7 +41,14 @@ fn create_docx_bytes(files: &[(&str, &str)]) -> Vec<u8> {
 fn minimal_document_xml(body_content: &str) -> String {
     format!(
         r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml"
+            xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+            xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006"
+            xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"
+            xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+            xmlns:wps="http://schemas.microsoft.com/office/word/2010/wordprocessingShape"
+            xmlns:v="urn:schemas-microsoft-com:vml">
   <w:body>
     {body_content}
   </w:body>
@@ -352,6 +367,33 @@ fn track_empty_segments() {
     assert!(track_changes::extract_changes(&segments).is_empty());
 }
 
+#[test]
+fn track_author_text_applies_only_that_authors_changes() {
+    let segments = vec![
+        Segment::Stable("The ".to_string()),
+        Segment::Deletion {
+            id: "1".into(),
+            author: "Bob".into(),
+            date: None,
+            text: "quick ".to_string(),
+        },
+        Segment::Insertion {
+            id: "2".into(),
+            author: "Alice".into(),
+            date: None,
+            text: "brown ".to_string(),
+        },
+        Segment::Stable("fox".to_string()),
+    ];
+    assert_eq!(track_changes::author_text(&segments, "Alice"), "The quick brown fox");
+    assert_eq!(track_changes::author_text(&segments, "Bob"), "The fox");
+    // An author with no changes sees the base text
+    assert_eq!(
+        track_changes::author_text(&segments, "Carol"),
+        track_changes::base_text(&segments)
+    );
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 //  3. paragraphs.rs tests
 // ═══════════════════════════════════════════════════════════════════════
@@ -543,6 +585,44 @@ fn paragraphs_whitespace_only_paragraph_skipped() {
     assert_eq!(paras[0].index, 0);
 }
 
+#[test]
+fn paragraphs_capture_para_id_and_text_id() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml">
+  <w:body>
+    <w:p w14:paraId="1A2B3C4D" w14:textId="77AA00FF"><w:r><w:t>Identified</w:t></w:r></w:p>
+    <w:p><w:r><w:t>Anonymous</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+    let paras = paragraphs::parse_document(xml);
+    assert_eq!(paras.len(), 2);
+    assert_eq!(paras[0].para_id.as_deref(), Some("1A2B3C4D"));
+    assert_eq!(paras[0].text_id.as_deref(), Some("77AA00FF"));
+    assert!(paras[1].para_id.is_none());
+    assert!(paras[1].text_id.is_none());
+}
+
+#[test]
+fn paragraph_ids_are_read_only_from_the_word_2010_namespace() {
+    // Any prefix bound to the w14 namespace counts; a paraId from another
+    // vocabulary does not
+    let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:x14="http://schemas.microsoft.com/office/word/2010/wordml"
+            xmlns:ext="urn:example:ids">
+  <w:body>
+    <w:p x14:paraId="1A2B3C4D" x14:textId="77AA00FF"><w:r><w:t>Identified</w:t></w:r></w:p>
+    <w:p ext:paraId="DEADBEEF" textId="0000FFFF"><w:r><w:t>Foreign</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+    let paras = paragraphs::parse_document(xml);
+    assert_eq!(paras[0].para_id.as_deref(), Some("1A2B3C4D"));
+    assert_eq!(paras[0].text_id.as_deref(), Some("77AA00FF"));
+    assert!(paras[1].para_id.is_none());
+    assert!(paras[1].text_id.is_none());
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 //  4. docx.rs integration tests
 // ═══════════════════════════════════════════════════════════════════════
@@ -705,6 +785,7 @@ fn make_parsed_paragraph(
         segments,
         comment_ids,
         comment_anchor_texts,
+        ..Default::default()
     }
 }
 
@@ -743,6 +824,7 @@ fn matcher_comments_matched_to_correct_paragraphs() {
             text: "Comment on first".into(),
             anchor_text: String::new(),
             initials: Some("A".into()),
+            source_file: None,
         },
     );
     comments_map.insert(
@@ -754,6 +836,7 @@ fn matcher_comments_matched_to_correct_paragraphs() {
             text: "Comment on second".into(),
             anchor_text: String::new(),
             initials: Some("B".into()),
+            source_file: None,
         },
     );
 
@@ -816,6 +899,7 @@ fn matcher_reviewer_list_with_correct_counts() {
             text: "A comment".into(),
             anchor_text: String::new(),
             initials: None,
+            source_file: None,
         },
     );
 
@@ -834,11 +918,17 @@ fn matcher_reviewer_list_with_correct_counts() {
 
 #[test]
 fn matcher_detect_conflicts_multiple_authors() {
-    // Two different authors editing the same paragraph → has_conflicts = true
+    // Alice inserts inside the text Bob deletes → one conflict over Bob's span
     let paras = vec![make_parsed_paragraph(
         0,
         vec![
             Segment::Stable("Shared paragraph ".into()),
+            Segment::Deletion {
+                id: "2".into(),
+                author: "Bob".into(),
+                date: None,
+                text: "Bob's ".into(),
+            },
             Segment::Insertion {
                 id: "1".into(),
                 author: "Alice".into(),
@@ -846,10 +936,10 @@ fn matcher_detect_conflicts_multiple_authors() {
                 text: "Alice's addition ".into(),
             },
             Segment::Deletion {
-                id: "2".into(),
+                id: "3".into(),
                 author: "Bob".into(),
                 date: None,
-                text: "Bob's removal".into(),
+                text: "removal".into(),
             },
         ],
         vec![],
@@ -860,10 +950,21 @@ fn matcher_detect_conflicts_multiple_authors() {
         matcher::build_paragraph_blocks(&paras, &HashMap::new(), "conflict.docx");
 
     assert_eq!(blocks.len(), 1);
-    assert!(
-        blocks[0].has_conflicts,
-        "Should detect conflict when multiple authors edit same paragraph"
+    assert_eq!(
+        blocks[0].conflicts.len(),
+        1,
+        "Should detect conflict when one author inserts inside another's deletion"
     );
+    let conflict = &blocks[0].conflicts[0];
+    assert_eq!(conflict.start, 17);
+    assert_eq!(conflict.end, 30);
+    assert_eq!(conflict.original_text, "Bob's removal");
+    assert_eq!(conflict.edits.len(), 2);
+    assert_eq!(conflict.edits[0].author, "Bob");
+    assert_eq!(conflict.edits[0].change_ids, vec!["2", "3"]);
+    assert_eq!(conflict.edits[0].proposed_text, "");
+    assert_eq!(conflict.edits[1].author, "Alice");
+    assert_eq!(conflict.edits[1].proposed_text, "Bob's Alice's addition removal");
 
     // Both authors should appear in reviewers
     assert_eq!(reviewers.len(), 2);
@@ -872,6 +973,82 @@ fn matcher_detect_conflicts_multiple_authors() {
     assert!(names.contains(&"Bob"));
 }
 
+#[test]
+fn matcher_no_conflicts_for_separate_edits_by_different_authors() {
+    // Two reviewers fixing typos in different sentences is not a conflict
+    let paras = vec![make_parsed_paragraph(
+        0,
+        vec![
+            Segment::Stable("The claimant ".into()),
+            Segment::Deletion {
+                id: "1".into(),
+                author: "Alice".into(),
+                date: None,
+                text: "recieved".into(),
+            },
+            Segment::Insertion {
+                id: "2".into(),
+                author: "Alice".into(),
+                date: None,
+                text: "received".into(),
+            },
+            Segment::Stable(" the goods. The defendant ".into()),
+            Segment::Deletion {
+                id: "3".into(),
+                author: "Bob".into(),
+                date: None,
+                text: "refussed".into(),
+            },
+            Segment::Insertion {
+                id: "4".into(),
+                author: "Bob".into(),
+                date: None,
+                text: "refused".into(),
+            },
+            Segment::Stable(" payment.".into()),
+        ],
+        vec![],
+        HashMap::new(),
+    )];
+
+    let (blocks, _) = matcher::build_paragraph_blocks(&paras, &HashMap::new(), "typos.docx");
+    assert!(blocks[0].conflicts.is_empty());
+}
+
+#[test]
+fn matcher_conflict_on_competing_insertions() {
+    // Both reviewers insert different text at the same point
+    let paras = vec![make_parsed_paragraph(
+        0,
+        vec![
+            Segment::Stable("Payment is ".into()),
+            Segment::Insertion {
+                id: "1".into(),
+                author: "Alice".into(),
+                date: None,
+                text: "not ".into(),
+            },
+            Segment::Insertion {
+                id: "2".into(),
+                author: "Bob".into(),
+                date: None,
+                text: "never ".into(),
+            },
+            Segment::Stable("due.".into()),
+        ],
+        vec![],
+        HashMap::new(),
+    )];
+
+    let (blocks, _) = matcher::build_paragraph_blocks(&paras, &HashMap::new(), "due.docx");
+    assert_eq!(blocks[0].conflicts.len(), 1);
+    let conflict = &blocks[0].conflicts[0];
+    assert_eq!((conflict.start, conflict.end), (11, 11));
+    assert_eq!(conflict.original_text, "");
+    assert_eq!(conflict.edits[0].proposed_text, "not ");
+    assert_eq!(conflict.edits[1].proposed_text, "never ");
+}
+
 #[test]
 fn matcher_no_conflicts_single_author() {
     let paras = vec![make_parsed_paragraph(
@@ -896,7 +1073,7 @@ fn matcher_no_conflicts_single_author() {
     )];
 
     let (blocks, _) = matcher::build_paragraph_blocks(&paras, &HashMap::new(), "single.docx");
-    assert!(!blocks[0].has_conflicts, "Single author should not conflict");
+    assert!(blocks[0].conflicts.is_empty(), "Single author should not conflict");
 }
 
 #[test]
@@ -929,6 +1106,42 @@ fn matcher_reviewer_versions_populated_when_text_differs() {
     );
 }
 
+#[test]
+fn matcher_reviewer_versions_apply_only_own_changes() {
+    let paras = vec![make_parsed_paragraph(
+        0,
+        vec![
+            Segment::Stable("The ".into()),
+            Segment::Deletion {
+                id: "1".into(),
+                author: "Bob".into(),
+                date: None,
+                text: "quick ".into(),
+            },
+            Segment::Stable("fox jumps".into()),
+            Segment::Insertion {
+                id: "2".into(),
+                author: "Alice".into(),
+                date: None,
+                text: " high".into(),
+            },
+        ],
+        vec![],
+        HashMap::new(),
+    )];
+
+    let (blocks, _) = matcher::build_paragraph_blocks(&paras, &HashMap::new(), "test.docx");
+    let versions = &blocks[0].reviewer_versions;
+
+    assert_eq!(versions.len(), 2);
+    // Versions are listed in order of each author's first change
+    assert_eq!(versions[0].reviewer_name, "Bob");
+    assert_eq!(versions[0].resulting_text, "The fox jumps");
+    assert_eq!(versions[1].reviewer_name, "Alice");
+    assert_eq!(versions[1].resulting_text, "The quick fox jumps high");
+    assert_eq!(blocks[0].revised_text, "The fox jumps high");
+}
+
 #[test]
 fn matcher_no_reviewer_versions_when_no_changes() {
     let paras = vec![make_parsed_paragraph(
@@ -944,7 +1157,7 @@ fn matcher_no_reviewer_versions_when_no_changes() {
         "No changes means no reviewer versions"
     );
     assert!(blocks[0].track_changes.is_empty());
-    assert!(!blocks[0].has_conflicts);
+    assert!(blocks[0].conflicts.is_empty());
 }
 
 #[test]
@@ -1257,3 +1470,3837 @@ fn test_wholly_deleted_revised_text_empty() {
     assert_eq!(para.paragraph_status, ParagraphStatus::WhollyDeleted);
     assert_eq!(para.paragraph_change_author.as_deref(), Some("Eve"));
 }
+
+// ═══════════════════════════════════════════════════════════════════════
+//  7. merge.rs tests (multi-document collation)
+// ═══════════════════════════════════════════════════════════════════════
+
+
+fn reviewer_copy(author: &str, replacement: &str, extra_body: &str) -> Vec<u8> {
+    let doc_xml = minimal_document_xml(&format!(
+        r#"<w:p><w:r><w:t>The claimant received the goods on 1 March.</w:t></w:r></w:p>
+        <w:p>
+            <w:r><w:t>Payment was due within</w:t></w:r>
+            <w:del w:id="1" w:author="{author}"><w:r><w:delText>14</w:delText></w:r></w:del>
+            <w:ins w:id="2" w:author="{author}"><w:r><w:t>{replacement}</w:t></w:r></w:ins>
+            <w:r><w:t>days of delivery.</w:t></w:r>
+        </w:p>
+        {extra_body}"#
+    ));
+    create_docx_bytes(&[("word/document.xml", &doc_xml)])
+}
+
+fn base_copy() -> Vec<u8> {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>The claimant received the goods on 1 March.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Payment was due within</w:t></w:r><w:r><w:t>14</w:t></w:r><w:r><w:t>days of delivery.</w:t></w:r></w:p>"#,
+    );
+    create_docx_bytes(&[("word/document.xml", &doc_xml)])
+}
+
+#[test]
+fn align_tokenize_and_jaccard() {
+    let tokens = align::tokenize("The Claimant, relies on: the contract!");
+    assert_eq!(tokens.len(), 5);
+    assert!(tokens.contains("claimant"));
+    assert!(tokens.contains("contract"));
+
+    let jaccard = |a: &str, b: &str| align::jaccard(&align::tokenize(a), &align::tokenize(b));
+    assert_eq!(jaccard("", ""), 1.0);
+    assert_eq!(jaccard("a b", ""), 0.0);
+    assert_eq!(jaccard("a b c", "b c d"), 0.5);
+}
+
+#[test]
+fn merge_collates_reviewer_copies_into_base() {
+    let base = base_copy();
+    let alice = reviewer_copy("Alice", "28", "");
+    let bob = reviewer_copy(
+        "Bob",
+        "7",
+        r#"<w:p><w:ins w:id="3" w:author="Bob"><w:r><w:t>Interest accrues daily.</w:t></w:r></w:ins></w:p>"#,
+    );
+
+    let result = merge::collate(
+        &[("base.docx", &base), ("alice.docx", &alice), ("bob.docx", &bob)],
+        "base.docx",
+    )
+    .expect("Should collate");
+
+    assert_eq!(result.paragraphs.len(), 3);
+
+    let payment = &result.paragraphs[1];
+    assert_eq!(payment.source_file.as_deref(), Some("base.docx"));
+    assert_eq!(payment.track_changes.len(), 4);
+    let sources: Vec<&str> = payment
+        .track_changes
+        .iter()
+        .filter_map(|tc| tc.source_file.as_deref())
+        .collect();
+    assert_eq!(sources, vec!["alice.docx", "alice.docx", "bob.docx", "bob.docx"]);
+
+    // Each copy's reviewer version stands alone
+    assert_eq!(payment.reviewer_versions.len(), 2);
+    assert_eq!(
+        payment.reviewer_versions[0].resulting_text,
+        "Payment was due within28days of delivery."
+    );
+    assert_eq!(
+        payment.reviewer_versions[1].resulting_text,
+        "Payment was due within7days of delivery."
+    );
+
+    // Alice and Bob replaced the same span differently
+    assert_eq!(payment.conflicts.len(), 1);
+    let conflict = &payment.conflicts[0];
+    assert_eq!(conflict.original_text, "14");
+    let proposals: Vec<(&str, &str)> = conflict
+        .edits
+        .iter()
+        .map(|e| (e.author.as_str(), e.proposed_text.as_str()))
+        .collect();
+    assert_eq!(proposals, vec![("Alice", "28"), ("Bob", "7")]);
+
+    // Bob's new paragraph has no match and is kept with its provenance
+    let new_para = &result.paragraphs[2];
+    assert_eq!(new_para.index, 2);
+    assert_eq!(new_para.paragraph_status, ParagraphStatus::WhollyInserted);
+    assert_eq!(new_para.source_file.as_deref(), Some("bob.docx"));
+
+    // One reviewer list across all files
+    let names: Vec<&str> = result.reviewers.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["Alice", "Bob"]);
+    assert_eq!(result.reviewers[0].change_count, 2);
+    assert_eq!(result.reviewers[1].change_count, 3);
+    assert_eq!(result.reviewers[1].file_name, "bob.docx");
+    assert_ne!(result.reviewers[0].colour, result.reviewers[1].colour);
+}
+
+#[test]
+fn merge_same_edit_in_two_copies_is_not_a_conflict() {
+    let base = base_copy();
+    let alice = reviewer_copy("Alice", "28", "");
+    let bob = reviewer_copy("Bob", "28", "");
+
+    let result = merge::collate(
+        &[("alice.docx", &alice), ("base.docx", &base), ("bob.docx", &bob)],
+        "base.docx",
+    )
+    .expect("Should collate");
+
+    assert!(result.paragraphs[1].conflicts.is_empty());
+}
+
+#[test]
+fn merge_error_when_base_missing_or_file_invalid() {
+    let base = base_copy();
+    let err = merge::collate(&[("base.docx", &base)], "other.docx").unwrap_err();
+    assert!(err.contains("other.docx"), "got '{}'", err);
+
+    let err = merge::collate(
+        &[("base.docx", &base), ("broken.docx", b"not a zip")],
+        "base.docx",
+    )
+    .unwrap_err();
+    assert!(err.starts_with("broken.docx:"), "got '{}'", err);
+}
+
+#[test]
+fn collate_entry_point_takes_owned_files_and_reports_errors_in_the_result() {
+    let files = vec![
+        ("base.docx".to_string(), base_copy()),
+        ("bob.docx".to_string(), reviewer_copy("Bob", "28", "")),
+    ];
+
+    let result = crate::collate(&files, "base.docx", &crate::CollateOptions::default());
+    assert!(result.error.is_none());
+    assert_eq!(result.reviewers.len(), 1);
+    assert_eq!(result.reviewers[0].name, "Bob");
+
+    let result = crate::collate(&files, "other.docx", &crate::CollateOptions::default());
+    assert!(result.error.as_deref().unwrap().contains("other.docx"));
+    assert!(result.paragraphs.is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  8. align.rs tests (order-preserving paragraph alignment)
+// ═══════════════════════════════════════════════════════════════════════
+
+fn align_keys_with_ids(texts: &[(&str, Option<&str>)]) -> Vec<align::AlignKey> {
+    texts
+        .iter()
+        .enumerate()
+        .map(|(i, (t, id))| {
+            let para = paragraphs::ParsedParagraph {
+                index: i,
+                segments: vec![Segment::Stable(t.to_string())],
+                para_id: id.map(|s| s.to_string()),
+                ..Default::default()
+            };
+            align::AlignKey::new(&para)
+        })
+        .collect()
+}
+
+fn align_keys(texts: &[&str]) -> Vec<align::AlignKey> {
+    texts
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let segments = vec![Segment::Stable(t.to_string())];
+            align::AlignKey::new(&make_parsed_paragraph(i, segments, vec![], HashMap::new()))
+        })
+        .collect()
+}
+
+#[test]
+fn align_keeps_repeated_boilerplate_in_order() {
+    let base = align_keys(&[
+        "The Claimant relies on the contract.",
+        "Clause one concerns delivery of the goods.",
+        "The Claimant relies on the contract.",
+        "Clause two concerns payment for the goods.",
+    ]);
+    let other = align_keys(&[
+        "The Claimant relies on the contract.",
+        "Clause one concerns delivery of the goods.",
+        "A wholly different point about limitation.",
+        "The Claimant relies on the contract.",
+        "Clause two concerns prompt payment for the goods.",
+    ]);
+
+    let pairs = align::align(&base, &other);
+    let steps: Vec<(Option<usize>, Option<usize>, Option<align::MatchKind>)> =
+        pairs.iter().map(|p| (p.base, p.other, p.kind)).collect();
+
+    use align::MatchKind::{Exact, Similar};
+    assert_eq!(
+        steps,
+        vec![
+            (Some(0), Some(0), Some(Exact)),
+            (Some(1), Some(1), Some(Exact)),
+            (None, Some(2), None),
+            (Some(2), Some(3), Some(Exact)),
+            (Some(3), Some(4), Some(Similar)),
+        ]
+    );
+    assert_eq!(pairs[0].score, 1.0);
+    assert!(pairs[4].score > 0.3 && pairs[4].score < 1.0);
+    assert_eq!(pairs[2].score, 0.0);
+}
+
+#[test]
+fn align_similarity_respects_order_in_gaps() {
+    // Both reviewer paragraphs are edited, so nothing matches exactly; each must
+    // still pair with its own base paragraph rather than the most similar one overall
+    let base = align_keys(&[
+        "Heading",
+        "The defendant shall pay the sum of five pounds.",
+        "The defendant shall pay the sum of ten pounds forthwith.",
+    ]);
+    let other = align_keys(&[
+        "Heading",
+        "The defendant shall pay the sum of ten pounds.",
+        "The defendant shall pay the sum of twenty pounds forthwith.",
+    ]);
+
+    let pairs = align::align(&base, &other);
+    let matched: Vec<(Option<usize>, Option<usize>)> =
+        pairs.iter().map(|p| (p.base, p.other)).collect();
+    assert_eq!(
+        matched,
+        vec![(Some(0), Some(0)), (Some(1), Some(1)), (Some(2), Some(2))]
+    );
+}
+
+#[test]
+fn merge_places_items_by_document_order() {
+    let boilerplate = "The Claimant relies on the contract.";
+    let base_xml = minimal_document_xml(&format!(
+        r#"<w:p><w:r><w:t>{boilerplate}</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Clause one concerns delivery.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>{boilerplate}</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Clause two concerns payment.</w:t></w:r></w:p>"#
+    ));
+    let copy_xml = minimal_document_xml(&format!(
+        r#"<w:p><w:r><w:t>{boilerplate}</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Clause one concerns delivery.</w:t></w:r></w:p>
+        <w:p><w:ins w:id="9" w:author="Carol"><w:r><w:t>Clause one A concerns risk.</w:t></w:r></w:ins></w:p>
+        <w:p><w:commentRangeStart w:id="1"/><w:r><w:t>{boilerplate}</w:t></w:r><w:commentRangeEnd w:id="1"/></w:p>
+        <w:p><w:r><w:t>Clause two concerns payment.</w:t></w:r></w:p>"#
+    ));
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="1" w:author="Carol"><w:p><w:r><w:t>Second reliance only</w:t></w:r></w:p></w:comment>"#,
+    );
+    let base = create_docx_bytes(&[("word/document.xml", &base_xml)]);
+    let copy = create_docx_bytes(&[
+        ("word/document.xml", &copy_xml),
+        ("word/comments.xml", &comments_xml),
+    ]);
+
+    let result = merge::collate(&[("base.docx", &base), ("carol.docx", &copy)], "base.docx")
+        .expect("Should collate");
+
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| {
+            if p.base_text.is_empty() {
+                p.revised_text.as_str()
+            } else {
+                p.base_text.as_str()
+            }
+        })
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            boilerplate,
+            "Clause one concerns delivery.",
+            "Clause one A concerns risk.",
+            boilerplate,
+            "Clause two concerns payment.",
+        ]
+    );
+    let indices: Vec<usize> = result.paragraphs.iter().map(|p| p.index).collect();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+
+    // The comment stays on the second occurrence, not the first
+    assert!(result.paragraphs[0].comments.is_empty());
+    assert_eq!(result.paragraphs[3].comments.len(), 1);
+    assert_eq!(result.paragraphs[3].comments[0].text, "Second reliance only");
+    assert_eq!(result.paragraphs[2].source_file.as_deref(), Some("carol.docx"));
+}
+
+#[test]
+fn align_para_id_takes_precedence_over_text() {
+    let base = align_keys_with_ids(&[
+        ("Definitions apply throughout.", Some("00000001")),
+        ("The seller warrants title to the goods.", Some("00000002")),
+        ("Notices must be in writing.", Some("00000003")),
+    ]);
+    // The second paragraph was rewritten without tracking and a copy of the
+    // first was pasted at the end under a fresh id
+    let other = align_keys_with_ids(&[
+        ("Definitions apply throughout.", Some("00000001")),
+        ("No warranty is given in respect of anything.", Some("00000002")),
+        ("Notices must be in writing.", Some("00000003")),
+        ("Definitions apply throughout.", Some("0000000A")),
+    ]);
+
+    let pairs = align::align(&base, &other);
+    let steps: Vec<(Option<usize>, Option<usize>, Option<align::MatchKind>)> =
+        pairs.iter().map(|p| (p.base, p.other, p.kind)).collect();
+
+    use align::MatchKind::ParaId;
+    assert_eq!(
+        steps,
+        vec![
+            (Some(0), Some(0), Some(ParaId)),
+            (Some(1), Some(1), Some(ParaId)),
+            (Some(2), Some(2), Some(ParaId)),
+            (None, Some(3), None),
+        ]
+    );
+    // The score still reports how far the text has drifted
+    assert!(pairs[1].score < 0.3);
+    assert_eq!(pairs[0].score, 1.0);
+}
+
+#[test]
+fn merge_matches_rewritten_paragraph_by_para_id() {
+    let base_xml = minimal_document_xml(
+        r#"<w:p w14:paraId="0A0A0A0A"><w:r><w:t>The seller warrants title to the goods.</w:t></w:r></w:p>
+        <w:p w14:paraId="0B0B0B0B"><w:r><w:t>Notices must be in writing.</w:t></w:r></w:p>"#,
+    );
+    let copy_xml = minimal_document_xml(
+        r#"<w:p w14:paraId="0A0A0A0A"><w:commentRangeStart w:id="4"/><w:r><w:t>No warranty is given at all.</w:t></w:r><w:commentRangeEnd w:id="4"/></w:p>
+        <w:p w14:paraId="0B0B0B0B"><w:r><w:t>Notices must be in writing.</w:t></w:r></w:p>"#,
+    );
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="4" w:author="Dan"><w:p><w:r><w:t>Rewritten</w:t></w:r></w:p></w:comment>"#,
+    );
+    let base = create_docx_bytes(&[("word/document.xml", &base_xml)]);
+    let copy = create_docx_bytes(&[
+        ("word/document.xml", &copy_xml),
+        ("word/comments.xml", &comments_xml),
+    ]);
+
+    let result = merge::collate(&[("base.docx", &base), ("dan.docx", &copy)], "base.docx")
+        .expect("Should collate");
+
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].para_id.as_deref(), Some("0A0A0A0A"));
+    assert_eq!(result.paragraphs[0].comments.len(), 1);
+    assert_eq!(result.paragraphs[0].comments[0].author, "Dan");
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  9. Footnotes and endnotes
+// ═══════════════════════════════════════════════════════════════════════
+
+fn minimal_notes_xml(root: &str, notes_content: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:{root}s xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:{root} w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:{root}>
+  <w:{root} w:type="continuationSeparator" w:id="0"><w:p><w:r><w:t>--</w:t></w:r></w:p></w:{root}>
+  {notes_content}
+</w:{root}s>"#
+    )
+}
+
+#[test]
+fn paragraphs_parse_notes_skips_separators() {
+    let xml = minimal_notes_xml(
+        "footnote",
+        r#"<w:footnote w:id="1"><w:p><w:r><w:t>See the schedule.</w:t></w:r></w:p></w:footnote>
+        <w:footnote w:id="2"><w:p><w:r><w:t>First line.</w:t></w:r></w:p><w:p><w:r><w:t>Second line.</w:t></w:r></w:p></w:footnote>"#,
+    );
+    let notes = paragraphs::parse_notes(&xml, BlockKind::Footnote);
+
+    assert_eq!(notes.len(), 3);
+    assert!(notes.iter().all(|n| n.kind == BlockKind::Footnote));
+    assert_eq!(notes[0].note_id.as_deref(), Some("1"));
+    assert_eq!(notes[1].note_id.as_deref(), Some("2"));
+    assert_eq!(notes[2].note_id.as_deref(), Some("2"));
+}
+
+#[test]
+fn paragraphs_append_notes_links_references() {
+    let body = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Intro.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Claim</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>"#,
+    );
+    let mut paras = paragraphs::parse_document(&body);
+    assert_eq!(paras[1].footnote_refs, vec!["1".to_string()]);
+
+    let xml = minimal_notes_xml(
+        "footnote",
+        r#"<w:footnote w:id="1"><w:p><w:r><w:t>Source.</w:t></w:r></w:p></w:footnote>
+        <w:footnote w:id="9"><w:p><w:r><w:t>Orphan.</w:t></w:r></w:p></w:footnote>"#,
+    );
+    paragraphs::append_notes(&mut paras, paragraphs::parse_notes(&xml, BlockKind::Footnote));
+
+    assert_eq!(paras.len(), 4);
+    assert_eq!(paras[2].index, 2);
+    assert_eq!(paras[2].anchor_index, Some(1));
+    assert_eq!(paras[3].index, 3);
+    assert_eq!(paras[3].anchor_index, None);
+}
+
+#[test]
+fn docx_parses_footnote_and_endnote_changes_and_comments() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Body text</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>
+        <w:p><w:r><w:t>More text</w:t></w:r><w:r><w:endnoteReference w:id="1"/></w:r></w:p>"#,
+    );
+    let footnotes_xml = minimal_notes_xml(
+        "footnote",
+        r#"<w:footnote w:id="1"><w:p>
+            <w:commentRangeStart w:id="7"/><w:r><w:t>See page</w:t></w:r><w:commentRangeEnd w:id="7"/>
+            <w:ins w:id="20" w:author="Alice"><w:r><w:t> 12</w:t></w:r></w:ins>
+        </w:p></w:footnote>"#,
+    );
+    let endnotes_xml = minimal_notes_xml(
+        "endnote",
+        r#"<w:endnote w:id="1"><w:p><w:r><w:t>Ibid.</w:t></w:r>
+            <w:del w:id="21" w:author="Bob"><w:r><w:delText> op. cit.</w:delText></w:r></w:del>
+        </w:p></w:endnote>"#,
+    );
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="7" w:author="Carol"><w:p><w:r><w:t>Which edition?</w:t></w:r></w:p></w:comment>"#,
+    );
+    let data = create_docx_bytes(&[
+        ("word/document.xml", &doc_xml),
+        ("word/footnotes.xml", &footnotes_xml),
+        ("word/endnotes.xml", &endnotes_xml),
+        ("word/comments.xml", &comments_xml),
+    ]);
+
+    let result = docx::parse(&data, "notes.docx").expect("Should parse");
+
+    assert_eq!(result.paragraphs.len(), 4);
+    let footnote = &result.paragraphs[2];
+    assert_eq!(footnote.kind, BlockKind::Footnote);
+    assert_eq!(footnote.note_id.as_deref(), Some("1"));
+    assert_eq!(footnote.anchor_index, Some(0));
+    assert_eq!(footnote.track_changes.len(), 1);
+    assert_eq!(footnote.track_changes[0].author, "Alice");
+    assert_eq!(footnote.comments.len(), 1);
+    assert_eq!(footnote.comments[0].author, "Carol");
+
+    let endnote = &result.paragraphs[3];
+    assert_eq!(endnote.kind, BlockKind::Endnote);
+    assert_eq!(endnote.anchor_index, Some(1));
+    assert_eq!(endnote.track_changes[0].author, "Bob");
+    assert_eq!(result.paragraphs[0].kind, BlockKind::Body);
+}
+
+#[test]
+fn merge_remaps_note_anchors_after_insertions() {
+    let footnotes_xml = minimal_notes_xml(
+        "footnote",
+        r#"<w:footnote w:id="1"><w:p><w:r><w:t>Source of the claim.</w:t></w:r></w:p></w:footnote>"#,
+    );
+    let base_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Opening paragraph of the report.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>The claim made here</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>"#,
+    );
+    let copy_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Opening paragraph of the report.</w:t></w:r></w:p>
+        <w:p><w:ins w:id="3" w:author="Eve"><w:r><w:t>A brand new inserted paragraph.</w:t></w:r></w:ins></w:p>
+        <w:p><w:r><w:t>The claim made here</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>"#,
+    );
+    let base = create_docx_bytes(&[
+        ("word/document.xml", &base_xml),
+        ("word/footnotes.xml", &footnotes_xml),
+    ]);
+    let copy = create_docx_bytes(&[
+        ("word/document.xml", &copy_xml),
+        ("word/footnotes.xml", &footnotes_xml),
+    ]);
+
+    let result = merge::collate(&[("base.docx", &base), ("eve.docx", &copy)], "base.docx")
+        .expect("Should collate");
+
+    let kinds: Vec<BlockKind> = result.paragraphs.iter().map(|p| p.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![BlockKind::Body, BlockKind::Body, BlockKind::Body, BlockKind::Footnote]
+    );
+    assert_eq!(result.paragraphs[2].base_text, "The claim made here");
+    assert_eq!(result.paragraphs[3].anchor_index, Some(2));
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 10. Headers and footers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn minimal_header_footer_xml(root: &str, content: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:{root} xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  {content}
+</w:{root}>"#
+    )
+}
+
+fn document_rels_xml(relationships: &[(&str, &str, &str)]) -> String {
+    let entries: String = relationships
+        .iter()
+        .map(|(id, kind, target)| {
+            format!(
+                r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{target}"/>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{entries}</Relationships>"#
+    )
+}
+
+#[test]
+fn docx_parses_headers_and_footers_by_section() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Section one body</w:t></w:r>
+            <w:pPr><w:sectPr>
+                <w:headerReference w:type="default" r:id="rId1"/>
+                <w:headerReference w:type="first" r:id="rId2"/>
+                <w:sectPrChange><w:sectPr><w:headerReference w:type="default" r:id="rId9"/></w:sectPr></w:sectPrChange>
+            </w:sectPr></w:pPr></w:p>
+        <w:p><w:r><w:t>Section two body</w:t></w:r></w:p>
+        <w:sectPr>
+            <w:headerReference w:type="default" r:id="rId1"/>
+            <w:footerReference w:type="even" r:id="rId3"/>
+        </w:sectPr>"#,
+    );
+    let header1 = minimal_header_footer_xml(
+        "hdr",
+        r#"<w:p><w:r><w:t>DRAFT</w:t></w:r><w:ins w:id="5" w:author="Alice"><w:r><w:t> PRIVILEGED</w:t></w:r></w:ins></w:p>"#,
+    );
+    let header2 = minimal_header_footer_xml(
+        "hdr",
+        r#"<w:p><w:commentRangeStart w:id="8"/><w:r><w:t>Acme Ltd v Widget plc</w:t></w:r><w:commentRangeEnd w:id="8"/></w:p>"#,
+    );
+    let footer1 = minimal_header_footer_xml("ftr", r#"<w:p><w:r><w:t>Ref 123</w:t></w:r></w:p>"#);
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="8" w:author="Bob"><w:p><w:r><w:t>Party names</w:t></w:r></w:p></w:comment>"#,
+    );
+    let rels = document_rels_xml(&[
+        ("rId1", "header", "header1.xml"),
+        ("rId2", "header", "header2.xml"),
+        ("rId3", "footer", "/word/footer1.xml"),
+        ("rId4", "comments", "comments.xml"),
+    ]);
+    let data = create_docx_bytes(&[
+        ("word/document.xml", &doc_xml),
+        ("word/_rels/document.xml.rels", &rels),
+        ("word/header1.xml", &header1),
+        ("word/header2.xml", &header2),
+        ("word/footer1.xml", &footer1),
+        ("word/comments.xml", &comments_xml),
+    ]);
+
+    let result = docx::parse(&data, "hf.docx").expect("Should parse");
+
+    assert_eq!(result.paragraphs.len(), 5);
+    let labels: Vec<_> = result
+        .paragraphs
+        .iter()
+        .map(|p| (p.kind, p.section, p.header_footer_type))
+        .collect();
+    assert_eq!(
+        labels,
+        vec![
+            (BlockKind::Body, None, None),
+            (BlockKind::Body, None, None),
+            (BlockKind::Header, Some(0), Some(HeaderFooterType::Default)),
+            (BlockKind::Header, Some(0), Some(HeaderFooterType::First)),
+            (BlockKind::Footer, Some(1), Some(HeaderFooterType::Even)),
+        ]
+    );
+    assert_eq!(result.paragraphs[2].base_text, "DRAFT");
+    assert_eq!(result.paragraphs[2].track_changes[0].author, "Alice");
+    assert_eq!(result.paragraphs[3].comments[0].author, "Bob");
+    assert_eq!(result.paragraphs[4].base_text, "Ref 123");
+}
+
+#[test]
+fn docx_reports_unreferenced_header_parts_without_section() {
+    let doc_xml = minimal_document_xml(r#"<w:p><w:r><w:t>Body</w:t></w:r></w:p>"#);
+    let rels = document_rels_xml(&[("rId7", "footer", "footer2.xml")]);
+    let footer = minimal_header_footer_xml("ftr", r#"<w:p><w:r><w:t>Page footer</w:t></w:r></w:p>"#);
+    let data = create_docx_bytes(&[
+        ("word/document.xml", &doc_xml),
+        ("word/_rels/document.xml.rels", &rels),
+        ("word/footer2.xml", &footer),
+    ]);
+
+    let result = docx::parse(&data, "hf.docx").expect("Should parse");
+
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[1].kind, BlockKind::Footer);
+    assert_eq!(result.paragraphs[1].section, None);
+    assert_eq!(result.paragraphs[1].header_footer_type, None);
+}
+
+#[test]
+fn unreferenced_header_parts_are_separate_stories() {
+    // The split mark ending the first part must not reach into the second
+    let doc_xml = minimal_document_xml(r#"<w:p><w:r><w:t>Body</w:t></w:r></w:p>"#);
+    let rels = document_rels_xml(&[
+        ("rId7", "header", "header7.xml"),
+        ("rId8", "header", "header8.xml"),
+    ]);
+    let first = minimal_header_footer_xml(
+        "hdr",
+        &marked_paragraph("ins", "1", "<w:r><w:t>Old draft header</w:t></w:r>"),
+    );
+    let second =
+        minimal_header_footer_xml("hdr", r#"<w:p><w:r><w:t>Other header</w:t></w:r></w:p>"#);
+    let data = create_docx_bytes(&[
+        ("word/document.xml", &doc_xml),
+        ("word/_rels/document.xml.rels", &rels),
+        ("word/header7.xml", &first),
+        ("word/header8.xml", &second),
+    ]);
+
+    let result = docx::parse(&data, "hf.docx").expect("Should parse");
+
+    assert_eq!(result.paragraphs.len(), 3);
+    let other = &result.paragraphs[2];
+    assert_eq!(other.base_text, "Other header");
+    assert_eq!(other.split_from, None);
+    assert!(other.track_changes.is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 11. Tables
+// ═══════════════════════════════════════════════════════════════════════
+
+fn table_row_xml(cells: &[&str]) -> String {
+    let cells: String = cells
+        .iter()
+        .map(|c| format!(r#"<w:tc><w:p><w:r><w:t>{c}</w:t></w:r></w:p></w:tc>"#))
+        .collect();
+    format!("<w:tr>{cells}</w:tr>")
+}
+
+#[test]
+fn paragraphs_record_table_coordinates() {
+    let xml = minimal_document_xml(&format!(
+        r#"<w:p><w:r><w:t>Schedule of loss</w:t></w:r></w:p>
+        <w:tbl>
+            <w:tr><w:trPr><w:tblHeader/></w:trPr>
+                <w:tc><w:p><w:r><w:t>Item</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:t>Amount</w:t></w:r></w:p></w:tc>
+            </w:tr>
+            {}
+            <w:tr>
+                <w:tc><w:tcPr><w:gridSpan w:val="2"/></w:tcPr><w:p><w:r><w:t>Total claimed</w:t></w:r></w:p></w:tc>
+            </w:tr>
+        </w:tbl>
+        <w:p><w:r><w:t>After the table</w:t></w:r></w:p>"#,
+        table_row_xml(&["Lost earnings", "12,000"])
+    ));
+    let paras = paragraphs::parse_document(&xml);
+
+    assert_eq!(paras.len(), 7);
+    assert!(paras[0].table.is_none());
+    assert!(paras[6].table.is_none());
+
+    let header = paras[2].table.as_ref().unwrap();
+    assert_eq!((header.table_index, header.row, header.column), (0, 0, 1));
+    assert!(header.is_header);
+
+    let amount = paras[4].table.as_ref().unwrap();
+    assert_eq!((amount.row, amount.column), (1, 1));
+    assert!(!amount.is_header);
+    assert_eq!(amount.column_header.as_deref(), Some("Amount"));
+
+    let total = paras[5].table.as_ref().unwrap();
+    assert_eq!((total.row, total.column), (2, 0));
+    assert_eq!(total.column_header.as_deref(), Some("Item"));
+}
+
+#[test]
+fn paragraphs_number_nested_tables_and_label_from_first_row() {
+    let xml = minimal_document_xml(&format!(
+        r#"<w:tbl>{}<w:tr>
+            <w:tc><w:p><w:r><w:t>Outer</w:t></w:r></w:p></w:tc>
+            <w:tc><w:tbl>{}</w:tbl><w:p><w:r><w:t>After inner</w:t></w:r></w:p></w:tc>
+        </w:tr></w:tbl>"#,
+        table_row_xml(&["Date", "Event"]),
+        table_row_xml(&["x", "y"])
+    ));
+    let paras = paragraphs::parse_document(&xml);
+    let coords: Vec<_> = paras
+        .iter()
+        .map(|p| {
+            let t = p.table.as_ref().unwrap();
+            (t.table_index, t.row, t.column, t.column_header.clone())
+        })
+        .collect();
+
+    assert_eq!(
+        coords,
+        vec![
+            (0, 0, 0, Some("Date".to_string())),
+            (0, 0, 1, Some("Event".to_string())),
+            (0, 1, 0, Some("Date".to_string())),
+            (1, 0, 0, Some("x".to_string())),
+            (1, 0, 1, Some("y".to_string())),
+            (0, 1, 1, Some("Event".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn merge_matches_table_rows_by_column() {
+    let base_xml = minimal_document_xml(&format!(
+        "<w:tbl>{}{}{}</w:tbl>",
+        table_row_xml(&["Item", "Amount"]),
+        table_row_xml(&["Rent", "500"]),
+        table_row_xml(&["Travel", "500"]),
+    ));
+    let copy_xml = minimal_document_xml(&format!(
+        r#"<w:tbl>{}{}
+            <w:tr>
+                <w:tc><w:p><w:ins w:id="1" w:author="Fay"><w:r><w:t>Parking</w:t></w:r></w:ins></w:p></w:tc>
+                <w:tc><w:p><w:ins w:id="2" w:author="Fay"><w:r><w:t>40</w:t></w:r></w:ins></w:p></w:tc>
+            </w:tr>
+            <w:tr>
+                <w:tc><w:p><w:r><w:t>Travel</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:del w:id="3" w:author="Fay"><w:r><w:delText>500</w:delText></w:r></w:del><w:ins w:id="4" w:author="Fay"><w:r><w:t>450</w:t></w:r></w:ins></w:p></w:tc>
+            </w:tr>
+        </w:tbl>"#,
+        table_row_xml(&["Item", "Amount"]),
+        table_row_xml(&["Rent", "500"]),
+    ));
+    let base = create_docx_bytes(&[("word/document.xml", &base_xml)]);
+    let copy = create_docx_bytes(&[("word/document.xml", &copy_xml)]);
+
+    let result = merge::collate(&[("base.docx", &base), ("fay.docx", &copy)], "base.docx")
+        .expect("Should collate");
+
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| p.revised_text.as_str())
+        .collect();
+    assert_eq!(
+        texts,
+        vec!["Item", "Amount", "Rent", "500", "Parking", "40", "Travel", "500"]
+    );
+    let travel_amount = &result.paragraphs[7];
+    assert_eq!(travel_amount.track_changes.len(), 2);
+    let coords = travel_amount.table.as_ref().unwrap();
+    assert_eq!((coords.row, coords.column), (2, 1));
+    assert_eq!(coords.column_header.as_deref(), Some("Amount"));
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 12. Text boxes and shapes
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn paragraphs_split_text_box_content_from_host() {
+    let xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Clause 4 applies.</w:t></w:r>
+            <w:r><mc:AlternateContent>
+                <mc:Choice Requires="wps"><w:drawing><wp:anchor>
+                    <wp:positionH relativeFrom="column"><wp:posOffset>123825</wp:posOffset></wp:positionH>
+                    <a:graphic><a:graphicData><wps:wsp><wps:txbx><w:txbxContent>
+                        <w:p><w:r><w:t>Callout:</w:t></w:r><w:ins w:id="9" w:author="Gil"><w:r><w:t> see note</w:t></w:r></w:ins></w:p>
+                    </w:txbxContent></wps:txbx></wps:wsp></a:graphicData></a:graphic>
+                </wp:anchor></w:drawing></mc:Choice>
+                <mc:Fallback><w:pict><v:shape><v:textbox><w:txbxContent>
+                    <w:p><w:r><w:t>Callout: see note</w:t></w:r></w:p>
+                </w:txbxContent></v:textbox></v:shape></w:pict></mc:Fallback>
+            </mc:AlternateContent></w:r>
+        </w:p>
+        <w:p><w:r><w:t>Next clause.</w:t></w:r></w:p>"#,
+    );
+    let paras = paragraphs::parse_document(&xml);
+
+    assert_eq!(paras.len(), 3);
+    assert_eq!(track_changes::base_text(&paras[0].segments), "Clause 4 applies.");
+    assert_eq!(paras[1].kind, BlockKind::TextBox);
+    assert_eq!(paras[1].index, 1);
+    assert_eq!(paras[1].anchor_index, Some(0));
+    assert_eq!(
+        track_changes::revised_text(&paras[1].segments),
+        "Callout:see note"
+    );
+    assert!(matches!(
+        &paras[1].segments[1],
+        Segment::Insertion { author, .. } if author == "Gil"
+    ));
+    assert_eq!(paras[2].index, 2);
+    assert_eq!(paras[2].kind, BlockKind::Body);
+}
+
+#[test]
+fn paragraphs_give_drawingml_text_its_own_block() {
+    let xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>See diagram</w:t></w:r>
+            <w:r><w:drawing><wp:inline><wp:extent cx="100" cy="200"/>
+                <a:graphic><a:graphicData><wps:wsp><wps:txBody>
+                    <a:p><a:r><a:t>Step 1</a:t></a:r></a:p>
+                    <a:p><a:r><a:t>Step 2</a:t></a:r></a:p>
+                </wps:txBody></wps:wsp></a:graphicData></a:graphic>
+            </wp:inline></w:drawing></w:r>
+        </w:p>"#,
+    );
+    let paras = paragraphs::parse_document(&xml);
+
+    let texts: Vec<(BlockKind, String, Option<usize>)> = paras
+        .iter()
+        .map(|p| (p.kind, track_changes::base_text(&p.segments), p.anchor_index))
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            (BlockKind::Body, "See diagram".to_string(), None),
+            (BlockKind::TextBox, "Step 1".to_string(), Some(0)),
+            (BlockKind::TextBox, "Step 2".to_string(), Some(0)),
+        ]
+    );
+}
+
+#[test]
+fn text_box_tables_are_labelled_apart_from_each_other_and_the_body() {
+    let text_box = |rows: &[String]| {
+        format!(
+            r#"<w:r><w:drawing><wps:txbx><w:txbxContent><w:tbl>{}</w:tbl><w:p/></w:txbxContent></wps:txbx></w:drawing></w:r>"#,
+            rows.concat()
+        )
+    };
+    let xml = minimal_document_xml(&format!(
+        r#"<w:tbl>{}{}</w:tbl>
+        <w:p><w:r><w:t>First host.</w:t></w:r>{}</w:p>
+        <w:p><w:r><w:t>Second host.</w:t></w:r>{}</w:p>"#,
+        table_row_xml(&["Item", "Amount"]),
+        table_row_xml(&["Rent", "500"]),
+        text_box(&[table_row_xml(&["Name", "Role"]), table_row_xml(&["Ann", "Chair"])]),
+        text_box(&[table_row_xml(&["Step", "Owner"]), table_row_xml(&["Sign", "Bo"])]),
+    ));
+    let paras = paragraphs::parse_document(&xml);
+
+    let labels: Vec<(String, Option<String>)> = paras
+        .iter()
+        .filter(|p| p.table.as_ref().is_some_and(|t| t.row == 1))
+        .map(|p| {
+            (
+                track_changes::base_text(&p.segments),
+                p.table.as_ref().unwrap().column_header.clone(),
+            )
+        })
+        .collect();
+    let label = |text: &str, header: &str| (text.to_string(), Some(header.to_string()));
+    assert_eq!(
+        labels,
+        vec![
+            label("Rent", "Item"),
+            label("500", "Amount"),
+            label("Ann", "Name"),
+            label("Chair", "Role"),
+            label("Sign", "Step"),
+            label("Bo", "Owner"),
+        ]
+    );
+}
+
+#[test]
+fn merge_remaps_text_box_anchors() {
+    let text_box = r#"<w:r><w:drawing><wps:txbx><w:txbxContent>
+        <w:p><w:r><w:t>Margin note text</w:t></w:r></w:p>
+    </w:txbxContent></wps:txbx></w:drawing></w:r>"#;
+    let base_xml = minimal_document_xml(&format!(
+        r#"<w:p><w:r><w:t>First clause of the deed.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Second clause of the deed.</w:t></w:r>{text_box}</w:p>"#
+    ));
+    let copy_xml = minimal_document_xml(&format!(
+        r#"<w:p><w:ins w:id="1" w:author="Hal"><w:r><w:t>Recital inserted ahead of everything.</w:t></w:r></w:ins></w:p>
+        <w:p><w:r><w:t>First clause of the deed.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Second clause of the deed.</w:t></w:r>{text_box}</w:p>"#
+    ));
+    let base = create_docx_bytes(&[("word/document.xml", &base_xml)]);
+    let copy = create_docx_bytes(&[("word/document.xml", &copy_xml)]);
+
+    let result = merge::collate(&[("base.docx", &base), ("hal.docx", &copy)], "base.docx")
+        .expect("Should collate");
+
+    assert_eq!(result.paragraphs.len(), 4);
+    let text_box = &result.paragraphs[3];
+    assert_eq!(text_box.kind, BlockKind::TextBox);
+    assert_eq!(text_box.anchor_index, Some(2));
+    assert_eq!(result.paragraphs[2].base_text, "Second clause of the deed.");
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 13. Namespace-aware element matching
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn paragraphs_match_elements_by_namespace_not_prefix() {
+    let prefixed = r#"<?xml version="1.0" encoding="UTF-8"?>
+<wp:document xmlns:wp="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <wp:body>
+    <wp:p><wp:r><wp:t>Keep </wp:t></wp:r><wp:del wp:id="1" wp:author="Ida"><wp:r><wp:delText>this</wp:delText></wp:r></wp:del></wp:p>
+  </wp:body>
+</wp:document>"#;
+    let default_ns = r#"<?xml version="1.0" encoding="UTF-8"?>
+<document xmlns="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <body>
+    <p><r><t>Keep </t></r><del id="1" author="Ida"><r><delText>this</delText></r></del></p>
+  </body>
+</document>"#;
+
+    for xml in [prefixed, default_ns] {
+        let paras = paragraphs::parse_document(xml);
+        assert_eq!(paras.len(), 1);
+        assert_eq!(track_changes::base_text(&paras[0].segments), "Keepthis");
+        assert!(matches!(
+            &paras[0].segments[1],
+            Segment::Deletion { author, .. } if author == "Ida"
+        ));
+    }
+}
+
+#[test]
+fn paragraphs_ignore_foreign_elements_with_word_local_names() {
+    let xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>Stable text</w:t></w:r>
+            <x:ins xmlns:x="urn:example:extension" x:id="7" x:author="Tool"><w:r><w:t> plus more</w:t></w:r></x:ins>
+        </w:p>
+        <a:p><a:r><a:t>Stray DrawingML paragraph</a:t></a:r></a:p>"#,
+    );
+    let paras = paragraphs::parse_document(&xml);
+
+    assert_eq!(paras.len(), 1);
+    assert!(paras[0]
+        .segments
+        .iter()
+        .all(|s| matches!(s, Segment::Stable(_))));
+    assert_eq!(
+        track_changes::base_text(&paras[0].segments),
+        "Stable textplus more"
+    );
+}
+
+#[test]
+fn comments_match_by_namespace() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<c:comments xmlns:c="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:o="urn:other">
+  <c:comment c:id="1" c:author="Jo"><c:p><c:r><c:t>Real</c:t></c:r></c:p></c:comment>
+  <o:comment o:id="2" o:author="Nobody"><o:t>Not a Word comment</o:t></o:comment>
+</c:comments>"#;
+    let map = comments::parse_comments(xml);
+
+    assert_eq!(map.len(), 1);
+    assert_eq!(map["1"].author, "Jo");
+    assert_eq!(map["1"].text, "Real");
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 14. Strict OOXML
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Rewrite a Transitional part into its Strict (ISO/IEC 29500 Strict) twin
+fn to_strict(xml: &str) -> String {
+    xml.replace(
+        "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
+        "http://purl.oclc.org/ooxml/wordprocessingml/main",
+    )
+    .replace(
+        "http://schemas.openxmlformats.org/drawingml/2006/main",
+        "http://purl.oclc.org/ooxml/drawingml/main",
+    )
+    .replace(
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships",
+        "http://purl.oclc.org/ooxml/officeDocument/relationships",
+    )
+}
+
+#[test]
+fn docx_strict_package_matches_transitional_twin() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:commentRangeStart w:id="1"/><w:r><w:t>The Tenant shall pay</w:t></w:r><w:commentRangeEnd w:id="1"/>
+            <w:ins w:id="2" w:author="Kim"><w:r><w:t> promptly</w:t></w:r></w:ins>
+            <w:r><w:footnoteReference w:id="1"/></w:r>
+            <w:r><w:drawing><a:graphic><a:graphicData><wps:wsp><wps:txBody>
+                <a:p><a:r><a:t>Shape label</a:t></a:r></a:p>
+            </wps:txBody></wps:wsp></a:graphicData></a:graphic></w:drawing></w:r></w:p>
+        <w:tbl><w:tr><w:tc><w:p><w:r><w:t>Rent</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+        <w:sectPr><w:headerReference w:type="default" r:id="rId1"/></w:sectPr>"#,
+    );
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="1" w:author="Lee"><w:p><w:r><w:t>Amount?</w:t></w:r></w:p></w:comment>"#,
+    );
+    let footnotes_xml = minimal_notes_xml(
+        "footnote",
+        r#"<w:footnote w:id="1"><w:p><w:del w:id="3" w:author="Kim"><w:r><w:delText>Old note</w:delText></w:r></w:del></w:p></w:footnote>"#,
+    );
+    let header_xml =
+        minimal_header_footer_xml("hdr", r#"<w:p><w:r><w:t>DRAFT</w:t></w:r></w:p>"#);
+    let rels = document_rels_xml(&[("rId1", "header", "header1.xml")]);
+
+    let parts = [
+        ("word/document.xml", doc_xml),
+        ("word/comments.xml", comments_xml),
+        ("word/footnotes.xml", footnotes_xml),
+        ("word/header1.xml", header_xml),
+        ("word/_rels/document.xml.rels", rels),
+    ];
+    let transitional: Vec<(&str, &str)> = parts.iter().map(|(n, x)| (*n, x.as_str())).collect();
+    let strict_parts: Vec<(&str, String)> = parts.iter().map(|(n, x)| (*n, to_strict(x))).collect();
+    let strict: Vec<(&str, &str)> = strict_parts
+        .iter()
+        .map(|(n, x)| (*n, x.as_str()))
+        .collect();
+    assert!(strict[0].1.contains("purl.oclc.org"));
+
+    let expected = docx::parse(&create_docx_bytes(&transitional), "lease.docx").unwrap();
+    let actual = docx::parse(&create_docx_bytes(&strict), "lease.docx").unwrap();
+
+    assert_eq!(expected.paragraphs.len(), 5);
+    assert_eq!(
+        serde_json::to_string(&actual).unwrap(),
+        serde_json::to_string(&expected).unwrap()
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 15. Package relationships and content types
+// ═══════════════════════════════════════════════════════════════════════
+
+fn package_rels_xml(relationships: &[(&str, &str, &str)]) -> String {
+    let entries: String = relationships
+        .iter()
+        .map(|(id, rel_type, target)| {
+            format!(r#"<Relationship Id="{id}" Type="{rel_type}" Target="{target}"/>"#)
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{entries}</Relationships>"#
+    )
+}
+
+const OFFICE_DOCUMENT_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+const CORE_PROPERTIES_REL: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties";
+
+#[test]
+fn docx_resolves_renamed_parts_through_relationships() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:commentRangeStart w:id="1"/><w:r><w:t>Main text</w:t></w:r><w:commentRangeEnd w:id="1"/></w:p>
+        <w:sectPr><w:headerReference w:type="default" r:id="rIdH"/></w:sectPr>"#,
+    );
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="1" w:author="Mo"><w:p><w:r><w:t>Found it</w:t></w:r></w:p></w:comment>"#,
+    );
+    let header_xml =
+        minimal_header_footer_xml("hdr", r#"<w:p><w:r><w:t>Shared header</w:t></w:r></w:p>"#);
+    let core_xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties"
+                   xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Renamed</dc:title></cp:coreProperties>"#;
+    let data = create_docx_bytes(&[
+        (
+            "_rels/.rels",
+            &package_rels_xml(&[
+                ("rId1", OFFICE_DOCUMENT_REL, "doc/main.xml"),
+                ("rId2", CORE_PROPERTIES_REL, "meta/props.xml"),
+            ]),
+        ),
+        ("doc/main.xml", &doc_xml),
+        (
+            "doc/_rels/main.xml.rels",
+            &document_rels_xml(&[
+                ("rIdC", "comments", "remarks.xml"),
+                ("rIdH", "header", "../shared/top.xml"),
+            ]),
+        ),
+        ("doc/remarks.xml", &comments_xml),
+        ("shared/top.xml", &header_xml),
+        ("meta/props.xml", core_xml),
+    ]);
+
+    let result = docx::parse(&data, "renamed.docx").expect("Should parse");
+
+    assert_eq!(result.document_title.as_deref(), Some("Renamed"));
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].comments[0].author, "Mo");
+    assert_eq!(result.paragraphs[1].kind, BlockKind::Header);
+    assert_eq!(result.paragraphs[1].base_text, "Shared header");
+}
+
+#[test]
+fn docx_finds_macro_template_main_part_by_content_type() {
+    let doc_xml = minimal_document_xml(r#"<w:p><w:r><w:t>Template body</w:t></w:r></w:p>"#);
+    let content_types = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="xml" ContentType="application/xml"/>
+  <Override PartName="/word/template.xml" ContentType="application/vnd.ms-word.template.macroEnabledTemplate.main+xml"/>
+</Types>"#;
+    let data = create_docx_bytes(&[
+        ("[Content_Types].xml", content_types),
+        ("word/template.xml", &doc_xml),
+    ]);
+
+    let result = docx::parse(&data, "letter.dotm").expect("Should parse");
+    assert_eq!(result.paragraphs.len(), 1);
+    assert_eq!(result.paragraphs[0].base_text, "Template body");
+}
+
+#[test]
+fn docx_accepts_strict_office_document_relationship() {
+    let doc_xml = to_strict(&minimal_document_xml(
+        r#"<w:p><w:r><w:t>Strict body</w:t></w:r></w:p>"#,
+    ));
+    let data = create_docx_bytes(&[
+        (
+            "_rels/.rels",
+            &package_rels_xml(&[(
+                "rId1",
+                "http://purl.oclc.org/ooxml/officeDocument/relationships/officeDocument",
+                "word/body.xml",
+            )]),
+        ),
+        ("word/body.xml", &doc_xml),
+    ]);
+
+    let result = docx::parse(&data, "strict.docx").expect("Should parse");
+    assert_eq!(result.paragraphs[0].base_text, "Strict body");
+}
+
+#[test]
+fn docx_error_names_missing_relationship() {
+    let no_rel = create_docx_bytes(&[
+        ("_rels/.rels", &package_rels_xml(&[("rId2", CORE_PROPERTIES_REL, "docProps/core.xml")])),
+        ("word/document.xml", &minimal_document_xml("")),
+    ]);
+    let err = docx::parse(&no_rel, "a.docx").unwrap_err();
+    assert!(err.contains("officeDocument"), "got '{}'", err);
+
+    let dangling = create_docx_bytes(&[(
+        "_rels/.rels",
+        &package_rels_xml(&[("rId1", OFFICE_DOCUMENT_REL, "word/missing.xml")]),
+    )]);
+    let err = docx::parse(&dangling, "b.docx").unwrap_err();
+    assert!(
+        err.contains("rId1") && err.contains("word/missing.xml"),
+        "got '{}'",
+        err
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 16. Flat OPC (single-file Word XML)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Wrap parts into a Flat OPC `pkg:package` document, dropping each part's
+/// XML declaration as Word does
+fn flat_opc(parts: &[(&str, &str, &str)]) -> String {
+    let body: String = parts
+        .iter()
+        .map(|(name, content_type, xml)| {
+            let xml = match xml.find("?>") {
+                Some(end) if xml.starts_with("<?xml") => &xml[end + 2..],
+                _ => xml,
+            };
+            format!(
+                r#"<pkg:part pkg:name="/{name}" pkg:contentType="{content_type}"><pkg:xmlData>{xml}</pkg:xmlData></pkg:part>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" standalone="yes"?>
+<?mso-application progid="Word.Document"?>
+<pkg:package xmlns:pkg="http://schemas.microsoft.com/office/2006/xmlPackage">{body}
+<pkg:part pkg:name="/word/media/image1.png" pkg:contentType="image/png" pkg:compression="store"><pkg:binaryData>iVBORw0KGgo=</pkg:binaryData></pkg:part>
+</pkg:package>"#
+    )
+}
+
+#[test]
+fn docx_reads_flat_opc_like_its_zip_twin() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:commentRangeStart w:id="1"/><w:r><w:t>Flat text</w:t></w:r><w:commentRangeEnd w:id="1"/>
+            <w:del w:id="2" w:author="Ned"><w:r><w:delText> gone</w:delText></w:r></w:del></w:p>"#,
+    );
+    let comments_xml = minimal_comments_xml(
+        r#"<w:comment w:id="1" w:author="Ned"><w:p><w:r><w:t>Check</w:t></w:r></w:p></w:comment>"#,
+    );
+    let core_xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties"
+                   xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Flat title</dc:title></cp:coreProperties>"#;
+    let package_rels = package_rels_xml(&[
+        ("rId1", OFFICE_DOCUMENT_REL, "word/document.xml"),
+        ("rId2", CORE_PROPERTIES_REL, "docProps/core.xml"),
+    ]);
+    let document_rels = document_rels_xml(&[("rId5", "comments", "comments.xml")]);
+    let rels_type = "application/vnd.openxmlformats-package.relationships+xml";
+    let parts = [
+        ("_rels/.rels", rels_type, package_rels.as_str()),
+        (
+            "word/document.xml",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+            doc_xml.as_str(),
+        ),
+        ("word/_rels/document.xml.rels", rels_type, document_rels.as_str()),
+        (
+            "word/comments.xml",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.comments+xml",
+            comments_xml.as_str(),
+        ),
+        (
+            "docProps/core.xml",
+            "application/vnd.openxmlformats-package.core-properties+xml",
+            core_xml,
+        ),
+    ];
+
+    let flat = flat_opc(&parts);
+    let zipped: Vec<(&str, &str)> = parts.iter().map(|(n, _, x)| (*n, *x)).collect();
+
+    let from_flat = docx::parse(flat.as_bytes(), "doc.xml").expect("Should parse Flat OPC");
+    let from_zip = docx::parse(&create_docx_bytes(&zipped), "doc.xml").unwrap();
+
+    assert_eq!(from_flat.document_title.as_deref(), Some("Flat title"));
+    assert_eq!(from_flat.paragraphs[0].comments[0].text, "Check");
+    assert_eq!(
+        serde_json::to_string(&from_flat).unwrap(),
+        serde_json::to_string(&from_zip).unwrap()
+    );
+}
+
+#[test]
+fn docx_flat_opc_without_rels_uses_part_content_types() {
+    let doc_xml = minimal_document_xml(r#"<w:p><w:r><w:t>Only part</w:t></w:r></w:p>"#);
+    let flat = flat_opc(&[(
+        "word/body.xml",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+        &doc_xml,
+    )]);
+
+    let result = docx::parse(flat.as_bytes(), "flat.xml").expect("Should parse");
+    assert_eq!(result.paragraphs[0].base_text, "Only part");
+}
+
+#[test]
+fn docx_non_package_xml_is_still_a_zip_error() {
+    let err = docx::parse(b"<html><body>hello</body></html>", "page.html").unwrap_err();
+    assert!(err.contains("ZIP"), "got '{}'", err);
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 17. OpenDocument text (.odt)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Build an .odt package from the children of `office:text` and, optionally,
+/// a document title for meta.xml
+fn create_odt_bytes(text_content: &str, title: Option<&str>) -> Vec<u8> {
+    let content_xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+    xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"
+    xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0"
+    xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0">
+<office:body><office:text>{text_content}</office:text></office:body>
+</office:document-content>"#
+    );
+    let meta_xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0"
+    xmlns:dc="http://purl.org/dc/elements/1.1/">
+<office:meta>{}</office:meta></office:document-meta>"#,
+        title
+            .map(|t| format!("<dc:title>{}</dc:title>", t))
+            .unwrap_or_default()
+    );
+    create_docx_bytes(&[
+        ("mimetype", "application/vnd.oasis.opendocument.text"),
+        ("content.xml", &content_xml),
+        ("meta.xml", &meta_xml),
+    ])
+}
+
+/// A `text:changed-region` for `text:tracked-changes`
+fn odt_change(id: &str, kind: &str, author: &str, deleted: &str) -> String {
+    format!(
+        r#"<text:changed-region text:id="{id}"><text:{kind}><office:change-info><dc:creator>{author}</dc:creator><dc:date>2024-03-01T10:00:00</dc:date></office:change-info>{deleted}</text:{kind}></text:changed-region>"#
+    )
+}
+
+#[test]
+fn odt_maps_tracked_changes_onto_segments() {
+    let changes = format!(
+        "<text:tracked-changes>{}{}</text:tracked-changes>",
+        odt_change("ct1", "insertion", "Ivy", ""),
+        odt_change("ct2", "deletion", "Dan", "<text:p>old words </text:p>"),
+    );
+    let data = create_odt_bytes(
+        &format!(
+            r#"{changes}<text:p>Keep <text:change-start text:change-id="ct1"/>new<text:change-end text:change-id="ct1"/> and <text:change text:change-id="ct2"/>end</text:p>"#
+        ),
+        Some("Odt title"),
+    );
+
+    let result = odt::parse(&data, "review.odt").expect("Should parse .odt");
+    assert_eq!(result.document_title.as_deref(), Some("Odt title"));
+    assert_eq!(result.paragraphs.len(), 1);
+    let block = &result.paragraphs[0];
+    assert_eq!(block.base_text, "Keep  and old words end");
+    assert_eq!(block.revised_text, "Keep new and end");
+    let authors: Vec<&str> = block
+        .track_changes
+        .iter()
+        .map(|c| c.author.as_str())
+        .collect();
+    assert_eq!(authors, vec!["Ivy", "Dan"]);
+}
+
+#[test]
+fn odt_format_changes_are_reported_as_warnings() {
+    let changes = format!(
+        "<text:tracked-changes>{}{}</text:tracked-changes>",
+        odt_change("ct1", "insertion", "Ivy", ""),
+        odt_change("ct2", "format-change", "Fay", ""),
+    );
+    let data = create_odt_bytes(
+        &format!(
+            r#"{changes}<text:p><text:change-start text:change-id="ct2"/>Bold<text:change-end text:change-id="ct2"/> and <text:change-start text:change-id="ct1"/>new<text:change-end text:change-id="ct1"/></text:p>"#
+        ),
+        None,
+    );
+
+    let result = odt::parse(&data, "review.odt").expect("Should parse .odt");
+    let block = &result.paragraphs[0];
+    assert_eq!(block.base_text, "Bold and ");
+    assert_eq!(block.revised_text, "Bold and new");
+    assert_eq!(block.track_changes.len(), 1);
+    assert_eq!(
+        result.warnings,
+        vec![
+            "1 formatting change(s) in this .odt file are not read; only insertions and deletions are"
+                .to_string()
+        ]
+    );
+}
+
+#[test]
+fn odt_change_info_paragraph_is_not_deleted_text() {
+    let changes = r#"<text:tracked-changes><text:changed-region text:id="ct1"><text:deletion><office:change-info><dc:creator>Dan</dc:creator><text:p>Why removed</text:p></office:change-info><text:p>gone</text:p></text:deletion></text:changed-region></text:tracked-changes>"#;
+    let data = create_odt_bytes(
+        &format!(r#"{changes}<text:p>Stay<text:change text:change-id="ct1"/></text:p>"#),
+        None,
+    );
+
+    let parsed = odt::read(&data).unwrap();
+    match &parsed.paragraphs[0].segments[1] {
+        Segment::Deletion { text, .. } => assert_eq!(text, "gone"),
+        other => panic!("expected deletion, got {:?}", other),
+    }
+}
+
+#[test]
+fn odt_annotations_become_comments_with_anchor_text() {
+    let data = create_odt_bytes(
+        r#"<text:p>Before <office:annotation office:name="c1"><dc:creator>Ann</dc:creator><dc:date>2024-03-02T09:00:00</dc:date><meta:creator-initials>AN</meta:creator-initials><text:p>Please check</text:p></office:annotation>this claim<office:annotation-end office:name="c1"/> after</text:p>
+           <text:p>Point<office:annotation><dc:creator>Bo</dc:creator><text:p>Note here</text:p></office:annotation> comment</text:p>"#,
+        None,
+    );
+
+    let result = odt::parse(&data, "review.odt").unwrap();
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].base_text, "Before this claim after");
+
+    let ranged = &result.paragraphs[0].comments[0];
+    assert_eq!(ranged.author, "Ann");
+    assert_eq!(ranged.text, "Please check");
+    assert_eq!(ranged.anchor_text, "this claim");
+    assert_eq!(ranged.initials.as_deref(), Some("AN"));
+
+    let point = &result.paragraphs[1].comments[0];
+    assert_eq!(point.author, "Bo");
+    assert_eq!(point.text, "Note here");
+    assert_eq!(result.paragraphs[1].base_text, "Point comment");
+}
+
+#[test]
+fn odt_notes_follow_body_anchored_to_citing_paragraph() {
+    let data = create_odt_bytes(
+        r#"<text:p>First</text:p>
+           <text:p>Cited<text:note text:id="ftn1" text:note-class="footnote"><text:note-citation>1</text:note-citation><text:note-body><text:p>Footnote text</text:p></text:note-body></text:note> here</text:p>
+           <text:p>Last<text:note text:id="edn1" text:note-class="endnote"><text:note-citation>i</text:note-citation><text:note-body><text:p>Endnote text</text:p></text:note-body></text:note></text:p>"#,
+        None,
+    );
+
+    let result = odt::parse(&data, "notes.odt").unwrap();
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| p.base_text.as_str())
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            "First",
+            "Cited here",
+            "Last",
+            "Footnote text",
+            "Endnote text"
+        ]
+    );
+
+    let footnote = &result.paragraphs[3];
+    assert_eq!(footnote.kind, BlockKind::Footnote);
+    assert_eq!(footnote.note_id.as_deref(), Some("ftn1"));
+    assert_eq!(footnote.anchor_index, Some(1));
+    assert_eq!(result.paragraphs[4].kind, BlockKind::Endnote);
+    assert_eq!(result.paragraphs[4].anchor_index, Some(2));
+}
+
+#[test]
+fn odt_tables_and_text_boxes_get_coordinates_and_hosts() {
+    let data = create_odt_bytes(
+        r#"<table:table><table:table-header-rows><table:table-row><table:table-cell><text:p>Name</text:p></table:table-cell><table:table-cell><text:p>Score</text:p></table:table-cell></table:table-row></table:table-header-rows>
+           <table:table-row><table:table-cell><text:p>Ada</text:p></table:table-cell><table:table-cell><text:p>10</text:p></table:table-cell></table:table-row></table:table>
+           <text:p>Host<draw:frame><draw:text-box><text:p>Boxed</text:p></draw:text-box></draw:frame></text:p>"#,
+        None,
+    );
+
+    let result = odt::parse(&data, "table.odt").unwrap();
+    let score = &result.paragraphs[3];
+    assert_eq!(score.base_text, "10");
+    let coords = score.table.as_ref().expect("cell coordinates");
+    assert_eq!((coords.table_index, coords.row, coords.column), (0, 1, 1));
+    assert!(!coords.is_header);
+    assert_eq!(coords.column_header.as_deref(), Some("Score"));
+    assert!(result.paragraphs[0].table.as_ref().unwrap().is_header);
+
+    let text_box = &result.paragraphs[5];
+    assert_eq!(text_box.kind, BlockKind::TextBox);
+    assert_eq!(text_box.base_text, "Boxed");
+    assert_eq!(text_box.anchor_index, Some(4));
+}
+
+#[test]
+fn odt_whitespace_elements_and_collapsing() {
+    let data = create_odt_bytes(
+        "<text:p>  Two<text:s text:c=\"2\"/>spaces\n   and<text:tab/>tab  </text:p>",
+        None,
+    );
+    let parsed = odt::read(&data).unwrap();
+    assert_eq!(
+        track_changes::base_text(&parsed.paragraphs[0].segments),
+        "Two  spaces and\ttab"
+    );
+}
+
+#[test]
+fn odt_space_counts_from_the_file_are_bounded() {
+    let data = create_odt_bytes(
+        r#"<text:p>Wide<text:s text:c="18446744073709551615"/>gap<text:s text:c="4000000000"/>end</text:p>"#,
+        None,
+    );
+    let parsed = odt::read(&data).unwrap();
+    let text = track_changes::base_text(&parsed.paragraphs[0].segments);
+    assert!(text.starts_with("Wide ") && text.ends_with(" end"));
+    assert!(text.len() <= 2 * 1024 + "Widegapend".len());
+}
+
+#[test]
+fn odt_is_detected_by_format_dispatch_and_merges_with_docx() {
+    let docx = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(r#"<w:p><w:r><w:t>Shared sentence here.</w:t></w:r></w:p>"#),
+    )]);
+    let changes = format!(
+        "<text:tracked-changes>{}</text:tracked-changes>",
+        odt_change("ct1", "insertion", "Olga", "")
+    );
+    let odt_data = create_odt_bytes(
+        &format!(
+            r#"{changes}<text:p>Shared sentence here.<text:change-start text:change-id="ct1"/> Added.<text:change-end text:change-id="ct1"/></text:p>"#
+        ),
+        None,
+    );
+
+    let via_dispatch: CollateResult =
+        serde_json::from_str(&crate::parse_docx(&odt_data, "olga.odt")).unwrap();
+    assert!(via_dispatch.error.is_none());
+    assert_eq!(
+        via_dispatch.paragraphs[0].revised_text,
+        "Shared sentence here. Added."
+    );
+
+    let merged = merge::collate(
+        &[("base.docx", &docx[..]), ("olga.odt", &odt_data[..])],
+        "base.docx",
+    )
+    .expect("Should merge");
+    assert_eq!(merged.paragraphs.len(), 1);
+    let block = &merged.paragraphs[0];
+    assert_eq!(
+        block.track_changes[0].source_file.as_deref(),
+        Some("olga.odt")
+    );
+    assert_eq!(
+        block.reviewer_versions[0].resulting_text,
+        "Shared sentence here. Added."
+    );
+}
+
+#[test]
+fn odt_without_content_is_an_error() {
+    let data = create_docx_bytes(&[("mimetype", "application/vnd.oasis.opendocument.text")]);
+    let err = odt::parse(&data, "empty.odt").unwrap_err();
+    assert!(err.contains("content.xml"), "got '{}'", err);
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 18. Legacy Word binary (.doc)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Write streams into a version 3 compound file. The mini stream cutoff is 0,
+/// so every stream lives in regular 512-byte sectors.
+fn create_cfb_bytes(streams: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    const END: u32 = 0xFFFF_FFFE;
+    let sectors_for = |len: usize| len.div_ceil(512);
+    let stream_sectors: usize = streams.iter().map(|(_, s)| sectors_for(s.len())).sum();
+    let dir_sectors = sectors_for((streams.len() + 1) * 128);
+    let mut fat_sectors = 1;
+    while fat_sectors * 128 < fat_sectors + dir_sectors + stream_sectors {
+        fat_sectors += 1;
+    }
+
+    let mut fat: Vec<u32> = vec![0xFFFF_FFFD; fat_sectors];
+    let chain = |fat: &mut Vec<u32>, count: usize| -> u32 {
+        if count == 0 {
+            return END;
+        }
+        let start = fat.len() as u32;
+        for i in 1..count {
+            fat.push(start + i as u32);
+        }
+        fat.push(END);
+        start
+    };
+    let dir_start = chain(&mut fat, dir_sectors);
+    let starts: Vec<u32> = streams
+        .iter()
+        .map(|(_, s)| chain(&mut fat, sectors_for(s.len())))
+        .collect();
+    fat.resize(fat_sectors * 128, 0xFFFF_FFFF);
+
+    let mut out = vec![0u8; 512];
+    out[..8].copy_from_slice(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+    out[0x18..0x1A].copy_from_slice(&0x3Eu16.to_le_bytes());
+    out[0x1A..0x1C].copy_from_slice(&3u16.to_le_bytes());
+    out[0x1C..0x1E].copy_from_slice(&0xFFFEu16.to_le_bytes());
+    out[0x1E..0x20].copy_from_slice(&9u16.to_le_bytes());
+    out[0x20..0x22].copy_from_slice(&6u16.to_le_bytes());
+    out[0x2C..0x30].copy_from_slice(&(fat_sectors as u32).to_le_bytes());
+    out[0x30..0x34].copy_from_slice(&dir_start.to_le_bytes());
+    out[0x3C..0x40].copy_from_slice(&END.to_le_bytes());
+    out[0x44..0x48].copy_from_slice(&END.to_le_bytes());
+    for i in 0..109 {
+        let sector = if i < fat_sectors {
+            i as u32
+        } else {
+            0xFFFF_FFFF
+        };
+        out[0x4C + i * 4..0x50 + i * 4].copy_from_slice(&sector.to_le_bytes());
+    }
+    for entry in fat {
+        out.extend_from_slice(&entry.to_le_bytes());
+    }
+
+    // Directory: the root entry, then the streams as a chain of right siblings
+    let mut directory = Vec::new();
+    let entry = |name: &str, kind: u8, right: u32, child: u32, start: u32, size: usize| {
+        let mut e = vec![0u8; 128];
+        let units: Vec<u16> = name.encode_utf16().collect();
+        for (i, u) in units.iter().enumerate() {
+            e[i * 2..i * 2 + 2].copy_from_slice(&u.to_le_bytes());
+        }
+        e[64..66].copy_from_slice(&(((units.len() + 1) * 2) as u16).to_le_bytes());
+        e[66] = kind;
+        e[68..72].copy_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+        e[72..76].copy_from_slice(&right.to_le_bytes());
+        e[76..80].copy_from_slice(&child.to_le_bytes());
+        e[116..120].copy_from_slice(&start.to_le_bytes());
+        e[120..124].copy_from_slice(&(size as u32).to_le_bytes());
+        e
+    };
+    directory.extend(entry("Root Entry", 5, 0xFFFF_FFFF, 1, END, 0));
+    for (i, (name, data)) in streams.iter().enumerate() {
+        let right = if i + 1 < streams.len() {
+            i as u32 + 2
+        } else {
+            0xFFFF_FFFF
+        };
+        directory.extend(entry(name, 2, right, 0xFFFF_FFFF, starts[i], data.len()));
+    }
+    directory.resize(dir_sectors * 512, 0);
+    out.extend(directory);
+    for (_, data) in streams {
+        let mut data = data.clone();
+        data.resize(sectors_for(data.len()) * 512, 0);
+        out.extend(data);
+    }
+    out
+}
+
+/// A Word 97 document for tests. Each story is a list of (text, CHPX sprms)
+/// runs; table stream structures are added by their FibRgFcLcb index.
+struct DocFixture {
+    main: Vec<(&'static str, Vec<u8>)>,
+    footnotes: Vec<(&'static str, Vec<u8>)>,
+    headers: Vec<(&'static str, Vec<u8>)>,
+    annotations: Vec<(&'static str, Vec<u8>)>,
+    /// Store text as 8-bit Windows-1252 rather than UTF-16
+    compressed: bool,
+    n_fib: u16,
+    encrypted: bool,
+    authors: Vec<&'static str>,
+    table_parts: Vec<(usize, Vec<u8>)>,
+    title: Option<&'static str>,
+    /// Raw SummaryInformation stream, used instead of one made from `title`
+    summary: Option<Vec<u8>>,
+    /// (offset, value) longs written over the FIB, e.g. a damaged CP count
+    fib_longs: Vec<(usize, u32)>,
+}
+
+impl Default for DocFixture {
+    fn default() -> Self {
+        DocFixture {
+            main: Vec::new(),
+            footnotes: Vec::new(),
+            headers: Vec::new(),
+            annotations: Vec::new(),
+            compressed: false,
+            n_fib: 0x00C1,
+            encrypted: false,
+            authors: Vec::new(),
+            table_parts: Vec::new(),
+            title: None,
+            summary: None,
+            fib_longs: Vec::new(),
+        }
+    }
+}
+
+impl DocFixture {
+    fn bytes(&self) -> Vec<u8> {
+        const TEXT_START: usize = 0x600;
+        let ccp = |runs: &[(&str, Vec<u8>)]| -> u32 {
+            runs.iter()
+                .map(|(t, _)| t.encode_utf16().count() as u32)
+                .sum()
+        };
+        let runs: Vec<&(&str, Vec<u8>)> = self
+            .main
+            .iter()
+            .chain(&self.footnotes)
+            .chain(&self.headers)
+            .chain(&self.annotations)
+            .collect();
+
+        // Text, and the fc range of each run
+        let mut word = vec![0u8; TEXT_START];
+        let mut fc_runs = Vec::new();
+        for (text, sprms) in &runs {
+            let start = word.len() as u32;
+            for unit in text.encode_utf16() {
+                if self.compressed {
+                    word.push(unit as u8);
+                } else {
+                    word.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            fc_runs.push((start, word.len() as u32, sprms.clone()));
+        }
+        let text_end = word.len() as u32;
+
+        // One CHPX FKP page holding every run
+        word.resize(word.len().div_ceil(512) * 512, 0);
+        let pn = (word.len() / 512) as u32;
+        let mut page = vec![0u8; 512];
+        let crun = fc_runs.len();
+        let mut free = 511;
+        for (i, (start, end, sprms)) in fc_runs.iter().enumerate() {
+            page[i * 4..i * 4 + 4].copy_from_slice(&start.to_le_bytes());
+            page[i * 4 + 4..i * 4 + 8].copy_from_slice(&end.to_le_bytes());
+            if !sprms.is_empty() {
+                free = (free - 1 - sprms.len()) & !1;
+                page[free] = sprms.len() as u8;
+                page[free + 1..free + 1 + sprms.len()].copy_from_slice(sprms);
+                page[(crun + 1) * 4 + i] = (free / 2) as u8;
+            }
+        }
+        page[511] = crun as u8;
+        word.extend(page);
+
+        // Table stream: piece table, CHPX bin table, authors, extras
+        let mut parts: Vec<(usize, Vec<u8>)> = self.table_parts.clone();
+        let total =
+            ccp(&self.main) + ccp(&self.footnotes) + ccp(&self.headers) + ccp(&self.annotations);
+        let mut fc = TEXT_START as u32;
+        if self.compressed {
+            fc = (fc * 2) | 0x4000_0000;
+        }
+        let mut clx = vec![0x02];
+        clx.extend(16u32.to_le_bytes());
+        clx.extend(0u32.to_le_bytes());
+        clx.extend(total.to_le_bytes());
+        clx.extend(0u16.to_le_bytes());
+        clx.extend(fc.to_le_bytes());
+        clx.extend(0u16.to_le_bytes());
+        parts.push((33, clx));
+        let mut bte = Vec::new();
+        bte.extend((TEXT_START as u32).to_le_bytes());
+        bte.extend(text_end.to_le_bytes());
+        bte.extend(pn.to_le_bytes());
+        parts.push((12, bte));
+        if !self.authors.is_empty() {
+            parts.push((51, doc_sttb(&self.authors, 0)));
+        }
+
+        let mut table = Vec::new();
+        // FibRgFcLcb97 has 93 pairs; later indices need a longer FibRgFcLcb
+        let pairs = parts.iter().map(|(index, _)| index + 1).fold(93, usize::max);
+        let mut fc_lcb = vec![(0u32, 0u32); pairs];
+        for (index, data) in parts {
+            fc_lcb[index] = (table.len() as u32, data.len() as u32);
+            table.extend(data);
+        }
+
+        // FIB
+        let put16 =
+            |w: &mut Vec<u8>, at: usize, v: u16| w[at..at + 2].copy_from_slice(&v.to_le_bytes());
+        let put32 =
+            |w: &mut Vec<u8>, at: usize, v: u32| w[at..at + 4].copy_from_slice(&v.to_le_bytes());
+        put16(&mut word, 0, 0xA5EC);
+        put16(&mut word, 2, self.n_fib);
+        let mut flags = 0x0200; // fWhichTblStm: 1Table
+        if self.encrypted {
+            flags |= 0x0100;
+        }
+        put16(&mut word, 0x0A, flags);
+        put16(&mut word, 32, 14);
+        put16(&mut word, 62, 22);
+        put32(&mut word, 76, ccp(&self.main));
+        put32(&mut word, 80, ccp(&self.footnotes));
+        put32(&mut word, 84, ccp(&self.headers));
+        put32(&mut word, 92, ccp(&self.annotations));
+        put16(&mut word, 152, fc_lcb.len() as u16);
+        for (i, (fc, lcb)) in fc_lcb.iter().enumerate() {
+            put32(&mut word, 154 + i * 8, *fc);
+            put32(&mut word, 158 + i * 8, *lcb);
+        }
+        for &(offset, value) in &self.fib_longs {
+            put32(&mut word, offset, value);
+        }
+
+        let mut streams = vec![("WordDocument", word), ("1Table", table)];
+        let summary = self.summary.clone().or_else(|| {
+            self.title.map(|title| {
+                let mut value = title.as_bytes().to_vec();
+                value.push(0);
+                summary_information(30, value.len() as u32, &value)
+            })
+        });
+        if let Some(summary) = summary {
+            streams.push(("\u{5}SummaryInformation", summary));
+        }
+        create_cfb_bytes(&streams)
+    }
+}
+
+/// CHPX sprms marking an insertion by author `ibst` at packed date `dttm`
+fn doc_insertion(ibst: u16, dttm: u32) -> Vec<u8> {
+    let mut sprms = vec![0x01, 0x08, 0x01];
+    sprms.extend([0x04, 0x48]);
+    sprms.extend(ibst.to_le_bytes());
+    sprms.extend([0x05, 0x68]);
+    sprms.extend(dttm.to_le_bytes());
+    sprms
+}
+
+/// CHPX sprms marking a deletion, with Word 97's shared author sprm
+fn doc_deletion(ibst: u16, dttm: u32) -> Vec<u8> {
+    let mut sprms = vec![0x00, 0x08, 0x01];
+    sprms.extend([0x04, 0x48]);
+    sprms.extend(ibst.to_le_bytes());
+    sprms.extend([0x05, 0x68]);
+    sprms.extend(dttm.to_le_bytes());
+    sprms
+}
+
+/// Pack a date and time the way Word's DTTM does
+fn dttm(year: u32, month: u32, day: u32, hour: u32, minute: u32) -> u32 {
+    minute | (hour << 6) | (day << 11) | (month << 16) | ((year - 1900) << 20)
+}
+
+/// An extended (UTF-16) STTB with `cb_extra` zero bytes after each string
+fn doc_sttb(strings: &[&str], cb_extra: u16) -> Vec<u8> {
+    doc_sttb_with_extra(
+        &strings
+            .iter()
+            .map(|s| (*s, vec![0u8; cb_extra as usize]))
+            .collect::<Vec<_>>(),
+        cb_extra,
+    )
+}
+
+fn doc_sttb_with_extra(entries: &[(&str, Vec<u8>)], cb_extra: u16) -> Vec<u8> {
+    let mut data = vec![0xFF, 0xFF];
+    data.extend((entries.len() as u16).to_le_bytes());
+    data.extend(cb_extra.to_le_bytes());
+    for (s, extra) in entries {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        data.extend((units.len() as u16).to_le_bytes());
+        for u in units {
+            data.extend(u.to_le_bytes());
+        }
+        data.extend(extra);
+    }
+    data
+}
+
+/// A PLC: the CPs, then one data element per interval
+fn doc_plc(cps: &[u32], elements: &[Vec<u8>]) -> Vec<u8> {
+    let mut data: Vec<u8> = cps.iter().flat_map(|cp| cp.to_le_bytes()).collect();
+    for e in elements {
+        data.extend(e);
+    }
+    data
+}
+
+/// A SummaryInformation property set holding only the title
+fn summary_information(vt: u32, len: u32, value: &[u8]) -> Vec<u8> {
+    let mut data = vec![0u8; 48];
+    data[0..2].copy_from_slice(&0xFFFEu16.to_le_bytes());
+    data[24..28].copy_from_slice(&1u32.to_le_bytes());
+    data[44..48].copy_from_slice(&48u32.to_le_bytes());
+    let mut section = Vec::new();
+    section.extend(0u32.to_le_bytes()); // size, unused by the reader
+    section.extend(1u32.to_le_bytes());
+    section.extend(2u32.to_le_bytes()); // PIDSI_TITLE
+    section.extend(16u32.to_le_bytes());
+    section.extend(vt.to_le_bytes());
+    section.extend(len.to_le_bytes());
+    section.extend(value);
+    data.extend(section);
+    data
+}
+
+#[test]
+fn doc_reads_paragraphs_and_revision_marks() {
+    let date = dttm(2003, 5, 14, 9, 30);
+    let fixture = DocFixture {
+        main: vec![
+            ("First paragraph.\r", vec![]),
+            ("The parties ", vec![]),
+            ("shall", doc_deletion(1, date)),
+            ("must", doc_insertion(2, date)),
+            (" comply.\r", vec![]),
+        ],
+        authors: vec!["Unknown", "Dana", "Ivan"],
+        title: Some("Old draft"),
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "old.doc").expect("Should parse .doc");
+    assert_eq!(result.document_title.as_deref(), Some("Old draft"));
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].base_text, "First paragraph.");
+
+    let block = &result.paragraphs[1];
+    assert_eq!(block.base_text, "The parties shall comply.");
+    assert_eq!(block.revised_text, "The parties must comply.");
+    let authors: Vec<&str> = block
+        .track_changes
+        .iter()
+        .map(|c| c.author.as_str())
+        .collect();
+    assert_eq!(authors, vec!["Dana", "Ivan"]);
+    assert!(matches!(
+        block.track_changes[0].change_type,
+        ChangeType::Deletion
+    ));
+    assert!(matches!(
+        block.track_changes[1].change_type,
+        ChangeType::Insertion
+    ));
+    assert_eq!(
+        block.track_changes[0].date.as_deref(),
+        Some("2003-05-14T09:30:00Z")
+    );
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+}
+
+#[test]
+fn doc_text_inserted_then_deleted_is_a_nested_revision() {
+    let date = dttm(2003, 5, 14, 9, 30);
+    let mut sprms = doc_insertion(1, date);
+    sprms.extend([0x00, 0x08, 0x01]);
+    sprms.extend([0x63, 0x48]);
+    sprms.extend(2u16.to_le_bytes());
+    let fixture = DocFixture {
+        main: vec![
+            ("The parties ", vec![]),
+            ("promptly ", sprms),
+            ("comply.\r", vec![]),
+        ],
+        authors: vec!["Unknown", "Dana", "Ivan"],
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "old.doc").expect("Should parse .doc");
+    let block = &result.paragraphs[0];
+    assert_eq!(block.base_text, "The parties comply.");
+    assert_eq!(block.revised_text, "The parties comply.");
+    let changes: Vec<(&str, &ChangeType)> = block
+        .track_changes
+        .iter()
+        .map(|c| (c.author.as_str(), &c.change_type))
+        .collect();
+    assert!(
+        matches!(
+            changes[..],
+            [("Dana", ChangeType::Insertion), ("Ivan", ChangeType::Deletion)]
+        ),
+        "{:?}",
+        changes
+    );
+}
+
+#[test]
+fn doc_reads_compressed_text_and_keeps_field_results() {
+    let fixture = DocFixture {
+        main: vec![(
+            "Caf\u{e9} \u{13} PAGE \u{14}7\u{15} \u{93}quoted\u{94}\r",
+            vec![],
+        )],
+        compressed: true,
+        ..Default::default()
+    };
+    // Compressed pieces hold Windows-1252 bytes: 0x93/0x94 are curly quotes
+    let parsed = doc::read(&fixture.bytes()).unwrap();
+    assert_eq!(
+        track_changes::base_text(&parsed.paragraphs[0].segments),
+        "Caf\u{e9} 7 \u{201c}quoted\u{201d}"
+    );
+}
+
+#[test]
+fn doc_annotations_become_comments_with_anchor_text() {
+    // Main text: "Check this clause.\r" with the reference mark after "clause"
+    let main = "Check this clause\u{5}.\r";
+    let mut atrd = vec![0u8; 30];
+    atrd[0..2].copy_from_slice(&2u16.to_le_bytes());
+    atrd[2..4].copy_from_slice(&u16::from(b'R').to_le_bytes());
+    atrd[4..6].copy_from_slice(&u16::from(b'K').to_le_bytes());
+    atrd[20..22].copy_from_slice(&1u16.to_le_bytes()); // second owner
+    atrd[26..30].copy_from_slice(&7u32.to_le_bytes()); // bookmark tag
+    let mut atnbe = vec![0u8; 10];
+    atnbe[2..6].copy_from_slice(&7u32.to_le_bytes());
+    let owners: Vec<u8> = ["Someone", "Rosa"]
+        .iter()
+        .flat_map(|name| {
+            let units: Vec<u16> = name.encode_utf16().collect();
+            let mut xst = (units.len() as u16).to_le_bytes().to_vec();
+            xst.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+            xst
+        })
+        .collect();
+    let annotation = "\u{5}Too broad?\r";
+
+    let table_parts = vec![
+        (4, doc_plc(&[17, 19], &[atrd])),
+        (
+            5,
+            doc_plc(
+                &[0, annotation.len() as u32, annotation.len() as u32 + 1],
+                &[],
+            ),
+        ),
+        (36, owners),
+        (37, doc_sttb_with_extra(&[("", atnbe)], 10)),
+        (42, doc_plc(&[6, 19], &[vec![0, 0, 0, 0]])),
+        (43, doc_plc(&[17, 19], &[])),
+    ];
+    let fixture = DocFixture {
+        main: vec![(main, vec![])],
+        annotations: vec![(annotation, vec![])],
+        table_parts: table_parts.clone(),
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "notes.doc").unwrap();
+    assert_eq!(result.paragraphs[0].base_text, "Check this clause.");
+    let comment = &result.paragraphs[0].comments[0];
+    assert_eq!(comment.author, "Rosa");
+    assert_eq!(comment.initials.as_deref(), Some("RK"));
+    assert_eq!(comment.text, "Too broad?");
+    assert_eq!(comment.anchor_text, "this clause");
+    // Without an AtrdExtra there is no date to read
+    assert!(comment.date.is_none());
+    assert!(
+        result.warnings.iter().any(|w| w.contains("no date")),
+        "{:?}",
+        result.warnings
+    );
+
+    // Word 2002 and later store the date in an ATRDPost10 per annotation
+    let mut atrd_extra = vec![0u8; 18];
+    let dttm: u32 = 30 | (14 << 6) | (9 << 11) | (4 << 16) | (124 << 20);
+    atrd_extra[0..4].copy_from_slice(&dttm.to_le_bytes());
+    let fixture = DocFixture {
+        main: vec![(main, vec![])],
+        annotations: vec![(annotation, vec![])],
+        table_parts: [table_parts, vec![(127, atrd_extra)]].concat(),
+        ..Default::default()
+    };
+    let result = doc::parse(&fixture.bytes(), "notes.doc").unwrap();
+    let comment = &result.paragraphs[0].comments[0];
+    assert_eq!(comment.date.as_deref(), Some("2024-04-09T14:30:00Z"));
+    assert!(
+        !result.warnings.iter().any(|w| w.contains("no date")),
+        "{:?}",
+        result.warnings
+    );
+}
+
+#[test]
+fn doc_footnotes_follow_the_body_anchored_to_their_reference() {
+    let footnote = "\u{2} See the 1998 Act.\r";
+    let fixture = DocFixture {
+        main: vec![("Intro.\r", vec![]), ("As held\u{2}.\r", vec![])],
+        footnotes: vec![(footnote, vec![])],
+        table_parts: vec![
+            (2, doc_plc(&[14, 15], &[vec![1, 0]])),
+            (
+                3,
+                doc_plc(&[0, footnote.len() as u32, footnote.len() as u32 + 1], &[]),
+            ),
+        ],
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "fn.doc").unwrap();
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| p.base_text.as_str())
+        .collect();
+    assert_eq!(texts, vec!["Intro.", "As held.", " See the 1998 Act."]);
+    assert_eq!(result.paragraphs[2].kind, BlockKind::Footnote);
+    assert_eq!(result.paragraphs[2].note_id.as_deref(), Some("1"));
+    assert_eq!(result.paragraphs[2].anchor_index, Some(1));
+}
+
+#[test]
+fn doc_reports_what_it_cannot_recover() {
+    let fixture = DocFixture {
+        main: vec![
+            ("Cell one\u{7}Cell two\u{7}\u{7}", vec![]),
+            ("After\r", vec![]),
+        ],
+        headers: vec![("Header text\r", vec![])],
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "table.doc").unwrap();
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| p.base_text.as_str())
+        .collect();
+    assert_eq!(texts, vec!["Cell one", "Cell two", "After"]);
+    assert!(
+        result.warnings.iter().any(|w| w.contains("Tables")),
+        "{:?}",
+        result.warnings
+    );
+    assert!(
+        result.warnings.iter().any(|w| w.contains("Headers")),
+        "{:?}",
+        result.warnings
+    );
+}
+
+#[test]
+fn doc_damaged_lengths_do_not_overflow_or_overrun() {
+    // A UTF-16 title claiming about four billion characters, and a header
+    // count that pushes the later subdocuments past the end of the CP space
+    let title: Vec<u8> = "Draft".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let fixture = DocFixture {
+        main: vec![("Body text.\r", vec![])],
+        summary: Some(summary_information(31, u32::MAX, &title)),
+        fib_longs: vec![(84, u32::MAX - 4)],
+        ..Default::default()
+    };
+
+    let result = doc::parse(&fixture.bytes(), "damaged.doc").expect("Should parse .doc");
+    assert_eq!(result.document_title.as_deref(), Some("Draft"));
+    assert_eq!(result.paragraphs[0].base_text, "Body text.");
+}
+
+#[test]
+fn doc_rejects_what_it_cannot_read() {
+    let word95 = DocFixture {
+        main: vec![("Text\r", vec![])],
+        n_fib: 104,
+        ..Default::default()
+    };
+    let err = doc::parse(&word95.bytes(), "95.doc").unwrap_err();
+    assert!(err.contains("Word 6.0/95"), "got '{}'", err);
+
+    let encrypted = DocFixture {
+        main: vec![("Text\r", vec![])],
+        encrypted: true,
+        ..Default::default()
+    };
+    let err = doc::parse(&encrypted.bytes(), "locked.doc").unwrap_err();
+    assert!(err.contains("password"), "got '{}'", err);
+
+    let not_word = create_cfb_bytes(&[("Workbook", vec![0u8; 100])]);
+    let err = doc::parse(&not_word, "book.xls").unwrap_err();
+    assert!(err.contains("WordDocument"), "got '{}'", err);
+}
+
+#[test]
+fn doc_is_detected_by_format_dispatch_and_merges_with_docx() {
+    let docx = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(r#"<w:p><w:r><w:t>Shared sentence here.</w:t></w:r></w:p>"#),
+    )]);
+    let fixture = DocFixture {
+        main: vec![
+            ("Shared sentence here.", vec![]),
+            (" Added.", doc_insertion(0, 0)),
+            ("\r", vec![]),
+        ],
+        authors: vec!["Pat"],
+        headers: vec![("Head\r", vec![])],
+        ..Default::default()
+    };
+    let doc_data = fixture.bytes();
+
+    let via_dispatch: CollateResult =
+        serde_json::from_str(&crate::parse_docx(&doc_data, "pat.doc")).unwrap();
+    assert_eq!(
+        via_dispatch.paragraphs[0].revised_text,
+        "Shared sentence here. Added."
+    );
+
+    let merged = merge::collate(
+        &[("base.docx", &docx[..]), ("pat.doc", &doc_data[..])],
+        "base.docx",
+    )
+    .expect("Should merge");
+    assert_eq!(merged.paragraphs[0].track_changes[0].author, "Pat");
+    assert_eq!(
+        merged.warnings,
+        vec!["pat.doc: Headers and footers in .doc files are not read"]
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 19. RTF
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Wrap body content in the header groups Word writes, with a revision table
+fn rtf_document(authors: &[&str], body: &str) -> String {
+    let revtbl: String = authors.iter().map(|a| format!("{{{};}}", a)).collect();
+    format!(
+        r"{{\rtf1\ansi\ansicpg1252\deff0{{\fonttbl{{\f0\froman Times New Roman;}}}}
+{{\colortbl;\red0\green0\blue0;}}
+{{\stylesheet{{\ql \f0\fs24 Normal;}}}}
+{{\*\revtbl {revtbl}}}
+{{\info{{\title Settlement draft}}{{\author Clerk}}}}
+\viewkind4\uc1 {body}}}"
+    )
+}
+
+#[test]
+fn rtf_maps_revision_marks_onto_segments() {
+    // DTTM for 2021-06-15 14:05
+    let date = dttm(2021, 6, 15, 14, 5);
+    let body = format!(
+        r"\pard\plain First paragraph.\par
+\pard\plain The buyer {{\deleted\revauthdel1\revdttmdel{date} shall}}{{\revised\revauth2\revdttm{date} must}} pay.\par"
+    );
+    let rtf = rtf_document(&["Unknown", "Dana", "Ivan"], &body);
+
+    let result = rtf::parse(rtf.as_bytes(), "draft.rtf").expect("Should parse RTF");
+    assert_eq!(result.document_title.as_deref(), Some("Settlement draft"));
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].base_text, "First paragraph.");
+
+    let block = &result.paragraphs[1];
+    assert_eq!(block.base_text, "The buyer shall pay.");
+    assert_eq!(block.revised_text, "The buyer must pay.");
+    let authors: Vec<&str> = block
+        .track_changes
+        .iter()
+        .map(|c| c.author.as_str())
+        .collect();
+    assert_eq!(authors, vec!["Dana", "Ivan"]);
+    assert_eq!(
+        block.track_changes[1].date.as_deref(),
+        Some("2021-06-15T14:05:00Z")
+    );
+}
+
+#[test]
+fn rtf_text_inserted_then_deleted_is_a_nested_revision() {
+    let date = dttm(2021, 6, 15, 14, 5);
+    let body = format!(
+        r"\pard\plain The buyer {{\revised\revauth1\revdttm{date}\deleted\revauthdel2\revdttmdel{date} promptly }}pays.\par"
+    );
+    let rtf = rtf_document(&["Unknown", "Dana", "Ivan"], &body);
+
+    let result = rtf::parse(rtf.as_bytes(), "draft.rtf").expect("Should parse RTF");
+    let block = &result.paragraphs[0];
+    assert_eq!(block.base_text, "The buyer pays.");
+    assert_eq!(block.revised_text, "The buyer pays.");
+    let changes: Vec<(&str, &ChangeType)> = block
+        .track_changes
+        .iter()
+        .map(|c| (c.author.as_str(), &c.change_type))
+        .collect();
+    assert!(
+        matches!(
+            changes[..],
+            [("Dana", ChangeType::Insertion), ("Ivan", ChangeType::Deletion)]
+        ),
+        "{:?}",
+        changes
+    );
+    assert_ne!(block.track_changes[0].id, block.track_changes[1].id);
+}
+
+#[test]
+fn rtf_annotations_become_comments_with_anchor_text() {
+    let body = r"\pard\plain Pay within {\*\atrfstart 4}thirty days{\*\atrfend 4}.{\*\atnid RK}{\*\atnauthor Rosa}\chatn {\*\annotation{\*\atnref 4}{\*\atndate 1234567}\pard\plain {\field{\*\fldinst PAGE}}\chatn Is this
+ enough?\par}\par
+\pard\plain Next.\par";
+    let rtf = rtf_document(&["Unknown"], body);
+
+    let result = rtf::parse(rtf.as_bytes(), "comments.rtf").unwrap();
+    assert_eq!(result.paragraphs.len(), 2);
+    assert_eq!(result.paragraphs[0].base_text, "Pay within thirty days.");
+    let comment = &result.paragraphs[0].comments[0];
+    assert_eq!(comment.author, "Rosa");
+    assert_eq!(comment.initials.as_deref(), Some("RK"));
+    assert_eq!(comment.text, "Is this enough?");
+    assert_eq!(comment.anchor_text, "thirty days");
+    assert!(result.paragraphs[1].comments.is_empty());
+}
+
+#[test]
+fn rtf_escapes_unicode_and_fields() {
+    let body = r"\pard\plain Caf\'e9 \u8220\'93quoted\u8221\'94 {\field{\*\fldinst { PAGE }}{\fldrslt 7}}\~\{x\}\tab end\par";
+    let parsed = rtf::read(rtf_document(&[], body).as_bytes()).unwrap();
+    assert_eq!(
+        track_changes::base_text(&parsed.paragraphs[0].segments),
+        "Caf\u{e9} \u{201c}quoted\u{201d} 7\u{a0}{x}\tend"
+    );
+}
+
+#[test]
+fn rtf_notes_headers_and_tables() {
+    let body = r"{\header \pard\plain Privileged\par}{\footerf \pard\plain Page one\par}
+\pard\plain Held{\super\chftn}{\footnote \pard\plain {\super\chftn} See the 1998 Act.\par} here.\par
+\trowd\trhdr\cellx1000\cellx2000\pard\plain\intbl Name\cell Score\cell\row
+\trowd\cellx1000\cellx2000\pard\plain\intbl Ada\cell 10\cell\row
+\pard\plain After\par";
+    let result = rtf::parse(rtf_document(&[], body).as_bytes(), "notes.rtf").unwrap();
+    let texts: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .map(|p| p.base_text.as_str())
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Held here.",
+            "Name",
+            "Score",
+            "Ada",
+            "10",
+            "After",
+            "Privileged",
+            "Page one",
+            " See the 1998 Act."
+        ]
+    );
+
+    let score = result.paragraphs[4]
+        .table
+        .as_ref()
+        .expect("cell coordinates");
+    assert_eq!((score.table_index, score.row, score.column), (0, 1, 1));
+    assert_eq!(score.column_header.as_deref(), Some("Score"));
+    assert!(result.paragraphs[1].table.as_ref().unwrap().is_header);
+    assert!(result.paragraphs[5].table.is_none());
+
+    assert_eq!(result.paragraphs[6].kind, BlockKind::Header);
+    assert_eq!(result.paragraphs[7].kind, BlockKind::Footer);
+    assert_eq!(
+        result.paragraphs[7].header_footer_type,
+        Some(HeaderFooterType::First)
+    );
+
+    let note = &result.paragraphs[8];
+    assert_eq!(note.kind, BlockKind::Footnote);
+    assert_eq!(note.note_id.as_deref(), Some("1"));
+    assert_eq!(note.anchor_index, Some(0));
+}
+
+#[test]
+fn rtf_is_detected_by_format_dispatch_and_reports_skipped_content() {
+    let body = r"\pard\plain Shared sentence here.{\revised\revauth1 \u8212\'97Added.}\par
+{\shp{\*\shpinst{\sp{\sn shapeType}{\sv 202}}{\shptxt Box text\par}}}";
+    let rtf = rtf_document(&["Unknown", "Pat"], body);
+
+    let via_dispatch: CollateResult =
+        serde_json::from_str(&crate::parse_docx(rtf.as_bytes(), "pat.rtf")).unwrap();
+    assert!(via_dispatch.error.is_none());
+    assert_eq!(
+        via_dispatch.paragraphs[0].revised_text,
+        "Shared sentence here.\u{2014}Added."
+    );
+    assert_eq!(via_dispatch.paragraphs.len(), 1);
+    assert_eq!(
+        via_dispatch.warnings,
+        vec!["Shapes and text boxes in RTF files are not read"]
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 20. PDF markups
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A one-page PDF showing `lines` in 10pt Helvetica at 5pt per character,
+/// from (72, 700) down in 14pt steps, with the given annotation dictionaries
+/// as objects 7 onwards. The content stream is Flate-compressed when asked.
+fn pdf_bytes(
+    lines: &[&str],
+    annotations: &[String],
+    compress: bool,
+    trailer_extra: &str,
+) -> Vec<u8> {
+    let mut content = String::from("BT /F1 10 Tf 14 TL 72 700 Td ");
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            content.push_str("T* ");
+        }
+        content.push_str(&format!("({}) Tj ", line));
+    }
+    content.push_str("ET");
+    let (filter, stream) = if compress {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        ("/Filter /FlateDecode ", encoder.finish().unwrap())
+    } else {
+        ("", content.into_bytes())
+    };
+
+    let annots: String = (0..annotations.len())
+        .map(|i| format!("{} 0 R ", 7 + i))
+        .collect();
+    let widths = vec!["500"; 95].join(" ");
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R /Annots [{}] >>",
+            annots
+        )
+        .into_bytes(),
+        format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding /FirstChar 32 /LastChar 126 /Widths [{}] >>",
+            widths
+        )
+        .into_bytes(),
+        [
+            format!("<< /Length {} {}>>\nstream\n", stream.len(), filter).into_bytes(),
+            stream,
+            b"\nendstream".to_vec(),
+        ]
+        .concat(),
+        b"<< /Title (Markup print) >>".to_vec(),
+    ];
+    objects.extend(annotations.iter().map(|a| a.clone().into_bytes()));
+
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    for (i, object) in objects.iter().enumerate() {
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R {}>>\n%%EOF\n",
+            objects.len() + 1,
+            trailer_extra
+        )
+        .into_bytes(),
+    );
+    pdf
+}
+
+/// Quad points covering characters `from..to` of a line laid out by `pdf_bytes`
+fn pdf_quad(line: usize, from: usize, to: usize) -> String {
+    let (x0, x1) = (72 + 5 * from, 72 + 5 * to);
+    let (y0, y1) = (697 - 14 * line, 709 - 14 * line);
+    format!("{x0} {y1} {x1} {y1} {x0} {y0} {x1} {y0}")
+}
+
+/// A markup annotation dictionary; `extra` holds its geometry and links
+fn pdf_annotation(subtype: &str, author: &str, contents: &str, extra: &str) -> String {
+    // Contents as UTF-16BE with a byte order mark
+    let hex: String = contents
+        .encode_utf16()
+        .map(|u| format!("{:04X}", u))
+        .collect();
+    format!(
+        "<< /Type /Annot /Subtype /{subtype} /T ({author}) /Contents <FEFF{hex}> /M (D:20240315093000+01'00') {extra} >>"
+    )
+}
+
+/// Base document for the PDF tests, as a .docx
+fn pdf_base_copy() -> Vec<u8> {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>The claimant received the goods on 1 March.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Payment was due within 14 days of delivery.</w:t></w:r></w:p>"#,
+    );
+    create_docx_bytes(&[("word/document.xml", &doc_xml)])
+}
+
+const PDF_LINES: [&str; 3] = [
+    "The claimant received the goods on 1 March.",
+    "Payment was due within 14 days",
+    "of delivery.",
+];
+
+#[test]
+fn pdf_reads_markups_and_the_text_under_them() {
+    let annotations = vec![
+        pdf_annotation(
+            "Highlight",
+            "Priya",
+            "Which goods\u{2019}?",
+            &format!("/QuadPoints [{}]", pdf_quad(0, 26, 31)),
+        ),
+        // Underline running over the line break
+        pdf_annotation(
+            "Underline",
+            "Priya",
+            "Business days?",
+            &format!(
+                "/QuadPoints [{} {}]",
+                pdf_quad(1, 26, 30),
+                pdf_quad(2, 0, 11)
+            ),
+        ),
+        pdf_annotation("Text", "Owen", "Check the date", "/Rect [280 700 284 710]"),
+    ];
+
+    for compress in [false, true] {
+        let pdf = pdf_bytes(&PDF_LINES, &annotations, compress, "");
+        let read = pdf::read_markups(&pdf).expect("Should read the PDF");
+        assert_eq!(read.title.as_deref(), Some("Markup print"));
+        assert_eq!(read.markups.len(), 3);
+
+        let highlight = &read.markups[0];
+        assert_eq!(highlight.kind, pdf::MarkupKind::Highlight);
+        assert_eq!(highlight.id, "pdf-7");
+        assert_eq!(highlight.author, "Priya");
+        assert_eq!(highlight.contents, "Which goods\u{2019}?");
+        assert_eq!(highlight.date.as_deref(), Some("2024-03-15T09:30:00+01:00"));
+        assert_eq!(highlight.marked_text, "goods");
+        assert_eq!(highlight.context_before, "The claimant received the ");
+        assert!(highlight.context_after.starts_with(" on 1 March. Payment"));
+
+        assert_eq!(read.markups[1].marked_text, "days of delivery");
+
+        // A sticky note marks the point nearest its icon: after "1 March"
+        let note = &read.markups[2];
+        assert_eq!(note.kind, pdf::MarkupKind::Note);
+        assert_eq!(note.marked_text, "");
+        assert!(note.context_before.ends_with("goods on 1 March"));
+        assert!(note.context_after.starts_with(". Payment"));
+    }
+}
+
+#[test]
+fn pdf_markups_merge_into_base_paragraphs() {
+    let annotations = vec![
+        pdf_annotation(
+            "Highlight",
+            "Priya",
+            "Which goods?",
+            &format!("/QuadPoints [{}]", pdf_quad(0, 26, 31)),
+        ),
+        // A caret grouped with a strike-out replaces the struck text
+        pdf_annotation("Caret", "Priya", "30", "/Rect [190 686 200 698]"),
+        pdf_annotation(
+            "StrikeOut",
+            "Priya",
+            "",
+            &format!(
+                "/QuadPoints [{}] /IRT 8 0 R /RT /Group",
+                pdf_quad(1, 23, 25)
+            ),
+        ),
+        // Insertion at the end of the wrapped sentence, before the full stop
+        pdf_annotation("Caret", "Owen", " in full", "/Rect [125 672 129 684]"),
+    ];
+    let pdf = pdf_bytes(&PDF_LINES, &annotations, true, "");
+    let base = pdf_base_copy();
+
+    let result = merge::collate(&[("base.docx", &base), ("markup.pdf", &pdf)], "base.docx")
+        .expect("Should collate the PDF markups");
+    assert_eq!(result.paragraphs.len(), 2);
+
+    let first = &result.paragraphs[0];
+    assert_eq!(first.comments.len(), 1);
+    assert_eq!(first.comments[0].text, "Which goods?");
+    assert_eq!(first.comments[0].anchor_text, "goods");
+    assert_eq!(first.comments[0].author, "Priya");
+    assert_eq!(first.comments[0].source_file.as_deref(), Some("markup.pdf"));
+
+    let second = &result.paragraphs[1];
+    assert_eq!(
+        second.base_text,
+        "Payment was due within 14 days of delivery."
+    );
+    let changes: Vec<(&str, &str, &str)> = second
+        .track_changes
+        .iter()
+        .map(|c| {
+            (
+                c.author.as_str(),
+                c.original_text.as_str(),
+                c.new_text.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("Priya", "14", ""),
+            ("Priya", "", "30"),
+            ("Owen", "", " in full")
+        ]
+    );
+    assert!(second
+        .track_changes
+        .iter()
+        .all(|c| c.source_file.as_deref() == Some("markup.pdf")));
+    let versions: Vec<(&str, &str)> = second
+        .reviewer_versions
+        .iter()
+        .map(|v| (v.reviewer_name.as_str(), v.resulting_text.as_str()))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            ("Priya", "Payment was due within 30 days of delivery."),
+            (
+                "Owen",
+                "Payment was due within 14 days of delivery in full."
+            )
+        ]
+    );
+
+    let priya = result.reviewers.iter().find(|r| r.name == "Priya").unwrap();
+    assert_eq!(priya.file_name, "markup.pdf");
+    assert_eq!((priya.comment_count, priya.change_count), (1, 2));
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn pdf_markups_anchor_by_context_and_approximate_text() {
+    let base_xml = minimal_document_xml(
+        r#"<w:p><w:r><w:t>The price is fixed. The price is final.</w:t></w:r></w:p>
+        <w:p><w:r><w:t>The seller must deliver.</w:t></w:r></w:p>"#,
+    );
+    let base = create_docx_bytes(&[("word/document.xml", &base_xml)]);
+    let lines = [
+        "The price is fixed. The price is final.",
+        "The sel-",
+        "ler must deliver.",
+        "See Annex B.",
+    ];
+    let annotations = vec![
+        // The second of two identical words
+        pdf_annotation(
+            "StrikeOut",
+            "Owen",
+            "",
+            &format!("/QuadPoints [{}]", pdf_quad(0, 24, 29)),
+        ),
+        // Hyphenated across a line break in the print
+        pdf_annotation(
+            "Highlight",
+            "Owen",
+            "Which seller?",
+            &format!("/QuadPoints [{} {}]", pdf_quad(1, 4, 8), pdf_quad(2, 0, 8)),
+        ),
+        // Text that is not in the base document
+        pdf_annotation(
+            "Highlight",
+            "Owen",
+            "Attach it",
+            &format!("/QuadPoints [{}]", pdf_quad(3, 0, 11)),
+        ),
+    ];
+    let pdf = pdf_bytes(&lines, &annotations, false, "");
+
+    let result = merge::collate(&[("base.docx", &base), ("owen.pdf", &pdf)], "base.docx")
+        .expect("Should collate the PDF markups");
+    assert_eq!(result.paragraphs.len(), 2);
+
+    let change = &result.paragraphs[0].track_changes[0];
+    assert!(matches!(change.change_type, ChangeType::Deletion));
+    assert_eq!(change.original_text, "price");
+    assert_eq!(change.context_before, "The price is fixed. The ");
+    assert_eq!(
+        result.paragraphs[0].reviewer_versions[0].resulting_text,
+        "The price is fixed. The  is final."
+    );
+
+    let comment = &result.paragraphs[1].comments[0];
+    assert_eq!(comment.text, "Which seller?");
+    assert_eq!(comment.anchor_text, "seller must");
+
+    assert_eq!(
+        result.warnings,
+        vec![
+            r#"owen.pdf: Page 1: highlight by Owen not found in the base document ("See Annex B")"#
+        ]
+    );
+}
+
+#[test]
+fn pdf_encrypted_file_is_an_error_and_dispatch_reads_markups() {
+    let annotations = vec![pdf_annotation(
+        "StrikeOut",
+        "Priya",
+        "",
+        &format!("/QuadPoints [{}]", pdf_quad(1, 23, 25)),
+    )];
+    let encrypted = pdf_bytes(&PDF_LINES, &annotations, false, "/Encrypt 9 0 R ");
+    let err = match pdf::read_markups(&encrypted) {
+        Err(e) => e,
+        Ok(_) => panic!("Encrypted PDF should be rejected"),
+    };
+    assert!(err.contains("encrypted"));
+
+    // Without a base document, each markup is a paragraph of the text it covers
+    let pdf = pdf_bytes(&PDF_LINES, &annotations, false, "");
+    let result: CollateResult =
+        serde_json::from_str(&crate::parse_docx(&pdf, "markup.pdf")).unwrap();
+    assert!(result.error.is_none());
+    assert_eq!(result.document_title.as_deref(), Some("Markup print"));
+    assert_eq!(result.paragraphs.len(), 1);
+    assert_eq!(result.paragraphs[0].base_text, "14");
+    assert_eq!(result.paragraphs[0].revised_text, "");
+    assert_eq!(result.paragraphs[0].track_changes[0].author, "Priya");
+}
+
+#[test]
+fn pdf_truncated_anywhere_is_read_without_panicking() {
+    let annotations = vec![pdf_annotation(
+        "Highlight",
+        "Priya",
+        "Which goods?",
+        &format!("/QuadPoints [{}]", pdf_quad(0, 26, 31)),
+    )];
+    for compress in [false, true] {
+        let pdf = pdf_bytes(&PDF_LINES, &annotations, compress, "");
+        for len in 0..pdf.len() {
+            // Either an error or whatever markups survive; never a panic
+            let _ = formats::parse(&pdf[..len], "cut.pdf");
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 21. Format revisions
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn format_revisions_become_format_changes_with_summaries() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:pPr>
+                <w:pStyle w:val="Heading3"/>
+                <w:pPrChange w:id="7" w:author="Mira" w:date="2024-05-02T10:00:00Z">
+                    <w:pPr><w:pStyle w:val="Heading2"/></w:pPr>
+                </w:pPrChange>
+            </w:pPr>
+            <w:r><w:t xml:space="preserve">Definitions</w:t></w:r>
+        </w:p>
+        <w:p>
+            <w:r><w:t>“</w:t></w:r>
+            <w:r>
+                <w:rPr>
+                    <w:b/><w:bCs/>
+                    <w:rPrChange w:id="8" w:author="Mira" w:date="2024-05-02T10:01:00Z"><w:rPr/></w:rPrChange>
+                </w:rPr>
+                <w:t>Purchaser</w:t>
+            </w:r>
+            <w:r><w:t>” means the buyer.</w:t></w:r>
+        </w:p>"#,
+    );
+    let styles_xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/></w:style>
+</w:styles>"#;
+    let data = create_docx_bytes(&[
+        ("word/document.xml", &doc_xml),
+        ("word/styles.xml", styles_xml),
+    ]);
+
+    let result = docx::parse(&data, "mira.docx").expect("Should parse");
+    assert_eq!(result.paragraphs.len(), 2);
+
+    let heading = &result.paragraphs[0];
+    assert_eq!(heading.track_changes.len(), 1);
+    let change = &heading.track_changes[0];
+    assert!(matches!(change.change_type, ChangeType::FormatChange));
+    assert_eq!(change.author, "Mira");
+    assert_eq!(change.original_text, "Definitions");
+    assert_eq!(change.new_text, "Definitions");
+    assert_eq!(
+        change.summary.as_deref(),
+        Some("style Heading 2 → Heading 3")
+    );
+
+    let clause = &result.paragraphs[1];
+    assert_eq!(clause.base_text, "“Purchaser” means the buyer.");
+    assert_eq!(clause.revised_text, clause.base_text);
+    assert_eq!(clause.paragraph_status, ParagraphStatus::Normal);
+    let change = &clause.track_changes[0];
+    assert!(matches!(change.change_type, ChangeType::FormatChange));
+    assert_eq!(change.id, "8");
+    assert_eq!(change.date.as_deref(), Some("2024-05-02T10:01:00Z"));
+    assert_eq!(change.original_text, "Purchaser");
+    assert_eq!(change.context_before, "“");
+    assert_eq!(change.summary.as_deref(), Some("bold added"));
+    assert_eq!(change.source_file.as_deref(), Some("mira.docx"));
+
+    let mira = result.reviewers.iter().find(|r| r.name == "Mira").unwrap();
+    assert_eq!(mira.change_count, 2);
+}
+
+#[test]
+fn underline_summaries_name_the_underline_style() {
+    let change = |before: Option<&str>, after: Option<&str>| track_changes::PropertyChange {
+        name: "u".to_string(),
+        before: before.map(str::to_string),
+        after: after.map(str::to_string),
+    };
+    assert_eq!(
+        track_changes::summarise(&[change(None, Some("single"))]),
+        "underline added"
+    );
+    assert_eq!(
+        track_changes::summarise(&[change(Some("none"), Some("double"))]),
+        "double underline added"
+    );
+    assert_eq!(
+        track_changes::summarise(&[change(Some("single"), Some("double"))]),
+        "underline → double underline"
+    );
+    assert_eq!(
+        track_changes::summarise(&[change(Some("wave"), None)]),
+        "underline removed"
+    );
+}
+
+#[test]
+fn format_revision_summaries_describe_each_property() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:pPr>
+                <w:ind w:left="1440" w:hanging="720"/>
+                <w:numPr><w:ilvl w:val="1"/><w:numId w:val="4"/></w:numPr>
+                <w:rPr><w:b/></w:rPr>
+                <w:pPrChange w:id="1" w:author="Ola">
+                    <w:pPr><w:ind w:left="720" w:hanging="720"/></w:pPr>
+                </w:pPrChange>
+            </w:pPr>
+            <w:r>
+                <w:rPr>
+                    <w:sz w:val="28"/><w:color w:val="FF0000"/>
+                    <w:rPrChange w:id="2" w:author="Ola">
+                        <w:rPr><w:u w:val="single"/><w:sz w:val="24"/></w:rPr>
+                    </w:rPrChange>
+                </w:rPr>
+                <w:t>Schedule of loss</w:t>
+            </w:r>
+            <w:r><w:rPr><w:i/></w:rPr><w:t>.</w:t></w:r>
+        </w:p>"#,
+    );
+    let paragraphs = paragraphs::parse_document(&doc_xml);
+    assert_eq!(paragraphs.len(), 1);
+
+    let revision = paragraphs[0]
+        .format_change
+        .as_ref()
+        .expect("Paragraph format revision");
+    assert_eq!(
+        track_changes::summarise(&revision.properties),
+        "indentation left 36pt hanging 36pt → left 72pt hanging 36pt, numbering list 4 level 2 added"
+    );
+
+    // Only the run with a revision is a format change; the italic full stop is stable
+    let changes = track_changes::extract_changes(&paragraphs[0].segments);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].original_text, "Schedule of loss");
+    assert_eq!(
+        changes[0].summary.as_deref(),
+        Some("font size 12pt → 14pt, colour #FF0000 added, underline removed")
+    );
+    assert_eq!(
+        track_changes::base_text(&paragraphs[0].segments),
+        "Schedule of loss."
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 22. Tracked moves
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn moves_pair_source_and_destination_across_paragraphs() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t>Payment terms.</w:t></w:r>
+            <w:moveFromRangeStart w:id="10" w:author="Noor" w:name="move1"/>
+            <w:moveFrom w:id="11" w:author="Noor" w:date="2024-06-01T09:00:00Z">
+                <w:r><w:t>Late fees apply.</w:t></w:r>
+            </w:moveFrom>
+            <w:moveFromRangeEnd w:id="10"/>
+        </w:p>
+        <w:p><w:r><w:t>Delivery within ten days.</w:t></w:r></w:p>
+        <w:p>
+            <w:moveToRangeStart w:id="12" w:author="Noor" w:name="move1"/>
+            <w:moveTo w:id="13" w:author="Noor" w:date="2024-06-01T09:00:00Z">
+                <w:r><w:t>Late fees</w:t></w:r>
+            </w:moveTo>
+            <w:ins w:id="14" w:author="Noor"><w:r><w:t>, at 2%,</w:t></w:r></w:ins>
+            <w:moveTo w:id="15" w:author="Noor" w:date="2024-06-01T09:00:00Z">
+                <w:r><w:t>apply.</w:t></w:r>
+            </w:moveTo>
+            <w:moveToRangeEnd w:id="12"/>
+        </w:p>
+        <w:p>
+            <w:r><w:t>Notices.</w:t></w:r>
+            <w:moveFrom w:id="16" w:author="Noor"><w:r><w:t>Unnamed.</w:t></w:r></w:moveFrom>
+        </w:p>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "noor.docx").expect("Should parse");
+    assert_eq!(result.paragraphs.len(), 4);
+
+    let source = &result.paragraphs[0];
+    assert_eq!(source.base_text, "Payment terms.Late fees apply.");
+    assert_eq!(source.revised_text, "Payment terms.");
+    assert_eq!(source.track_changes.len(), 1);
+    let from = &source.track_changes[0];
+    assert!(matches!(from.change_type, ChangeType::Move));
+    assert_eq!(from.id, "11");
+    assert_eq!(from.original_text, "Late fees apply.");
+    assert_eq!(from.new_text, "");
+    assert_eq!(from.context_before, "Payment terms.");
+    let link = from.move_link.as_ref().expect("Move link");
+    assert_eq!(link.name, "move1");
+    assert_eq!(link.end, MoveEnd::From);
+    assert_eq!(link.other_paragraph, Some(2));
+    assert_eq!(link.other_change_id.as_deref(), Some("13"));
+
+    // The edit made inside the moved text is its own change
+    let destination = &result.paragraphs[2];
+    assert_eq!(destination.base_text, "");
+    assert_eq!(destination.revised_text, "Late fees, at 2%,apply.");
+    assert_eq!(
+        destination.paragraph_status,
+        ParagraphStatus::WhollyInserted
+    );
+    let kinds: Vec<(&str, bool)> = destination
+        .track_changes
+        .iter()
+        .map(|tc| {
+            (
+                tc.new_text.as_str(),
+                matches!(tc.change_type, ChangeType::Move),
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![("Late fees", true), (", at 2%,", false), ("apply.", true)]
+    );
+    for tc in destination
+        .track_changes
+        .iter()
+        .filter(|tc| tc.move_link.is_some())
+    {
+        let link = tc.move_link.as_ref().unwrap();
+        assert_eq!(link.end, MoveEnd::To);
+        assert_eq!(link.other_paragraph, Some(0));
+        assert_eq!(link.other_change_id.as_deref(), Some("11"));
+    }
+
+    // A move outside a named range cannot be paired and stays a deletion
+    let notices = &result.paragraphs[3];
+    assert_eq!(notices.track_changes.len(), 1);
+    assert!(matches!(
+        notices.track_changes[0].change_type,
+        ChangeType::Deletion
+    ));
+    assert!(notices.track_changes[0].move_link.is_none());
+}
+
+#[test]
+fn merged_moves_link_to_merged_paragraph_indices() {
+    let base = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(
+            r#"<w:p><w:r><w:t>Preamble.</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Late fees apply.</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Delivery within ten days.</w:t></w:r></w:p>"#,
+        ),
+    )]);
+    // The reviewer's copy lacks the preamble, so its paragraph indices differ
+    let reviewer = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(
+            r#"<w:p>
+                <w:moveFromRangeStart w:id="1" w:name="move7"/>
+                <w:moveFrom w:id="2" w:author="Noor"><w:r><w:t>Late fees apply.</w:t></w:r></w:moveFrom>
+                <w:moveFromRangeEnd w:id="1"/>
+            </w:p>
+            <w:p><w:r><w:t>Delivery within ten days.</w:t></w:r></w:p>
+            <w:p>
+                <w:moveToRangeStart w:id="3" w:name="move7"/>
+                <w:moveTo w:id="4" w:author="Noor"><w:r><w:t>Late fees apply.</w:t></w:r></w:moveTo>
+                <w:moveToRangeEnd w:id="3"/>
+            </w:p>"#,
+        ),
+    )]);
+
+    let result = merge::collate(
+        &[("base.docx", &base), ("noor.docx", &reviewer)],
+        "base.docx",
+    )
+    .expect("Should collate");
+    let texts: Vec<(&str, &str)> = result
+        .paragraphs
+        .iter()
+        .map(|p| (p.base_text.as_str(), p.revised_text.as_str()))
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            ("Preamble.", "Preamble."),
+            ("Late fees apply.", "Late fees apply."),
+            ("Delivery within ten days.", "Delivery within ten days."),
+            ("", "Late fees apply."),
+        ]
+    );
+
+    let from = result.paragraphs[1].track_changes[0]
+        .move_link
+        .as_ref()
+        .expect("Source end");
+    assert_eq!(from.other_paragraph, Some(3));
+    let to = result.paragraphs[3].track_changes[0]
+        .move_link
+        .as_ref()
+        .expect("Destination end");
+    assert_eq!(to.other_paragraph, Some(1));
+    assert_eq!(to.other_change_id.as_deref(), Some("2"));
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 23. Nested revisions
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn insertion_deleted_by_another_reviewer_reports_both_events() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t>Fee:</w:t></w:r>
+            <w:ins w:id="1" w:author="Asha" w:date="2024-07-01T09:00:00Z">
+                <w:r><w:t>£500</w:t></w:r>
+            </w:ins>
+            <w:ins w:id="3" w:author="Asha" w:date="2024-07-01T09:00:00Z">
+                <w:del w:id="4" w:author="Ben" w:date="2024-07-02T15:30:00Z">
+                    <w:r><w:delText>plus VAT</w:delText></w:r>
+                </w:del>
+            </w:ins>
+            <w:r><w:t>.</w:t></w:r>
+        </w:p>
+        <w:p>
+            <w:ins w:id="5" w:author="Asha">
+                <w:del w:id="6" w:author="Ben"><w:r><w:delText>Withdrawn clause.</w:delText></w:r></w:del>
+            </w:ins>
+        </w:p>"#,
+    );
+    let paragraphs = paragraphs::parse_document(&doc_xml);
+    assert_eq!(paragraphs.len(), 2);
+
+    let segments = &paragraphs[0].segments;
+    assert!(matches!(
+        &segments[2],
+        Segment::Nested { revisions, text } if revisions.len() == 2 && text == "plus VAT"
+    ));
+    assert_eq!(track_changes::base_text(segments), "Fee:.");
+    assert_eq!(track_changes::revised_text(segments), "Fee:£500.");
+    assert_eq!(
+        track_changes::author_text(segments, "Asha"),
+        "Fee:£500plus VAT."
+    );
+    assert_eq!(track_changes::author_text(segments, "Ben"), "Fee:.");
+
+    let changes = track_changes::extract_changes(segments);
+    let events: Vec<(&str, &str, &str, &str)> = changes
+        .iter()
+        .map(|tc| {
+            (
+                tc.id.as_str(),
+                tc.author.as_str(),
+                tc.original_text.as_str(),
+                tc.new_text.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("1", "Asha", "", "£500"),
+            ("3", "Asha", "", "plus VAT"),
+            ("4", "Ben", "plus VAT", ""),
+        ]
+    );
+    assert!(matches!(changes[2].change_type, ChangeType::Deletion));
+    assert_eq!(changes[2].date.as_deref(), Some("2024-07-02T15:30:00Z"));
+    assert_eq!(changes[2].context_before, "Fee:");
+    assert_eq!(changes[2].context_after, ".");
+
+    // Ben deleting part of Asha's insertion disagrees with it
+    let edits = conflicts::edits(segments);
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0].author, "Asha");
+    assert_eq!(edits[0].new_text, "£500plus VAT");
+    assert_eq!(edits[1].author, "Ben");
+    assert_eq!(edits[1].change_ids, vec!["4"]);
+    let found = conflicts::detect(&track_changes::base_text(segments), &edits);
+    assert_eq!(found.len(), 1);
+    let authors: Vec<&str> = found[0].edits.iter().map(|e| e.author.as_str()).collect();
+    assert_eq!(authors, vec!["Asha", "Ben"]);
+
+    // A paragraph inserted by one reviewer and deleted by another is in
+    // neither version but keeps both changes
+    let result = docx::parse(
+        &create_docx_bytes(&[("word/document.xml", &doc_xml)]),
+        "ben.docx",
+    )
+    .expect("Should parse");
+    let withdrawn = &result.paragraphs[1];
+    assert_eq!(withdrawn.base_text, "");
+    assert_eq!(withdrawn.revised_text, "");
+    let authors: Vec<&str> = withdrawn
+        .track_changes
+        .iter()
+        .map(|tc| tc.author.as_str())
+        .collect();
+    assert_eq!(authors, vec!["Asha", "Ben"]);
+    assert!(matches!(
+        withdrawn.track_changes[0].change_type,
+        ChangeType::Insertion
+    ));
+    assert!(matches!(
+        withdrawn.track_changes[1].change_type,
+        ChangeType::Deletion
+    ));
+}
+
+#[test]
+fn insertion_deleted_by_its_own_author_is_no_edit() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t>Fee:</w:t></w:r>
+            <w:ins w:id="1" w:author="Asha">
+                <w:del w:id="2" w:author="Asha"><w:r><w:delText>£400</w:delText></w:r></w:del>
+            </w:ins>
+            <w:ins w:id="3" w:author="Ben"><w:r><w:t>£500</w:t></w:r></w:ins>
+            <w:r><w:t>.</w:t></w:r>
+        </w:p>"#,
+    );
+    let paragraphs = paragraphs::parse_document(&doc_xml);
+    let segments = &paragraphs[0].segments;
+    assert_eq!(track_changes::author_text(segments, "Asha"), "Fee:.");
+
+    // Asha's net change is nothing, so Ben's insertion stands alone
+    let edits = conflicts::edits(segments);
+    let authors: Vec<&str> = edits.iter().map(|e| e.author.as_str()).collect();
+    assert_eq!(authors, vec!["Ben"]);
+    assert_eq!(edits[0].new_text, "£500");
+    assert!(conflicts::detect(&track_changes::base_text(segments), &edits).is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 24. Paragraph mark revisions
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A paragraph whose mark carries a tracked `w:ins` / `w:del` by Ola
+fn marked_paragraph(mark: &str, id: &str, runs: &str) -> String {
+    format!(
+        r#"<w:p><w:pPr><w:pStyle w:val="Body"/><w:rPr><w:{mark} w:id="{id}" w:author="Ola" w:date="2024-08-05T11:00:00Z"/></w:rPr></w:pPr>{runs}</w:p>"#
+    )
+}
+
+#[test]
+fn paragraph_mark_revisions_report_splits_and_joins() {
+    let doc_xml = minimal_document_xml(&[
+        marked_paragraph("ins", "1", "<w:r><w:t>Payment is due</w:t></w:r>"),
+        "<w:p><w:r><w:t>within 28 days.</w:t></w:r></w:p>".to_string(),
+        marked_paragraph("del", "2", "<w:r><w:t>Notice must be given</w:t></w:r>"),
+        "<w:p><w:r><w:t>in writing.</w:t></w:r></w:p>".to_string(),
+        // Enter at the end of a paragraph, then new text typed
+        marked_paragraph("ins", "3", "<w:r><w:t>Clause ends.</w:t></w:r>"),
+        r#"<w:p><w:ins w:id="4" w:author="Ola"><w:r><w:t>Typed after Enter.</w:t></w:r></w:ins></w:p>"#.to_string(),
+        // A whole paragraph pasted between two halves
+        marked_paragraph("ins", "5", "<w:r><w:t>Before paste.</w:t></w:r>"),
+        marked_paragraph("ins", "6", r#"<w:ins w:id="7" w:author="Ola"><w:r><w:t>Pasted.</w:t></w:r></w:ins>"#),
+        "<w:p><w:r><w:t>After paste.</w:t></w:r></w:p>".to_string(),
+    ]
+    .concat());
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "ola.docx").expect("Should parse");
+    let p = &result.paragraphs;
+    assert_eq!(p.len(), 9);
+
+    let relations: Vec<(Option<usize>, Option<usize>)> =
+        p.iter().map(|b| (b.split_from, b.merged_into)).collect();
+    assert_eq!(
+        relations,
+        vec![
+            (None, None),
+            (Some(0), None),
+            (None, None),
+            (None, Some(2)),
+            (None, None),
+            (Some(4), None),
+            (None, None),
+            (Some(6), None),
+            (Some(6), None),
+        ]
+    );
+
+    let split = &p[1].track_changes[0];
+    assert!(matches!(split.change_type, ChangeType::ParagraphSplit));
+    assert_eq!(split.id, "1");
+    assert_eq!(split.author, "Ola");
+    assert_eq!(split.date.as_deref(), Some("2024-08-05T11:00:00Z"));
+    assert_eq!(split.context_before, "Payment is due");
+    assert_eq!(split.context_after, "within 28 days.");
+    assert_eq!(p[1].paragraph_status, ParagraphStatus::Normal);
+
+    let join = &p[3].track_changes[0];
+    assert!(matches!(join.change_type, ChangeType::ParagraphJoin));
+    assert_eq!(join.id, "2");
+
+    // The second half of a split is not a new paragraph, even when all its
+    // text was typed after the split
+    assert_eq!(p[5].paragraph_status, ParagraphStatus::Normal);
+    assert_eq!(p[5].track_changes.len(), 2);
+
+    // A pasted paragraph has its own mark inserted, so it is new; the text
+    // after it continues the paragraph the paste split, with no second change
+    assert_eq!(p[7].paragraph_status, ParagraphStatus::WhollyInserted);
+    assert!(matches!(
+        p[7].track_changes[0].change_type,
+        ChangeType::ParagraphSplit
+    ));
+    assert!(p[8].track_changes.is_empty());
+}
+
+#[test]
+fn merged_split_points_at_the_base_paragraph() {
+    let base = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(
+            r#"<w:p><w:r><w:t>Preamble to the agreement.</w:t></w:r></w:p>
+            <w:p><w:r><w:t>The buyer shall pay the price in full within twenty eight days of delivery.</w:t></w:r></w:p>"#,
+        ),
+    )]);
+    let reviewer = create_docx_bytes(&[(
+        "word/document.xml",
+        &minimal_document_xml(&format!(
+            "{}{}",
+            marked_paragraph(
+                "ins",
+                "1",
+                "<w:r><w:t>The buyer shall pay the price in full within twenty eight days</w:t></w:r>"
+            ),
+            "<w:p><w:r><w:t>of delivery.</w:t></w:r></w:p>"
+        )),
+    )]);
+
+    let result = merge::collate(
+        &[("base.docx", &base), ("ola.docx", &reviewer)],
+        "base.docx",
+    )
+    .expect("Should collate");
+    let last = result.paragraphs.last().unwrap();
+    assert_eq!(last.base_text, "of delivery.");
+    assert_eq!(last.split_from, Some(1));
+    assert!(matches!(
+        last.track_changes[0].change_type,
+        ChangeType::ParagraphSplit
+    ));
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 25. Table structure revisions
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn row_and_cell_revisions_are_reported_with_their_text() {
+    let cell = |runs: &str| format!("<w:tc><w:p>{}</w:p></w:tc>", runs);
+    let text = |t: &str| format!("<w:r><w:t>{}</w:t></w:r>", t);
+    let inserted = |id: &str, t: &str| {
+        format!(
+            r#"<w:ins w:id="{}" w:author="Kai"><w:r><w:t>{}</w:t></w:r></w:ins>"#,
+            id, t
+        )
+    };
+    let deleted = |id: &str, t: &str| {
+        format!(
+            r#"<w:del w:id="{}" w:author="Kai"><w:r><w:delText>{}</w:delText></w:r></w:del>"#,
+            id, t
+        )
+    };
+    let doc_xml = minimal_document_xml(&format!(
+        r#"<w:tbl>
+            <w:tr><w:trPr><w:tblHeader/></w:trPr>{}{}</w:tr>
+            <w:tr>{}{}<w:tc><w:tcPr><w:cellIns w:id="20" w:author="Kai" w:date="2024-09-01T08:00:00Z"/></w:tcPr><w:p>{}</w:p></w:tc></w:tr>
+            <w:tr><w:trPr><w:ins w:id="21" w:author="Kai" w:date="2024-09-01T08:05:00Z"/></w:trPr>{}<w:tc><w:p>{}</w:p><w:p>{}</w:p></w:tc></w:tr>
+            <w:tr><w:trPr><w:del w:id="22" w:author="Kai" w:date="2024-09-01T08:10:00Z"/></w:trPr>{}{}</w:tr>
+        </w:tbl>"#,
+        cell(&text("Date")),
+        cell(&text("Event")),
+        cell(&text("3 May")),
+        cell(&text("Contract signed")),
+        inserted("1", "Witnessed"),
+        cell(&inserted("2", "12 May")),
+        inserted("3", "Site visit"),
+        inserted("4", "Defects noted"),
+        cell(&deleted("5", "19 May")),
+        cell(&deleted("6", "Payment made")),
+    ));
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "kai.docx").expect("Should parse");
+
+    let tables: Vec<(&TrackChange, &TableCoordinates)> = result
+        .paragraphs
+        .iter()
+        .flat_map(|p| {
+            p.track_changes
+                .iter()
+                .map(move |tc| (tc, p.table.as_ref().unwrap()))
+        })
+        .filter(|(tc, _)| {
+            matches!(
+                tc.change_type,
+                ChangeType::RowInsertion
+                    | ChangeType::RowDeletion
+                    | ChangeType::CellInsertion
+                    | ChangeType::CellDeletion
+            )
+        })
+        .collect();
+    assert_eq!(tables.len(), 3);
+
+    let (cell_change, at) = tables[0];
+    assert!(matches!(cell_change.change_type, ChangeType::CellInsertion));
+    assert_eq!((at.row, at.column), (1, 2));
+    assert_eq!(cell_change.id, "20");
+    assert_eq!(cell_change.author, "Kai");
+    assert_eq!(cell_change.new_text, "Witnessed");
+
+    let (row_added, at) = tables[1];
+    assert!(matches!(row_added.change_type, ChangeType::RowInsertion));
+    assert_eq!((at.row, at.column), (2, 0));
+    assert_eq!(row_added.date.as_deref(), Some("2024-09-01T08:05:00Z"));
+    assert_eq!(row_added.new_text, "12 May | Site visit Defects noted");
+    assert_eq!(row_added.original_text, "");
+
+    let (row_removed, at) = tables[2];
+    assert!(matches!(row_removed.change_type, ChangeType::RowDeletion));
+    assert_eq!(at.row, 3);
+    assert_eq!(row_removed.original_text, "19 May | Payment made");
+
+    // Only one row change per row, on its first paragraph
+    let kai = result.reviewers.iter().find(|r| r.name == "Kai").unwrap();
+    assert_eq!(kai.change_count, 9);
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 26. Layout revisions
+// ═══════════════════════════════════════════════════════════════════════
+
+fn layout_changes(result: &CollateResult) -> Vec<(usize, &TrackChange)> {
+    result
+        .paragraphs
+        .iter()
+        .flat_map(|p| p.track_changes.iter().map(move |tc| (p.index, tc)))
+        .filter(|(_, tc)| matches!(tc.change_type, ChangeType::LayoutChange))
+        .collect()
+}
+
+#[test]
+fn numbering_and_section_revisions_are_layout_changes() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="3"/><w:numberingChange w:id="30" w:author="Rosa" w:date="2024-10-01T09:00:00Z" w:original="%1."/></w:numPr></w:pPr>
+            <w:r><w:t>Scope</w:t></w:r>
+        </w:p>
+        <w:p>
+            <w:pPr><w:sectPr>
+                <w:pgSz w:w="15840" w:h="12240" w:orient="landscape"/>
+                <w:sectPrChange w:id="31" w:author="Rosa" w:date="2024-10-01T09:05:00Z"><w:sectPr><w:pgSz w:w="12240" w:h="15840"/></w:sectPr></w:sectPrChange>
+            </w:sectPr></w:pPr>
+            <w:r><w:t>Schedule</w:t></w:r>
+        </w:p>
+        <w:p><w:r><w:t>Terms</w:t></w:r></w:p>
+        <w:sectPr>
+            <w:headerReference w:type="default" r:id="rId7"/>
+            <w:pgMar w:top="720" w:bottom="1440"/>
+            <w:titlePg/>
+            <w:sectPrChange w:id="32" w:author="Lee"><w:sectPr><w:pgMar w:top="1440" w:bottom="1440"/></w:sectPr></w:sectPrChange>
+        </w:sectPr>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "rosa.docx").expect("Should parse");
+
+    let changes = layout_changes(&result);
+    assert_eq!(changes.len(), 3);
+
+    let (index, numbering) = changes[0];
+    assert_eq!(index, 0);
+    assert_eq!(numbering.id, "30");
+    assert_eq!(numbering.author, "Rosa");
+    assert_eq!(numbering.date.as_deref(), Some("2024-10-01T09:00:00Z"));
+    assert_eq!(
+        numbering.summary.as_deref(),
+        Some(r#"numbering "%1." → list 3 level 1"#)
+    );
+
+    let (index, section) = changes[1];
+    assert_eq!(index, 1);
+    assert_eq!(section.id, "31");
+    assert_eq!(
+        section.summary.as_deref(),
+        Some("section: page size 612pt × 792pt → 792pt × 612pt landscape")
+    );
+    assert_eq!(section.original_text, "");
+    assert_eq!(section.new_text, "");
+
+    // The last section's properties are reported on the last paragraph;
+    // header references are not compared
+    let (index, last_section) = changes[2];
+    assert_eq!(index, 2);
+    assert_eq!(last_section.author, "Lee");
+    assert_eq!(
+        last_section.summary.as_deref(),
+        Some("section: margins top 72pt bottom 72pt → top 36pt bottom 72pt, different first page added")
+    );
+
+    // Layout changes leave the text alone
+    assert!(result
+        .paragraphs
+        .iter()
+        .all(|p| p.base_text == p.revised_text));
+    let rosa = result.reviewers.iter().find(|r| r.name == "Rosa").unwrap();
+    assert_eq!(rosa.change_count, 2);
+}
+
+#[test]
+fn table_row_and_cell_property_revisions_are_layout_changes() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:tbl>
+            <w:tblPr>
+                <w:tblStyle w:val="GridTable1"/>
+                <w:tblPrChange w:id="40" w:author="Rosa"><w:tblPr><w:tblStyle w:val="TableGrid"/></w:tblPr></w:tblPrChange>
+            </w:tblPr>
+            <w:tr>
+                <w:trPr>
+                    <w:tblHeader/>
+                    <w:cantSplit/>
+                    <w:trPrChange w:id="41" w:author="Rosa"><w:trPr><w:tblHeader/></w:trPr></w:trPrChange>
+                </w:trPr>
+                <w:tc><w:p><w:r><w:t>Item</w:t></w:r></w:p></w:tc>
+                <w:tc><w:p><w:r><w:t>Cost</w:t></w:r></w:p></w:tc>
+            </w:tr>
+            <w:tr>
+                <w:tc><w:p><w:r><w:t>Survey</w:t></w:r></w:p></w:tc>
+                <w:tc>
+                    <w:tcPr>
+                        <w:vAlign w:val="center"/>
+                        <w:tcPrChange w:id="42" w:author="Lee"><w:tcPr/></w:tcPrChange>
+                    </w:tcPr>
+                    <w:p><w:r><w:t>400</w:t></w:r></w:p>
+                </w:tc>
+            </w:tr>
+        </w:tbl>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "rosa.docx").expect("Should parse");
+
+    let table = |index: usize| result.paragraphs[index].table.as_ref().unwrap();
+    assert!(table(0).is_header);
+    assert!(!table(2).is_header);
+    assert_eq!(table(3).column_header.as_deref(), Some("Cost"));
+
+    let changes = layout_changes(&result);
+    let summaries: Vec<(usize, &str, Option<&str>)> = changes
+        .iter()
+        .map(|(index, tc)| (*index, tc.id.as_str(), tc.summary.as_deref()))
+        .collect();
+    // Row and table changes are both reported on the table's first
+    // paragraph, in the order their elements close
+    assert_eq!(
+        summaries,
+        vec![
+            (0, "41", Some("row: keep row on one page added")),
+            (0, "40", Some("table: table style TableGrid → GridTable1")),
+            (3, "42", Some("cell: vertical alignment center added")),
+        ]
+    );
+    assert_eq!(changes[2].1.author, "Lee");
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 27. Fields
+// ═══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn field_codes_are_left_out_and_results_kept() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t>Refer to [</w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText xml:space="preserve"> REF _Ref123456 \h </w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:r><w:t>12.3</w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>
+            <w:r><w:t>] for terms.</w:t></w:r>
+        </w:p>
+        <w:p>
+            <w:r><w:t>Page [</w:t></w:r>
+            <w:fldSimple w:instr=" PAGE \* MERGEFORMAT "><w:r><w:t>4</w:t></w:r></w:fldSimple>
+            <w:r><w:t>] of the</w:t></w:r>
+            <w:fldSimple w:instr='HYPERLINK "https://example.com/terms of sale"'><w:r><w:t>terms</w:t></w:r></w:fldSimple>
+        </w:p>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "ada.docx").expect("Should parse");
+
+    let reference = &result.paragraphs[0];
+    assert_eq!(reference.base_text, "Refer to [12.3] for terms.");
+    assert_eq!(reference.revised_text, "Refer to [12.3] for terms.");
+    assert_eq!(
+        reference.fields,
+        vec![Field {
+            field_type: "REF".to_string(),
+            target: Some("_Ref123456".to_string()),
+            instruction: r"REF _Ref123456 \h".to_string(),
+            result: "12.3".to_string(),
+        }]
+    );
+
+    let simple = &result.paragraphs[1];
+    assert_eq!(simple.base_text, "Page [4] of theterms");
+    let summary: Vec<(&str, Option<&str>, &str)> = simple
+        .fields
+        .iter()
+        .map(|f| {
+            (
+                f.field_type.as_str(),
+                f.target.as_deref(),
+                f.result.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("PAGE", None, "4"),
+            (
+                "HYPERLINK",
+                Some("https://example.com/terms of sale"),
+                "terms"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn field_code_split_inside_an_argument_is_joined_as_written() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText xml:space="preserve"> REF _Ref12</w:instrText></w:r>
+            <w:r><w:instrText xml:space="preserve">3456 \h </w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:r><w:t>12.3</w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>
+            <w:r><w:t xml:space="preserve"> applies.</w:t></w:r>
+        </w:p>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "ada.docx").expect("Should parse");
+
+    let field = &result.paragraphs[0].fields[0];
+    assert_eq!(field.field_type, "REF");
+    assert_eq!(field.target.as_deref(), Some("_Ref123456"));
+    assert_eq!(field.instruction, r"REF _Ref123456 \h");
+    // Text after the field is trimmed as before
+    assert_eq!(result.paragraphs[0].base_text, "12.3applies.");
+}
+
+#[test]
+fn field_results_over_several_paragraphs_are_split_between_them() {
+    let pageref = |bookmark: &str, page: &str| {
+        format!(
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText>PAGEREF {} \h</w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:r><w:t>{}</w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+            bookmark, page
+        )
+    };
+    let doc_xml = minimal_document_xml(&format!(
+        r#"<w:p>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText xml:space="preserve"> TOC \o "1-3" \h </w:instrText></w:r>
+            <w:r><w:instrText xml:space="preserve">\z \u </w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:r><w:t>Introduction:</w:t></w:r>{}
+        </w:p>
+        <w:p>
+            <w:r><w:t>Scope:</w:t></w:r>{}
+        </w:p>
+        <w:p><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>
+        <w:p><w:r><w:t>Introduction</w:t></w:r></w:p>"#,
+        pageref("_Toc1", "1"),
+        pageref("_Toc2", "2"),
+    ));
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "ada.docx").expect("Should parse");
+
+    // The paragraph holding only the field end has no text
+    assert_eq!(result.paragraphs.len(), 3);
+    let fields = |index: usize| -> Vec<(String, Option<String>, String)> {
+        result.paragraphs[index]
+            .fields
+            .iter()
+            .map(|f| (f.field_type.clone(), f.target.clone(), f.result.clone()))
+            .collect()
+    };
+    let field = |kind: &str, target: Option<&str>, text: &str| {
+        (
+            kind.to_string(),
+            target.map(str::to_string),
+            text.to_string(),
+        )
+    };
+
+    assert_eq!(result.paragraphs[0].base_text, "Introduction:1");
+    assert_eq!(
+        fields(0),
+        vec![
+            field("TOC", None, "Introduction:1"),
+            field("PAGEREF", Some("_Toc1"), "1"),
+        ]
+    );
+    assert_eq!(
+        result.paragraphs[0].fields[0].instruction,
+        r#"TOC \o "1-3" \h \z \u"#
+    );
+    assert_eq!(result.paragraphs[1].base_text, "Scope:2");
+    assert_eq!(
+        fields(1),
+        vec![
+            field("TOC", None, "Scope:2"),
+            field("PAGEREF", Some("_Toc2"), "2"),
+        ]
+    );
+    assert!(result.paragraphs[2].fields.is_empty());
+}
+
+#[test]
+fn tracked_field_updates_change_only_the_result() {
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t>See clause</w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText>REF _Ref7 \r \h</w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:del w:id="1" w:author="Ada"><w:r><w:delText>4.2</w:delText></w:r></w:del>
+            <w:ins w:id="2" w:author="Ada"><w:r><w:t>4.3</w:t></w:r></w:ins>
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>
+            <w:del w:id="3" w:author="Ada">
+                <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+                <w:r><w:delInstrText>DATE \@ "d MMMM yyyy"</w:delInstrText></w:r>
+                <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+                <w:r><w:delText>, 1 May 2024</w:delText></w:r>
+                <w:r><w:fldChar w:fldCharType="end"/></w:r>
+            </w:del>
+            <w:r><w:t>.</w:t></w:r>
+        </w:p>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+    let result = docx::parse(&data, "ada.docx").expect("Should parse");
+
+    let para = &result.paragraphs[0];
+    assert_eq!(para.base_text, "See clause4.2, 1 May 2024.");
+    assert_eq!(para.revised_text, "See clause4.3.");
+    assert_eq!(para.fields.len(), 2);
+    assert_eq!(para.fields[0].result, "4.3");
+    assert_eq!(para.fields[1].field_type, "DATE");
+    assert_eq!(para.fields[1].result, "");
+
+    let texts: Vec<(&str, &str)> = para
+        .track_changes
+        .iter()
+        .map(|tc| (tc.original_text.as_str(), tc.new_text.as_str()))
+        .collect();
+    assert_eq!(texts, vec![("4.2", ""), ("", "4.3"), (", 1 May 2024", "")]);
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+// 28. Machine-generated changes
+// ═══════════════════════════════════════════════════════════════════════
+
+fn field_update_document() -> Vec<u8> {
+    let field = |code: &str, result: &str| {
+        format!(
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText>{}</w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>{}
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+            code, result
+        )
+    };
+    let replaced = |old: &str, new: &str, author: &str| {
+        format!(
+            r#"<w:del w:id="d{old}" w:author="{author}"><w:r><w:delText>{old}</w:delText></w:r></w:del><w:ins w:id="i{new}" w:author="{author}"><w:r><w:t>{new}</w:t></w:r></w:ins>"#,
+        )
+    };
+    let doc_xml = minimal_document_xml(&format!(
+        r#"<w:p>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText>TOC \o "1-3" \h</w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:r><w:t>Scope:</w:t></w:r>{}
+        </w:p>
+        <w:p>
+            <w:ins w:id="20" w:author="Word"><w:r><w:t>Definitions:</w:t></w:r></w:ins>{}
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>
+        </w:p>
+        <w:p>
+            <w:r><w:t>As clause</w:t></w:r>{}
+            <w:r><w:t>says, the</w:t></w:r>
+            <w:ins w:id="21" w:author="Zoe"><w:r><w:t>tenant</w:t></w:r></w:ins>
+            <w:r><w:t>must see</w:t></w:r>{}
+        </w:p>"#,
+        field(r"PAGEREF _Toc1 \h", &replaced("3", "4", "Word")),
+        field(
+            r"PAGEREF _Toc2 \h",
+            r#"<w:ins w:id="22" w:author="Word"><w:r><w:t>5</w:t></w:r></w:ins>"#
+        ),
+        field(r"REF _Ref9 \r \h", &replaced("2.1", "2.2", "Zoe")),
+        field(
+            r#"HYPERLINK "https://example.com""#,
+            &replaced("the site", "our site", "Zoe")
+        ),
+    ));
+    create_docx_bytes(&[("word/document.xml", &doc_xml)])
+}
+
+#[test]
+fn changes_inside_updated_field_results_are_flagged() {
+    let result = docx::parse(&field_update_document(), "zoe.docx").expect("Should parse");
+
+    let flags: Vec<Vec<(&str, Option<&str>)>> = result
+        .paragraphs
+        .iter()
+        .map(|p| {
+            p.track_changes
+                .iter()
+                .map(|tc| (tc.id.as_str(), tc.machine_generated.as_deref()))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        flags,
+        vec![
+            // Page numbers of a table of contents belong to it
+            vec![("d3", Some("TOC")), ("i4", Some("TOC"))],
+            vec![("20", Some("TOC")), ("22", Some("TOC"))],
+            // A hyperlink's text and edits outside fields are the reviewer's
+            vec![
+                ("d2.1", Some("REF")),
+                ("i2.2", Some("REF")),
+                ("21", None),
+                ("dthe site", None),
+                ("iour site", None),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn machine_generated_changes_can_be_dropped() {
+    let mut result = docx::parse(&field_update_document(), "zoe.docx").expect("Should parse");
+    let count = |result: &CollateResult, name: &str| {
+        result
+            .reviewers
+            .iter()
+            .find(|r| r.name == name)
+            .map(|r| r.change_count)
+    };
+    assert_eq!(count(&result, "Word"), Some(4));
+    assert_eq!(count(&result, "Zoe"), Some(5));
+
+    matcher::drop_machine_generated(&mut result);
+
+    let ids: Vec<&str> = result
+        .paragraphs
+        .iter()
+        .flat_map(|p| p.track_changes.iter().map(|tc| tc.id.as_str()))
+        .collect();
+    assert_eq!(ids, vec!["21", "dthe site", "iour site"]);
+    // Word made only generated changes, so is no longer a reviewer at all
+    assert_eq!(count(&result, "Word"), None);
+    assert_eq!(count(&result, "Zoe"), Some(3));
+    let versions: Vec<Vec<&str>> = result
+        .paragraphs
+        .iter()
+        .map(|p| {
+            p.reviewer_versions
+                .iter()
+                .map(|v| v.reviewer_name.as_str())
+                .collect()
+        })
+        .collect();
+    assert_eq!(versions, vec![vec![], vec![], vec!["Zoe"]]);
+    // The text still shows the updated results
+    assert_eq!(
+        result.paragraphs[2].revised_text,
+        "As clause2.2says, thetenantmust seeour site"
+    );
+}
+
+#[test]
+fn page_numbers_are_machine_generated_and_dropping_them_clears_their_conflicts() {
+    // Word filled in a page number that Zoe then deleted
+    let doc_xml = minimal_document_xml(
+        r#"<w:p>
+            <w:r><w:t xml:space="preserve">Page </w:t></w:r>
+            <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+            <w:r><w:instrText xml:space="preserve"> PAGE </w:instrText></w:r>
+            <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+            <w:ins w:id="1" w:author="Word">
+                <w:del w:id="2" w:author="Zoe"><w:r><w:delText>2</w:delText></w:r></w:del>
+            </w:ins>
+            <w:r><w:fldChar w:fldCharType="end"/></w:r>
+        </w:p>"#,
+    );
+    let data = create_docx_bytes(&[("word/document.xml", &doc_xml)]);
+
+    let result = crate::parse(&data, "zoe.docx", &crate::CollateOptions::default());
+    let block = &result.paragraphs[0];
+    let flags: Vec<Option<&str>> = block
+        .track_changes
+        .iter()
+        .map(|tc| tc.machine_generated.as_deref())
+        .collect();
+    assert_eq!(flags, vec![Some("PAGE"), Some("PAGE")]);
+    assert_eq!(block.conflicts.len(), 1);
+
+    let options = crate::CollateOptions {
+        drop_machine_generated: true,
+    };
+    let result = crate::parse(&data, "zoe.docx", &options);
+    let block = &result.paragraphs[0];
+    assert!(block.track_changes.is_empty());
+    assert!(block.conflicts.is_empty());
+    assert!(result.reviewers.is_empty());
+}
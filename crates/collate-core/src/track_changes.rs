@@ -1,6 +1,8 @@
-use crate::types::{ChangeType, TrackChange};
+use crate::types::{ChangeType, MoveEnd, MoveLink, TrackChange};
 
-/// Represents a segment of a paragraph: either stable text, an insertion, or a deletion
+/// Represents a segment of a paragraph: stable text, an insertion, a deletion,
+/// one end of a move, text under several revisions, or text whose formatting
+/// was changed with tracking on
 #[derive(Debug, Clone)]
 pub enum Segment {
     Stable(String),
@@ -16,6 +18,191 @@ pub enum Segment {
         date: Option<String>,
         text: String,
     },
+    /// Text moved away from here (`w:moveFrom`); behaves as a deletion.
+    /// `name` pairs it with the `MoveTo` segments of the same move.
+    MoveFrom {
+        id: String,
+        author: String,
+        date: Option<String>,
+        text: String,
+        name: String,
+    },
+    /// Text moved to here (`w:moveTo`); behaves as an insertion
+    MoveTo {
+        id: String,
+        author: String,
+        date: Option<String>,
+        text: String,
+        name: String,
+    },
+    /// Text under more than one revision, oldest (outermost) first, e.g.
+    /// inserted by one reviewer and deleted by another (`w:ins` wrapping `w:del`)
+    Nested {
+        revisions: Vec<Revision>,
+        text: String,
+    },
+    /// Text present in both versions whose run properties changed (`w:rPrChange`)
+    FormatChange {
+        revision: FormatRevision,
+        text: String,
+    },
+}
+
+impl Segment {
+    /// The segment for `text` under `revisions`, outermost first: a plain
+    /// insertion, deletion or move end for one revision, `Nested` for several
+    pub fn revised(mut revisions: Vec<Revision>, text: String) -> Segment {
+        if revisions.len() > 1 {
+            return Segment::Nested { revisions, text };
+        }
+        let Some(Revision {
+            kind,
+            id,
+            author,
+            date,
+        }) = revisions.pop()
+        else {
+            return Segment::Stable(text);
+        };
+        match kind {
+            RevisionKind::Insertion => Segment::Insertion {
+                id,
+                author,
+                date,
+                text,
+            },
+            RevisionKind::Deletion => Segment::Deletion {
+                id,
+                author,
+                date,
+                text,
+            },
+            RevisionKind::MoveFrom(name) => Segment::MoveFrom {
+                id,
+                author,
+                date,
+                text,
+                name,
+            },
+            RevisionKind::MoveTo(name) => Segment::MoveTo {
+                id,
+                author,
+                date,
+                text,
+                name,
+            },
+        }
+    }
+
+    /// Whether the segment holds anything but whitespace, in either version
+    pub fn has_content(&self) -> bool {
+        let text = match self {
+            Segment::Stable(text)
+            | Segment::Insertion { text, .. }
+            | Segment::Deletion { text, .. }
+            | Segment::MoveFrom { text, .. }
+            | Segment::MoveTo { text, .. }
+            | Segment::Nested { text, .. }
+            | Segment::FormatChange { text, .. } => text,
+        };
+        !text.trim().is_empty()
+    }
+}
+
+/// One tracked revision of a run of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    pub kind: RevisionKind,
+    pub id: String,
+    pub author: String,
+    pub date: Option<String>,
+}
+
+/// What a revision did to its text; moves carry the move's name
+#[derive(Debug, Clone, PartialEq)]
+pub enum RevisionKind {
+    Insertion,
+    Deletion,
+    MoveFrom(String),
+    MoveTo(String),
+}
+
+impl Revision {
+    /// Whether the revision brought the text in (insertion or move destination)
+    pub fn adds(&self) -> bool {
+        matches!(self.kind, RevisionKind::Insertion | RevisionKind::MoveTo(_))
+    }
+}
+
+/// Whether text under `revisions`, outermost first, is in the base text: the
+/// oldest revision did not add it
+fn in_base(revisions: &[Revision]) -> bool {
+    revisions.first().is_none_or(|r| !r.adds())
+}
+
+/// Whether text under `revisions` is in the revised text: the newest revision
+/// did not remove it
+fn in_revised(revisions: &[Revision]) -> bool {
+    revisions.last().is_none_or(|r| r.adds())
+}
+
+/// A tracked insertion or deletion of a table row (`w:trPr/w:ins|del`) or
+/// cell (`w:tcPr/w:cellIns|cellDel`), with the text the row or cell holds
+#[derive(Debug, Clone)]
+pub struct TableRevision {
+    pub revision: Revision,
+    pub scope: TableScope,
+    /// Cells separated by " | ", paragraphs within a cell by a space
+    pub text: String,
+}
+
+/// What a table revision inserted or deleted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableScope {
+    Row,
+    Cell,
+}
+
+/// A tracked change to the layout around text rather than its formatting:
+/// list numbering, section, table, row or cell properties
+#[derive(Debug, Clone)]
+pub struct LayoutRevision {
+    pub revision: FormatRevision,
+    pub scope: LayoutScope,
+}
+
+/// What a layout revision changed the properties of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutScope {
+    /// `w:numberingChange` in a paragraph's `w:numPr`
+    Numbering,
+    /// `w:sectPrChange`
+    Section,
+    /// `w:tblPrChange`
+    Table,
+    /// `w:trPrChange`
+    Row,
+    /// `w:tcPrChange`
+    Cell,
+}
+
+/// A tracked change to run or paragraph properties
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatRevision {
+    pub id: String,
+    pub author: String,
+    pub date: Option<String>,
+    pub properties: Vec<PropertyChange>,
+}
+
+/// One property changed by a format revision, named by its WordprocessingML
+/// element (`b`, `pStyle`, ...). Values are `w:val`, or `attribute=value` pairs
+/// for properties with several attributes; `None` when the property was unset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyChange {
+    pub name: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
 }
 
 /// Reconstruct the base text (before changes) from segments
@@ -23,9 +210,17 @@ pub fn base_text(segments: &[Segment]) -> String {
     let mut result = String::new();
     for seg in segments {
         match seg {
-            Segment::Stable(t) => result.push_str(t),
-            Segment::Deletion { text, .. } => result.push_str(text), // deleted text was in original
-            Segment::Insertion { .. } => {} // inserted text was not in original
+            Segment::Stable(t) | Segment::FormatChange { text: t, .. } => result.push_str(t),
+            // deleted text was in original
+            Segment::Deletion { text, .. } | Segment::MoveFrom { text, .. } => {
+                result.push_str(text)
+            }
+            Segment::Insertion { .. } | Segment::MoveTo { .. } => {} // inserted text was not in original
+            Segment::Nested { revisions, text } => {
+                if in_base(revisions) {
+                    result.push_str(text);
+                }
+            }
         }
     }
     result
@@ -36,9 +231,48 @@ pub fn revised_text(segments: &[Segment]) -> String {
     let mut result = String::new();
     for seg in segments {
         match seg {
-            Segment::Stable(t) => result.push_str(t),
-            Segment::Insertion { text, .. } => result.push_str(text), // inserted text appears in revised
-            Segment::Deletion { .. } => {} // deleted text removed in revised
+            Segment::Stable(t) | Segment::FormatChange { text: t, .. } => result.push_str(t),
+            // inserted text appears in revised
+            Segment::Insertion { text, .. } | Segment::MoveTo { text, .. } => result.push_str(text),
+            Segment::Deletion { .. } | Segment::MoveFrom { .. } => {} // deleted text removed in revised
+            Segment::Nested { revisions, text } => {
+                if in_revised(revisions) {
+                    result.push_str(text);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Reconstruct the text one reviewer proposed: the base text with only that
+/// author's insertions and deletions applied. Other authors' changes are left out,
+/// so their deletions stay in and their insertions are dropped.
+pub fn author_text(segments: &[Segment], reviewer: &str) -> String {
+    let mut result = String::new();
+    for seg in segments {
+        match seg {
+            Segment::Stable(t) | Segment::FormatChange { text: t, .. } => result.push_str(t),
+            Segment::Insertion { author, text, .. } | Segment::MoveTo { author, text, .. } => {
+                if author == reviewer {
+                    result.push_str(text);
+                }
+            }
+            Segment::Deletion { author, text, .. } | Segment::MoveFrom { author, text, .. } => {
+                if author != reviewer {
+                    result.push_str(text);
+                }
+            }
+            Segment::Nested { revisions, text } => {
+                // Start from the base and apply the reviewer's own revisions in order
+                let mut present = in_base(revisions);
+                for revision in revisions.iter().filter(|r| r.author == reviewer) {
+                    present = revision.adds();
+                }
+                if present {
+                    result.push_str(text);
+                }
+            }
         }
     }
     result
@@ -71,6 +305,10 @@ pub fn extract_changes(segments: &[Segment]) -> Vec<TrackChange> {
                     new_text: String::new(),
                     context_before,
                     context_after,
+                    source_file: None,
+                    summary: None,
+                    move_link: None,
+                    machine_generated: None,
                 });
 
                 position_in_base += text.len();
@@ -88,17 +326,454 @@ pub fn extract_changes(segments: &[Segment]) -> Vec<TrackChange> {
                     new_text: text.clone(),
                     context_before,
                     context_after,
+                    source_file: None,
+                    summary: None,
+                    move_link: None,
+                    machine_generated: None,
                 });
                 // Insertions don't advance position in base text
             }
+            Segment::MoveFrom {
+                id,
+                author,
+                date,
+                text,
+                name,
+            } => {
+                let context_before = extract_context_before(&full_base, position_in_base, 30);
+                let context_after =
+                    extract_context_after(&full_base, position_in_base + text.len(), 30);
+
+                changes.push(TrackChange {
+                    id: id.clone(),
+                    change_type: ChangeType::Move,
+                    author: author.clone(),
+                    date: date.clone(),
+                    original_text: text.clone(),
+                    new_text: String::new(),
+                    context_before,
+                    context_after,
+                    source_file: None,
+                    summary: None,
+                    move_link: Some(move_link(name, MoveEnd::From)),
+                    machine_generated: None,
+                });
+
+                position_in_base += text.len();
+            }
+            Segment::MoveTo {
+                id,
+                author,
+                date,
+                text,
+                name,
+            } => {
+                let context_before = extract_context_before(&full_base, position_in_base, 30);
+                let context_after = extract_context_after(&full_base, position_in_base, 30);
+
+                changes.push(TrackChange {
+                    id: id.clone(),
+                    change_type: ChangeType::Move,
+                    author: author.clone(),
+                    date: date.clone(),
+                    original_text: String::new(),
+                    new_text: text.clone(),
+                    context_before,
+                    context_after,
+                    source_file: None,
+                    summary: None,
+                    move_link: Some(move_link(name, MoveEnd::To)),
+                    machine_generated: None,
+                });
+            }
+            Segment::Nested { revisions, text } => {
+                // One change per revision, oldest first, all over the same text
+                let base_len = if in_base(revisions) { text.len() } else { 0 };
+                let context_before = extract_context_before(&full_base, position_in_base, 30);
+                let context_after =
+                    extract_context_after(&full_base, position_in_base + base_len, 30);
+
+                for revision in revisions {
+                    changes.push(revision_change(
+                        revision,
+                        text,
+                        context_before.clone(),
+                        context_after.clone(),
+                    ));
+                }
+
+                position_in_base += base_len;
+            }
+            Segment::FormatChange { revision, text } => {
+                let context_before = extract_context_before(&full_base, position_in_base, 30);
+                let context_after =
+                    extract_context_after(&full_base, position_in_base + text.len(), 30);
+
+                changes.push(format_change(revision, text, context_before, context_after));
+
+                position_in_base += text.len();
+            }
         }
     }
 
     changes
 }
 
+/// The TrackChange for a format revision over `text`, which reads the same
+/// before and after the change
+pub fn format_change(
+    revision: &FormatRevision,
+    text: &str,
+    context_before: String,
+    context_after: String,
+) -> TrackChange {
+    TrackChange {
+        id: revision.id.clone(),
+        change_type: ChangeType::FormatChange,
+        author: revision.author.clone(),
+        date: revision.date.clone(),
+        original_text: text.to_string(),
+        new_text: text.to_string(),
+        context_before,
+        context_after,
+        source_file: None,
+        summary: Some(summarise(&revision.properties)),
+        move_link: None,
+        machine_generated: None,
+    }
+}
+
+/// The TrackChange for one revision of `text` within a `Nested` segment
+fn revision_change(
+    revision: &Revision,
+    text: &str,
+    context_before: String,
+    context_after: String,
+) -> TrackChange {
+    let (change_type, link) = match &revision.kind {
+        RevisionKind::Insertion => (ChangeType::Insertion, None),
+        RevisionKind::Deletion => (ChangeType::Deletion, None),
+        RevisionKind::MoveFrom(name) => (ChangeType::Move, Some(move_link(name, MoveEnd::From))),
+        RevisionKind::MoveTo(name) => (ChangeType::Move, Some(move_link(name, MoveEnd::To))),
+    };
+    let (original_text, new_text) = if revision.adds() {
+        (String::new(), text.to_string())
+    } else {
+        (text.to_string(), String::new())
+    };
+    TrackChange {
+        id: revision.id.clone(),
+        change_type,
+        author: revision.author.clone(),
+        date: revision.date.clone(),
+        original_text,
+        new_text,
+        context_before,
+        context_after,
+        source_file: None,
+        summary: None,
+        move_link: link,
+        machine_generated: None,
+    }
+}
+
+/// The TrackChange for a paragraph mark revision between a paragraph ending
+/// with `before` and the next one starting with `after`: an inserted mark
+/// splits the two, a deleted one joins them
+pub fn mark_change(revision: &Revision, before: &str, after: &str) -> TrackChange {
+    TrackChange {
+        id: revision.id.clone(),
+        change_type: if revision.adds() {
+            ChangeType::ParagraphSplit
+        } else {
+            ChangeType::ParagraphJoin
+        },
+        author: revision.author.clone(),
+        date: revision.date.clone(),
+        original_text: String::new(),
+        new_text: String::new(),
+        context_before: extract_context_before(before, before.len(), 30),
+        context_after: extract_context_after(after, 0, 30),
+        source_file: None,
+        summary: None,
+        move_link: None,
+        machine_generated: None,
+    }
+}
+
+/// The TrackChange for a row or cell insertion or deletion
+pub fn table_change(table: &TableRevision) -> TrackChange {
+    let adds = table.revision.adds();
+    let change_type = match (table.scope, adds) {
+        (TableScope::Row, true) => ChangeType::RowInsertion,
+        (TableScope::Row, false) => ChangeType::RowDeletion,
+        (TableScope::Cell, true) => ChangeType::CellInsertion,
+        (TableScope::Cell, false) => ChangeType::CellDeletion,
+    };
+    let (original_text, new_text) = if adds {
+        (String::new(), table.text.clone())
+    } else {
+        (table.text.clone(), String::new())
+    };
+    TrackChange {
+        id: table.revision.id.clone(),
+        change_type,
+        author: table.revision.author.clone(),
+        date: table.revision.date.clone(),
+        original_text,
+        new_text,
+        context_before: String::new(),
+        context_after: String::new(),
+        source_file: None,
+        summary: None,
+        move_link: None,
+        machine_generated: None,
+    }
+}
+
+/// The TrackChange for a layout revision, summarised with what it applies to,
+/// e.g. "section: page size 612pt × 792pt → 792pt × 612pt"
+pub fn layout_change(layout: &LayoutRevision) -> TrackChange {
+    let summary = summarise(&layout.revision.properties);
+    let scope = match layout.scope {
+        // The summary already starts with "numbering"
+        LayoutScope::Numbering => None,
+        LayoutScope::Section => Some("section"),
+        LayoutScope::Table => Some("table"),
+        LayoutScope::Row => Some("row"),
+        LayoutScope::Cell => Some("cell"),
+    };
+    TrackChange {
+        id: layout.revision.id.clone(),
+        change_type: ChangeType::LayoutChange,
+        author: layout.revision.author.clone(),
+        date: layout.revision.date.clone(),
+        original_text: String::new(),
+        new_text: String::new(),
+        context_before: String::new(),
+        context_after: String::new(),
+        source_file: None,
+        summary: Some(match scope {
+            Some(scope) => format!("{}: {}", scope, summary),
+            None => summary,
+        }),
+        move_link: None,
+        machine_generated: None,
+    }
+}
+
+/// An unpaired link; `matcher::link_moves` finds the other end
+fn move_link(name: &str, end: MoveEnd) -> MoveLink {
+    MoveLink {
+        name: name.to_string(),
+        end,
+        other_paragraph: None,
+        other_change_id: None,
+    }
+}
+
+/// Describe property changes for a reader, e.g. "bold added, style Heading 2 →
+/// Heading 3"
+pub fn summarise(properties: &[PropertyChange]) -> String {
+    let descriptions: Vec<String> = properties.iter().filter_map(describe).collect();
+    if descriptions.is_empty() {
+        "formatting changed".to_string()
+    } else {
+        descriptions.join(", ")
+    }
+}
+
+/// Properties that are either on or off; their values are not shown
+const TOGGLES: [&str; 19] = [
+    "b",
+    "i",
+    "strike",
+    "dstrike",
+    "caps",
+    "smallCaps",
+    "vanish",
+    "emboss",
+    "imprint",
+    "outline",
+    "shadow",
+    "keepNext",
+    "keepLines",
+    "pageBreakBefore",
+    "widowControl",
+    "contextualSpacing",
+    "titlePg",
+    "tblHeader",
+    "cantSplit",
+];
+
+/// Values that switch a property off (`w:b w:val="0"`, `w:u w:val="none"`, ...)
+const OFF_VALUES: [&str; 5] = ["0", "false", "off", "none", "baseline"];
+
+fn describe(change: &PropertyChange) -> Option<String> {
+    let name = change.name.as_str();
+    let label = match name {
+        "b" => "bold",
+        "i" => "italic",
+        "u" => "underline",
+        "strike" => "strikethrough",
+        "dstrike" => "double strikethrough",
+        "caps" => "all caps",
+        "smallCaps" => "small caps",
+        "vanish" => "hidden",
+        "color" => "colour",
+        "sz" => "font size",
+        "rFonts" => "font",
+        "highlight" => "highlight",
+        "shd" => "shading",
+        "vertAlign" => "vertical alignment",
+        "rStyle" => "character style",
+        "pStyle" => "style",
+        "jc" => "alignment",
+        "ind" => "indentation",
+        "spacing" => "spacing",
+        "numPr" => "numbering",
+        "keepNext" => "keep with next",
+        "keepLines" => "keep lines together",
+        "pageBreakBefore" => "page break before",
+        "widowControl" => "widow control",
+        "contextualSpacing" => "contextual spacing",
+        "pBdr" => "borders",
+        "tabs" => "tabs",
+        "outlineLvl" => "outline level",
+        "pgSz" => "page size",
+        "pgMar" => "margins",
+        "cols" => "columns",
+        "type" => "section start",
+        "titlePg" => "different first page",
+        "tblStyle" => "table style",
+        "tblBorders" | "tcBorders" => "borders",
+        "tblW" | "tcW" => "width",
+        "trHeight" => "row height",
+        "tblHeader" => "repeat as header row",
+        "cantSplit" => "keep row on one page",
+        "vAlign" => "vertical alignment",
+        "gridSpan" => "merged columns",
+        "vMerge" => "vertical merge",
+        other => other,
+    };
+    let on = |value: &Option<String>| {
+        value
+            .as_deref()
+            .is_some_and(|v| !OFF_VALUES.contains(&v))
+    };
+    // A paragraph without a style has the default one
+    let before = match (&change.before, name) {
+        (None, "pStyle") => Some("Normal".to_string()),
+        (before, _) => before.clone(),
+    };
+    let toggle = TOGGLES.contains(&name);
+    // Underline values already read as "double underline"
+    let named = name == "u";
+
+    match (on(&before), on(&change.after)) {
+        (false, true) => {
+            let value = display(name, change.after.as_deref().unwrap_or_default());
+            Some(if toggle {
+                format!("{} added", label)
+            } else if named {
+                format!("{} added", value)
+            } else {
+                format!("{} {} added", label, value)
+            })
+        }
+        (true, false) => Some(format!("{} removed", label)),
+        (true, true) if !toggle => {
+            let before = display(name, before.as_deref().unwrap_or_default());
+            let after = display(name, change.after.as_deref().unwrap_or_default());
+            Some(if named {
+                format!("{} → {}", before, after)
+            } else {
+                format!("{} {} → {}", label, before, after)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A property value as a reader would put it: sizes in points, colours as hex
+fn display(name: &str, value: &str) -> String {
+    let pairs: Vec<(&str, &str)> = value
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    let points = |twips: &str| match twips.parse::<f64>() {
+        Ok(t) => format!("{}pt", t / 20.0),
+        Err(_) => twips.to_string(),
+    };
+    match name {
+        // Half-points
+        "sz" => match value.parse::<f64>() {
+            Ok(half_points) => format!("{}pt", half_points / 2.0),
+            Err(_) => value.to_string(),
+        },
+        "color" if value == "auto" => "automatic".to_string(),
+        "color" => format!("#{}", value),
+        "u" if value == "single" => "underline".to_string(),
+        "u" => format!("{} underline", value),
+        "rFonts" => pairs
+            .iter()
+            .find(|(k, _)| *k == "ascii")
+            .or(pairs.first())
+            .map_or(value.to_string(), |(_, v)| v.to_string()),
+        "numPr" => {
+            let get = |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+            let level = get("ilvl")
+                .and_then(|l| l.parse::<usize>().ok())
+                .map(|l| format!(" level {}", l + 1))
+                .unwrap_or_default();
+            format!("list {}{}", get("numId").unwrap_or("?"), level)
+        }
+        // Numbering as Word last displayed it, e.g. "%1."
+        "numbering" if pairs.is_empty() => format!("\"{}\"", value),
+        "numbering" => display("numPr", value),
+        "ind" | "spacing" => pairs
+            .iter()
+            .map(|(k, v)| match *k {
+                "left" | "right" | "start" | "end" | "hanging" | "firstLine" | "before"
+                | "after" => format!("{} {}", k, points(v)),
+                _ => format!("{} {}", k, v),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        // Width × height, then the orientation if given
+        "pgSz" => {
+            let get = |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+            let size = format!(
+                "{} × {}",
+                get("w").map_or("?".to_string(), points),
+                get("h").map_or("?".to_string(), points)
+            );
+            match get("orient") {
+                Some(orient) => format!("{} {}", size, orient),
+                None => size,
+            }
+        }
+        "pgMar" => pairs
+            .iter()
+            .map(|(k, v)| match *k {
+                "top" | "bottom" | "left" | "right" | "header" | "footer" | "gutter" => {
+                    format!("{} {}", k, points(v))
+                }
+                _ => format!("{} {}", k, v),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ if !pairs.is_empty() => pairs
+            .iter()
+            .map(|(k, v)| format!("{} {}", k, v))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => value.to_string(),
+    }
+}
+
 fn extract_context_before(text: &str, pos: usize, max_chars: usize) -> String {
-    let start = if pos > max_chars { pos - max_chars } else { 0 };
+    let start = pos.saturating_sub(max_chars);
     text.get(start..pos).unwrap_or("").to_string()
 }
 
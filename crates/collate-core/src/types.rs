@@ -6,6 +6,9 @@ pub struct CollateResult {
     pub reviewers: Vec<Reviewer>,
     pub document_title: Option<String>,
     pub error: Option<String>,
+    /// Parts of the input that could not be read (e.g. headers in a .doc file)
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 /// Indicates whether a paragraph is wholly new, wholly deleted, or a normal
@@ -20,9 +23,65 @@ pub enum ParagraphStatus {
     WhollyDeleted,
 }
 
+/// Which part of the document a paragraph block comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockKind {
+    /// Main document body
+    #[default]
+    Body,
+    /// Footnote text (word/footnotes.xml)
+    Footnote,
+    /// Endnote text (word/endnotes.xml)
+    Endnote,
+    /// Page header part (word/header*.xml)
+    Header,
+    /// Page footer part (word/footer*.xml)
+    Footer,
+    /// Text box or shape text, anchored in a paragraph of the surrounding story
+    TextBox,
+}
+
+/// Position of a paragraph inside a table. Indices are 0-based; `column` counts
+/// grid columns, so a cell spanning several columns reports the first of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableCoordinates {
+    /// Tables are numbered in document order within their story, nested tables included
+    pub table_index: usize,
+    pub row: usize,
+    pub column: usize,
+    /// The row is marked as a repeating header row (`w:tblHeader`)
+    pub is_header: bool,
+    /// Text of the column's header cell, taken from the header rows (or the first
+    /// row when none are marked)
+    pub column_header: Option<String>,
+}
+
+/// Which pages of a section a header or footer is shown on (`w:type` of its reference)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HeaderFooterType {
+    /// Every page not covered by `First` or `Even`
+    Default,
+    /// First page of the section
+    First,
+    /// Even-numbered pages
+    Even,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParagraphBlock {
     pub index: usize,
+    pub kind: BlockKind,
+    /// For notes: the note's `w:id`
+    pub note_id: Option<String>,
+    /// For notes: index of the body paragraph that holds the note reference.
+    /// For text boxes: index of the paragraph the box is anchored in.
+    pub anchor_index: Option<usize>,
+    /// For headers and footers: 0-based index of the first section that uses the part
+    pub section: Option<usize>,
+    /// For headers and footers: which pages of the section show the part
+    pub header_footer_type: Option<HeaderFooterType>,
+    /// Where the paragraph sits when it is inside a table cell
+    pub table: Option<TableCoordinates>,
     pub base_text: String,
     /// The full revised text after all changes (for display when the paragraph is wholly new)
     pub revised_text: String,
@@ -32,7 +91,42 @@ pub struct ParagraphBlock {
     pub reviewer_versions: Vec<ReviewerVersion>,
     pub comments: Vec<Comment>,
     pub track_changes: Vec<TrackChange>,
-    pub has_conflicts: bool,
+    /// Base spans that more than one reviewer edited in incompatible ways
+    pub conflicts: Vec<Conflict>,
+    /// File this paragraph was taken from
+    pub source_file: Option<String>,
+    /// Word's `w14:paraId`, shared by the same paragraph in every copy
+    pub para_id: Option<String>,
+    /// Word's `w14:textId`, which changes when the paragraph's text is edited
+    pub text_id: Option<String>,
+    /// Set when a tracked paragraph mark insertion split this paragraph off
+    /// the end of another: the index of the paragraph it was split from
+    #[serde(default)]
+    pub split_from: Option<usize>,
+    /// Set when a tracked paragraph mark deletion joined this paragraph onto
+    /// the previous one: the index of the paragraph it was merged into
+    #[serde(default)]
+    pub merged_into: Option<usize>,
+    /// Fields whose results show in the paragraph, in document order
+    #[serde(default)]
+    pub fields: Vec<Field>,
+}
+
+/// A Word field (`w:fldSimple`, or a `w:fldChar` begin/separate/end sequence).
+/// Only its result is part of the paragraph text; the field code is kept here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+    /// Field type from the code, upper-cased, e.g. "REF", "PAGEREF", "TOC"
+    pub field_type: String,
+    /// First argument of the code unless it is a switch: the bookmark of a
+    /// REF or PAGEREF, the address of a HYPERLINK
+    pub target: Option<String>,
+    /// The whole field code, e.g. `REF _Ref123456 \h`
+    pub instruction: String,
+    /// The result as it reads in this paragraph's revised text. A result
+    /// running over several paragraphs (a table of contents) is split between
+    /// them.
+    pub result: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +135,25 @@ pub struct ReviewerVersion {
     pub resulting_text: String,
 }
 
+/// A span of the base text that several reviewers edited differently.
+/// Offsets are in characters of the paragraph's `base_text`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conflict {
+    pub start: usize,
+    pub end: usize,
+    pub original_text: String,
+    pub edits: Vec<ConflictingEdit>,
+}
+
+/// One reviewer's competing version of a conflicting span
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictingEdit {
+    pub author: String,
+    pub change_ids: Vec<String>,
+    /// The span as this reviewer would have it, with only their own edits applied
+    pub proposed_text: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackChange {
     pub id: String,
@@ -51,12 +164,72 @@ pub struct TrackChange {
     pub new_text: String,
     pub context_before: String,
     pub context_after: String,
+    /// File this change was read from
+    pub source_file: Option<String>,
+    /// For format and layout changes: which properties changed, e.g. "bold added"
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// For moves: which end this is and where the other end is
+    #[serde(default)]
+    pub move_link: Option<MoveLink>,
+    /// Set when the change lies wholly inside the result of a field Word
+    /// rewrites when fields update (a table of contents, a cross-reference,
+    /// a date): the type of that field
+    #[serde(default)]
+    pub machine_generated: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChangeType {
     Insertion,
     Deletion,
+    /// Formatting changed with tracking on (`w:rPrChange` / `w:pPrChange`);
+    /// the text itself is the same before and after
+    FormatChange,
+    /// Text moved elsewhere in the document (`w:moveFrom` / `w:moveTo`). Each
+    /// end is a separate change linked to the other through `move_link`.
+    Move,
+    /// A paragraph mark was inserted, splitting one paragraph in two. Reported
+    /// on the second paragraph; both texts are empty.
+    ParagraphSplit,
+    /// A paragraph mark was deleted, joining a paragraph onto the previous
+    /// one. Reported on the joined paragraph; both texts are empty.
+    ParagraphJoin,
+    /// A table row was inserted (`w:trPr/w:ins`); reported on the row's
+    /// first paragraph with the row's text
+    RowInsertion,
+    /// A table row was deleted (`w:trPr/w:del`)
+    RowDeletion,
+    /// A table cell was inserted (`w:tcPr/w:cellIns`); reported on the
+    /// cell's first paragraph with the cell's text
+    CellInsertion,
+    /// A table cell was deleted (`w:tcPr/w:cellDel`)
+    CellDeletion,
+    /// Numbering, section, table, row or cell properties changed
+    /// (`w:numberingChange`, `w:sectPrChange`, `w:tblPrChange`,
+    /// `w:trPrChange`, `w:tcPrChange`); `summary` says what changed
+    LayoutChange,
+}
+
+/// Which end of a tracked move a change is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoveEnd {
+    /// The text's old position; it is gone from the revised text
+    From,
+    /// The text's new position; it is not in the base text
+    To,
+}
+
+/// Link from one end of a tracked move to the other. Ends are paired by the
+/// move's name (`w:name` of its range markers) within the same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveLink {
+    pub name: String,
+    pub end: MoveEnd,
+    /// Index of the paragraph holding the other end, when it was found
+    pub other_paragraph: Option<usize>,
+    /// Id of the first change at the other end
+    pub other_change_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +240,8 @@ pub struct Comment {
     pub text: String,
     pub anchor_text: String,
     pub initials: Option<String>,
+    /// File this comment was read from
+    pub source_file: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
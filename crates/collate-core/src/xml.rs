@@ -0,0 +1,99 @@
+use quick_xml::name::{Namespace, ResolveResult};
+
+/// The vocabularies the parsers dispatch on, identified by namespace URI so
+/// that the prefix a producing tool chose makes no difference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ns {
+    /// WordprocessingML (`w:`)
+    Word,
+    /// Word 2010 extensions (`w14:`)
+    Word2010,
+    /// Markup Compatibility (`mc:`)
+    MarkupCompatibility,
+    /// DrawingML main (`a:`)
+    DrawingMl,
+    /// Package relationships (.rels files)
+    PackageRelationships,
+    /// Package content types (`[Content_Types].xml`)
+    ContentTypes,
+    /// Flat OPC package (`pkg:` in Word's single-file XML format)
+    FlatPackage,
+    /// OpenDocument text content (`text:`)
+    OdfText,
+    /// OpenDocument office elements (`office:`)
+    OdfOffice,
+    /// OpenDocument tables (`table:`)
+    OdfTable,
+    /// OpenDocument drawing shapes and frames (`draw:`)
+    OdfDraw,
+    /// OpenDocument metadata (`meta:`)
+    OdfMeta,
+    /// Dublin Core elements (`dc:` in docProps/core.xml)
+    DublinCore,
+    /// Anything else, including unbound prefixes
+    Other,
+}
+
+// Transitional and Strict (ISO/IEC 29500 Strict) spellings of the same vocabularies
+const WORDPROCESSINGML: &[u8] = b"http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const WORDPROCESSINGML_STRICT: &[u8] = b"http://purl.oclc.org/ooxml/wordprocessingml/main";
+const WORD_2010: &[u8] = b"http://schemas.microsoft.com/office/word/2010/wordml";
+const MARKUP_COMPATIBILITY: &[u8] = b"http://schemas.openxmlformats.org/markup-compatibility/2006";
+const DRAWINGML: &[u8] = b"http://schemas.openxmlformats.org/drawingml/2006/main";
+const DRAWINGML_STRICT: &[u8] = b"http://purl.oclc.org/ooxml/drawingml/main";
+const PACKAGE_RELATIONSHIPS: &[u8] =
+    b"http://schemas.openxmlformats.org/package/2006/relationships";
+const CONTENT_TYPES: &[u8] = b"http://schemas.openxmlformats.org/package/2006/content-types";
+const FLAT_PACKAGE: &[u8] = b"http://schemas.microsoft.com/office/2006/xmlPackage";
+const ODF_TEXT: &[u8] = b"urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const ODF_OFFICE: &[u8] = b"urn:oasis:names:tc:opendocument:xmlns:office:1.0";
+const ODF_TABLE: &[u8] = b"urn:oasis:names:tc:opendocument:xmlns:table:1.0";
+const ODF_DRAW: &[u8] = b"urn:oasis:names:tc:opendocument:xmlns:drawing:1.0";
+const ODF_META: &[u8] = b"urn:oasis:names:tc:opendocument:xmlns:meta:1.0";
+const DUBLIN_CORE: &[u8] = b"http://purl.org/dc/elements/1.1/";
+
+/// Classify the namespace an element or attribute name resolved to
+pub fn ns(resolved: &ResolveResult) -> Ns {
+    match resolved {
+        ResolveResult::Bound(Namespace(uri)) => match *uri {
+            WORDPROCESSINGML | WORDPROCESSINGML_STRICT => Ns::Word,
+            WORD_2010 => Ns::Word2010,
+            MARKUP_COMPATIBILITY => Ns::MarkupCompatibility,
+            DRAWINGML | DRAWINGML_STRICT => Ns::DrawingMl,
+            PACKAGE_RELATIONSHIPS => Ns::PackageRelationships,
+            CONTENT_TYPES => Ns::ContentTypes,
+            FLAT_PACKAGE => Ns::FlatPackage,
+            ODF_TEXT => Ns::OdfText,
+            ODF_OFFICE => Ns::OdfOffice,
+            ODF_TABLE => Ns::OdfTable,
+            ODF_DRAW => Ns::OdfDraw,
+            ODF_META => Ns::OdfMeta,
+            DUBLIN_CORE => Ns::DublinCore,
+            _ => Ns::Other,
+        },
+        _ => Ns::Other,
+    }
+}
+
+/// Prefixes of officeDocument relationship types, Transitional then Strict
+const RELATIONSHIP_TYPES: [&str; 2] = [
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/",
+    "http://purl.oclc.org/ooxml/officeDocument/relationships/",
+];
+
+/// The short name of an officeDocument relationship type (`header`,
+/// `comments`, `officeDocument`, ...), whichever conformance class it uses
+pub fn relationship_type(rel_type: &str) -> Option<&str> {
+    RELATIONSHIP_TYPES
+        .iter()
+        .find_map(|prefix| rel_type.strip_prefix(prefix))
+}
+
+/// Extract the local name from a potentially prefixed attribute name
+pub fn local_name(name: &[u8]) -> &[u8] {
+    if let Some(pos) = name.iter().position(|&b| b == b':') {
+        &name[pos + 1..]
+    } else {
+        name
+    }
+}
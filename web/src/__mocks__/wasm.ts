@@ -8,12 +8,29 @@ export interface CollateResult {
   reviewers: Reviewer[];
   document_title: string | null;
   error: string | null;
+  /** Parts of the input the parser could not read (e.g. headers in a .doc file) */
+  warnings?: string[];
 }
 
 export type ParagraphStatusType = 'Normal' | 'WhollyInserted' | 'WhollyDeleted';
 
+export type BlockKind = 'Body' | 'Footnote' | 'Endnote' | 'Header' | 'Footer' | 'TextBox';
+
+export type HeaderFooterType = 'Default' | 'First' | 'Even';
+
 export interface ParagraphBlock {
   index: number;
+  kind: BlockKind;
+  /** For footnote/endnote blocks: the note's id in the source document */
+  note_id: string | null;
+  /** For footnote/endnote blocks: index of the paragraph holding the reference;
+   *  for text boxes: index of the paragraph the box is anchored in */
+  anchor_index: number | null;
+  /** For header/footer blocks: 0-based index of the first section using the part */
+  section: number | null;
+  header_footer_type: HeaderFooterType | null;
+  /** Set when the paragraph is inside a table cell */
+  table: TableCoordinates | null;
   base_text: string;
   revised_text: string;
   paragraph_status: ParagraphStatusType;
@@ -21,7 +38,50 @@ export interface ParagraphBlock {
   reviewer_versions: ReviewerVersion[];
   comments: DocxComment[];
   track_changes: TrackChange[];
-  has_conflicts: boolean;
+  conflicts: Conflict[];
+  source_file: string | null;
+  /** Word's w14:paraId — the same paragraph in every copy shares it */
+  para_id: string | null;
+  text_id: string | null;
+  /** Index of the paragraph a tracked paragraph mark insertion split this one from */
+  split_from?: number | null;
+  /** Index of the paragraph a tracked paragraph mark deletion joined this one onto */
+  merged_into?: number | null;
+  /** Fields whose results show in the paragraph; their codes are not in the text */
+  fields?: Field[];
+}
+
+export interface Field {
+  /** Upper-cased field type, e.g. 'REF', 'PAGEREF', 'TOC' */
+  field_type: string;
+  /** Bookmark or address the field points at, when its code names one */
+  target: string | null;
+  instruction: string;
+  /** The result as it reads in this paragraph */
+  result: string;
+}
+
+export interface TableCoordinates {
+  table_index: number;
+  /** 0-based row and grid column */
+  row: number;
+  column: number;
+  is_header: boolean;
+  column_header: string | null;
+}
+
+export interface Conflict {
+  /** Character offsets into base_text */
+  start: number;
+  end: number;
+  original_text: string;
+  edits: ConflictingEdit[];
+}
+
+export interface ConflictingEdit {
+  author: string;
+  change_ids: string[];
+  proposed_text: string;
 }
 
 export interface ReviewerVersion {
@@ -31,13 +91,41 @@ export interface ReviewerVersion {
 
 export interface TrackChange {
   id: string;
-  change_type: 'Insertion' | 'Deletion';
+  change_type:
+    | 'Insertion'
+    | 'Deletion'
+    | 'FormatChange'
+    | 'Move'
+    | 'ParagraphSplit'
+    | 'ParagraphJoin'
+    | 'RowInsertion'
+    | 'RowDeletion'
+    | 'CellInsertion'
+    | 'CellDeletion'
+    | 'LayoutChange';
   author: string;
   date: string | null;
   original_text: string;
   new_text: string;
   context_before: string;
   context_after: string;
+  /** File this change was read from (absent in sessions saved by older versions) */
+  source_file?: string | null;
+  /** For format changes: which properties changed, e.g. "bold added" */
+  summary?: string | null;
+  /** For moves: which end this is and where the other end is */
+  move_link?: MoveLink | null;
+  /** Type of the field whose result Word rewrote to make this change (TOC, REF, ...) */
+  machine_generated?: string | null;
+}
+
+/** Link from one end of a tracked move to the other, paired by move name */
+export interface MoveLink {
+  name: string;
+  end: 'From' | 'To';
+  /** Index of the paragraph holding the other end, when it was found */
+  other_paragraph: number | null;
+  other_change_id: string | null;
 }
 
 export interface DocxComment {
@@ -47,6 +135,8 @@ export interface DocxComment {
   text: string;
   anchor_text: string;
   initials: string | null;
+  /** File this comment was read from (absent in sessions saved by older versions) */
+  source_file?: string | null;
 }
 
 export interface Reviewer {
@@ -118,7 +208,11 @@ export interface MergedParagraph {
 
 export const initWasm = vi.fn(async () => {});
 
-export const parseDocx = vi.fn(async (_file: File): Promise<CollateResult> => {
+export interface CollateOptions {
+  dropMachineGenerated?: boolean;
+}
+
+export const parseDocx = vi.fn(async (_file: File, _options?: CollateOptions): Promise<CollateResult> => {
   return {
     paragraphs: [],
     reviewers: [],
@@ -126,3 +220,14 @@ export const parseDocx = vi.fn(async (_file: File): Promise<CollateResult> => {
     error: null,
   };
 });
+
+export const collateDocuments = vi.fn(
+  async (_files: File[], _baseName: string, _options?: CollateOptions): Promise<CollateResult> => {
+    return {
+      paragraphs: [],
+      reviewers: [],
+      document_title: null,
+      error: null,
+    };
+  }
+);
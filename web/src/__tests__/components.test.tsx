@@ -10,6 +10,7 @@ const mockStoreState: Record<string, unknown> = {
   documents: new Map(),
   documentMeta: new Map(),
   baseDocument: null,
+  documentTitle: null,
   mergedParagraphs: [] as unknown[],
   manualComments: [],
   statuses: new Map(),
@@ -22,8 +23,10 @@ const mockStoreState: Record<string, unknown> = {
   currentView: 'landing' as const,
   newItemIds: new Set(),
   newItemNotification: null,
+  dropMachineGenerated: false,
   addDocument: vi.fn(),
   removeDocument: vi.fn(),
+  setDropMachineGenerated: vi.fn(),
   addManualComment: vi.fn(),
   removeManualComment: vi.fn(),
   setStatus: vi.fn(),
@@ -79,6 +82,7 @@ vi.mock('lucide-react', () => {
 import { PrivacyBadge } from '../components/PrivacyBadge';
 import { FileUpload } from '../components/FileUpload';
 import { ParagraphBlock } from '../components/ParagraphBlock';
+import { GeneratedChangesSummary } from '../components/GeneratedChangesSummary';
 import type { MergedParagraph } from '../wasm';
 
 // ─── Helpers ──────────────────────────────────────────────────────────
@@ -151,7 +155,7 @@ describe('FileUpload', () => {
 
   it('shows drag-drop zone in default mode', () => {
     render(<FileUpload />);
-    expect(screen.getByText(/Drop your marked-up .docx files here/)).toBeInTheDocument();
+    expect(screen.getByText(/Drop your marked-up .docx, .doc, .odt, .rtf or .pdf files here/)).toBeInTheDocument();
     expect(screen.getByText(/or click to browse/)).toBeInTheDocument();
   });
 
@@ -181,11 +185,19 @@ describe('FileUpload', () => {
     expect(screen.getByText('Failed to parse document')).toBeInTheDocument();
   });
 
-  it('has a hidden file input accepting .docx', () => {
+  it('has a hidden file input accepting every supported format', () => {
     render(<FileUpload />);
     const input = document.querySelector('input[type="file"]') as HTMLInputElement;
     expect(input).toBeTruthy();
-    expect(input.accept).toBe('.docx');
+    const accepted = input.accept.split(',');
+    expect(accepted).toEqual(
+      expect.arrayContaining(['.docx', '.docm', '.dotx', '.dotm', '.xml', '.odt', '.doc', '.rtf', '.pdf'])
+    );
+    expect(accepted).toContain('application/vnd.ms-word.document.macroEnabled.12');
+    expect(accepted).toContain('application/vnd.openxmlformats-officedocument.wordprocessingml.template');
+    expect(accepted).toContain('application/vnd.ms-word.template.macroEnabled.12');
+    expect(accepted).toContain('application/xml');
+    expect(accepted).toContain('text/xml');
     expect(input.multiple).toBe(true);
   });
 });
@@ -361,3 +373,88 @@ describe('ParagraphBlock', () => {
     expect(screen.getByText('Conflict')).toBeInTheDocument();
   });
 });
+
+// ─── GeneratedChangesSummary ─────────────────────────────────────────
+
+describe('GeneratedChangesSummary', () => {
+  const generated = (id: string, fieldType: string, original: string, updated: string) => ({
+    id,
+    change_type: 'Insertion' as const,
+    author: 'Word',
+    date: null,
+    original_text: original,
+    new_text: updated,
+    context_before: '',
+    context_after: '',
+    machine_generated: fieldType,
+  });
+
+  beforeEach(() => {
+    mockStoreState.statuses = new Map();
+    mockStoreState.activeFilter = 'all';
+    mockStoreState.documents = new Map();
+    mockStoreState.dropMachineGenerated = false;
+  });
+
+  it('collapses field updates into one item per field type', async () => {
+    const paragraphs = [
+      makeMergedParagraph({ index: 0, track_changes: [generated('1', 'TOC', '3', '4'), generated('2', 'TOC', '', 'Scope')] }),
+      makeMergedParagraph({ index: 1, track_changes: [generated('3', 'TOC', '5', '6')] }),
+      makeMergedParagraph({ index: 4, track_changes: [generated('4', 'REF', '2.1', '2.2')] }),
+    ];
+
+    render(<GeneratedChangesSummary paragraphs={paragraphs} />);
+
+    expect(screen.getByText('Table of contents updated')).toBeInTheDocument();
+    expect(screen.getByText('3 changes in 2 paragraphs')).toBeInTheDocument();
+    expect(screen.getByText('Cross-references updated')).toBeInTheDocument();
+    expect(screen.getByText('1 change in 1 paragraph')).toBeInTheDocument();
+    // Collapsed until opened
+    expect(screen.queryByText('2.2')).not.toBeInTheDocument();
+
+    await userEvent.click(screen.getByText('Cross-references updated'));
+    expect(screen.getByText('2.2')).toBeInTheDocument();
+    expect(screen.getByText('¶ 5')).toBeInTheDocument();
+  });
+
+  it('accepts every change of a field type at once', async () => {
+    const paragraphs = [
+      makeMergedParagraph({ track_changes: [generated('1', 'TOC', '3', '4'), generated('2', 'TOC', '5', '6')] }),
+    ];
+
+    render(<GeneratedChangesSummary paragraphs={paragraphs} />);
+    await userEvent.click(screen.getByText('Accept all'));
+
+    expect(mockStoreState.bulkSetStatus).toHaveBeenCalledWith(['1', '2'], 'accepted');
+  });
+
+  it('collates again without field updates when asked to leave them out', async () => {
+    mockStoreState.documents = new Map([['draft.docx', new File(['x'], 'draft.docx')]]);
+    const paragraphs = [makeMergedParagraph({ track_changes: [generated('1', 'TOC', '3', '4')] })];
+
+    render(<GeneratedChangesSummary paragraphs={paragraphs} />);
+    await userEvent.click(screen.getByText('Leave out field updates'));
+
+    expect(mockStoreState.setDropMachineGenerated).toHaveBeenCalledWith(true);
+  });
+
+  it('offers to bring field updates back once they are left out', async () => {
+    mockStoreState.dropMachineGenerated = true;
+
+    render(<GeneratedChangesSummary paragraphs={[makeMergedParagraph()]} />);
+    await userEvent.click(screen.getByText('Show them'));
+
+    expect(mockStoreState.setDropMachineGenerated).toHaveBeenCalledWith(false);
+  });
+
+  it('leaves field updates out of the paragraph they sit in', () => {
+    const para = makeMergedParagraph({
+      track_changes: [generated('1', 'TOC', '3', '4')],
+    });
+
+    render(<ParagraphBlock paragraph={para} />);
+
+    // Its only change is listed in the summary, so the paragraph has nothing to show
+    expect(screen.queryByText('¶ 1')).not.toBeInTheDocument();
+  });
+});
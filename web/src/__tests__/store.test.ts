@@ -4,7 +4,8 @@ import { describe, it, expect, beforeEach, vi } from 'vitest';
 vi.mock('../wasm');
 
 import { useCollateStore } from '../hooks/useCollateStore';
-import type { ManualComment, CommentStatus } from '../wasm';
+import { collateDocuments } from '../wasm';
+import type { CollateResult, ManualComment, ParagraphBlock } from '../wasm';
 
 // ─── Helpers ────────────────────────────────────────────────────────
 
@@ -13,34 +14,45 @@ function resetStore() {
   useCollateStore.getState().clearAll();
 }
 
-/**
- * Tokenize + Jaccard helpers — duplicated from the store's private functions
- * so we can unit-test the algorithm directly.
- */
-function tokenize(text: string): Set<string> {
-  return new Set(
-    text
-      .toLowerCase()
-      .replace(/[^\w\s]/g, '')
-      .split(/\s+/)
-      .filter((w) => w.length > 0)
-  );
+// ─── Test helpers ───────────────────────────────────────────────────
+
+function makeParagraph(overrides: Partial<ParagraphBlock> = {}): ParagraphBlock {
+  return {
+    index: 0,
+    kind: 'Body',
+    note_id: null,
+    anchor_index: null,
+    section: null,
+    header_footer_type: null,
+    table: null,
+    base_text: 'The parties agree as follows.',
+    revised_text: 'The parties agree as follows.',
+    paragraph_status: 'Normal',
+    paragraph_change_author: null,
+    reviewer_versions: [],
+    comments: [],
+    track_changes: [],
+    conflicts: [],
+    source_file: 'base.docx',
+    para_id: null,
+    text_id: null,
+    ...overrides,
+  };
 }
 
-function jaccardSimilarity(a: string, b: string): number {
-  const setA = tokenize(a);
-  const setB = tokenize(b);
-  if (setA.size === 0 && setB.size === 0) return 1;
-  if (setA.size === 0 || setB.size === 0) return 0;
-  let intersection = 0;
-  for (const word of setA) {
-    if (setB.has(word)) intersection++;
-  }
-  const union = setA.size + setB.size - intersection;
-  return union === 0 ? 0 : intersection / union;
+function makeResult(overrides: Partial<CollateResult> = {}): CollateResult {
+  return {
+    paragraphs: [makeParagraph()],
+    reviewers: [],
+    document_title: 'Agreement',
+    error: null,
+    ...overrides,
+  };
 }
 
-// ─── Test helpers ───────────────────────────────────────────────────
+function makeFile(name: string): File {
+  return new File(['x'], name);
+}
 
 function makeManualComment(overrides: Partial<ManualComment> = {}): ManualComment {
   return {
@@ -342,100 +354,149 @@ describe('useCollateStore', () => {
     });
   });
 
-  // ── dismissNotification ─────────────────────────────────────────
+  // ── addDocument / removeDocument ───────────────────────────────
 
-  describe('dismissNotification', () => {
-    it('clears the new item notification', () => {
-      // Manually set a notification via internal state for testing
-      useCollateStore.setState({
-        newItemNotification: { count: 5, filename: 'doc2.docx' },
-      });
-      expect(useCollateStore.getState().newItemNotification).not.toBeNull();
-
-      useCollateStore.getState().dismissNotification();
-      expect(useCollateStore.getState().newItemNotification).toBeNull();
+  describe('addDocument', () => {
+    beforeEach(() => {
+      vi.mocked(collateDocuments).mockReset();
     });
-  });
-});
 
-// ─── Jaccard similarity tests ─────────────────────────────────────
+    it('collates every loaded file against the first one', async () => {
+      vi.mocked(collateDocuments).mockResolvedValue(makeResult());
+
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+      await useCollateStore.getState().addDocument(makeFile('alice.docx'));
 
-describe('Jaccard similarity (paragraph matching)', () => {
-  describe('tokenize', () => {
-    it('lowercases and splits on whitespace', () => {
-      const tokens = tokenize('Hello World');
-      expect(tokens.has('hello')).toBe(true);
-      expect(tokens.has('world')).toBe(true);
-      expect(tokens.size).toBe(2);
+      const [files, baseName] = vi.mocked(collateDocuments).mock.calls[1];
+      expect(files.map((f) => f.name)).toEqual(['base.docx', 'alice.docx']);
+      expect(baseName).toBe('base.docx');
+
+      const state = useCollateStore.getState();
+      expect(state.baseDocument).toBe('base.docx');
+      expect(state.documentTitle).toBe('Agreement');
+      expect(state.documents.size).toBe(2);
+      expect(state.mergedParagraphs).toHaveLength(1);
+      expect(state.currentView).toBe('collate');
     });
 
-    it('removes punctuation', () => {
-      const tokens = tokenize('Hello, world! How are you?');
-      expect(tokens.has('hello')).toBe(true);
-      expect(tokens.has('world')).toBe(true);
-      expect(tokens.has(',')).toBe(false);
-      expect(tokens.has('!')).toBe(false);
+    it('marks items read from a later file as new', async () => {
+      vi.mocked(collateDocuments).mockResolvedValueOnce(makeResult());
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+
+      const comment = {
+        id: 'c1',
+        author: 'Alice',
+        date: null,
+        text: 'Too broad',
+        anchor_text: 'parties',
+        initials: null,
+        source_file: 'alice.docx',
+      };
+      vi.mocked(collateDocuments).mockResolvedValueOnce(
+        makeResult({ paragraphs: [makeParagraph({ comments: [comment] })] })
+      );
+      await useCollateStore.getState().addDocument(makeFile('alice.docx'));
+
+      const state = useCollateStore.getState();
+      expect(state.newItemIds.has('c1')).toBe(true);
+      expect(state.newItemNotification).toEqual({ count: 1, filename: 'alice.docx' });
+      expect(state.mergedParagraphs[0].has_new_items).toBe(true);
     });
 
-    it('deduplicates words', () => {
-      const tokens = tokenize('the the the cat');
-      expect(tokens.size).toBe(2); // "the" and "cat"
+    it('keeps the previous state when collation fails', async () => {
+      vi.mocked(collateDocuments).mockResolvedValueOnce(makeResult());
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+
+      vi.mocked(collateDocuments).mockResolvedValueOnce(
+        makeResult({ paragraphs: [], error: 'broken.docx: not a zip' })
+      );
+      await useCollateStore.getState().addDocument(makeFile('broken.docx'));
+
+      const state = useCollateStore.getState();
+      expect(state.error).toBe('broken.docx: not a zip');
+      expect(state.documents.size).toBe(1);
+      expect(state.mergedParagraphs).toHaveLength(1);
     });
 
-    it('returns empty set for empty/whitespace string', () => {
-      expect(tokenize('').size).toBe(0);
-      expect(tokenize('   ').size).toBe(0);
+    it('attaches manual comments to the collated paragraphs', async () => {
+      vi.mocked(collateDocuments).mockResolvedValue(makeResult());
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+
+      useCollateStore.getState().addManualComment(makeManualComment({ paragraph_index: 0 }));
+
+      expect(useCollateStore.getState().mergedParagraphs[0].manual_comments).toHaveLength(1);
     });
   });
 
-  describe('jaccardSimilarity', () => {
-    it('returns 1 for identical strings', () => {
-      expect(jaccardSimilarity('hello world', 'hello world')).toBe(1);
+  describe('removeDocument', () => {
+    beforeEach(() => {
+      vi.mocked(collateDocuments).mockReset();
+      vi.mocked(collateDocuments).mockResolvedValue(makeResult());
     });
 
-    it('returns 1 for two empty strings', () => {
-      expect(jaccardSimilarity('', '')).toBe(1);
-    });
+    it('re-collates the remaining files', async () => {
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+      await useCollateStore.getState().addDocument(makeFile('alice.docx'));
 
-    it('returns 0 when one string is empty and the other is not', () => {
-      expect(jaccardSimilarity('', 'hello')).toBe(0);
-      expect(jaccardSimilarity('hello', '')).toBe(0);
-    });
+      await useCollateStore.getState().removeDocument('alice.docx');
 
-    it('returns 0 for completely different strings', () => {
-      expect(jaccardSimilarity('cat dog', 'red blue')).toBe(0);
+      const calls = vi.mocked(collateDocuments).mock.calls;
+      expect(calls[calls.length - 1][0].map((f) => f.name)).toEqual(['base.docx']);
+      expect(useCollateStore.getState().documents.size).toBe(1);
     });
 
-    it('returns correct similarity for partially overlapping strings', () => {
-      // "the cat sat" → {the, cat, sat}
-      // "the dog sat" → {the, dog, sat}
-      // intersection = {the, sat} = 2
-      // union = {the, cat, sat, dog} = 4
-      // similarity = 2/4 = 0.5
-      expect(jaccardSimilarity('the cat sat', 'the dog sat')).toBe(0.5);
+    it('clears the view when the last file is removed', async () => {
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+      vi.mocked(collateDocuments).mockClear();
+
+      await useCollateStore.getState().removeDocument('base.docx');
+
+      const state = useCollateStore.getState();
+      expect(collateDocuments).not.toHaveBeenCalled();
+      expect(state.mergedParagraphs).toEqual([]);
+      expect(state.documentTitle).toBeNull();
+      expect(state.currentView).toBe('landing');
     });
+  });
 
-    it('is case-insensitive', () => {
-      expect(jaccardSimilarity('Hello World', 'hello world')).toBe(1);
+  describe('setDropMachineGenerated', () => {
+    beforeEach(() => {
+      vi.mocked(collateDocuments).mockReset();
+      vi.mocked(collateDocuments).mockResolvedValue(makeResult());
     });
 
-    it('ignores punctuation differences', () => {
-      expect(jaccardSimilarity('hello, world!', 'hello world')).toBe(1);
+    it('collates the loaded files again with the option', async () => {
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+
+      await useCollateStore.getState().setDropMachineGenerated(true);
+
+      const calls = vi.mocked(collateDocuments).mock.calls;
+      expect(calls[calls.length - 1][2]).toEqual({ dropMachineGenerated: true });
+      expect(useCollateStore.getState().dropMachineGenerated).toBe(true);
     });
 
-    it('handles high similarity for minor edits', () => {
-      const a = 'The plaintiff contends that the contract was breached on multiple occasions';
-      const b = 'The plaintiff contends that the contract was breached on several occasions';
-      // Only "multiple" vs "several" differ — high similarity expected
-      const sim = jaccardSimilarity(a, b);
-      expect(sim).toBeGreaterThan(0.7);
+    it('is passed on when documents are added', async () => {
+      await useCollateStore.getState().setDropMachineGenerated(true);
+      expect(collateDocuments).not.toHaveBeenCalled();
+
+      await useCollateStore.getState().addDocument(makeFile('base.docx'));
+
+      expect(vi.mocked(collateDocuments).mock.calls[0][2]).toEqual({ dropMachineGenerated: true });
     });
+  });
+
+  // ── dismissNotification ─────────────────────────────────────────
 
-    it('handles low similarity for unrelated paragraphs', () => {
-      const a = 'The plaintiff contends that the contract was breached';
-      const b = 'Weather forecasts predict rain tomorrow in London';
-      const sim = jaccardSimilarity(a, b);
-      expect(sim).toBeLessThan(0.1);
+  describe('dismissNotification', () => {
+    it('clears the new item notification', () => {
+      // Manually set a notification via internal state for testing
+      useCollateStore.setState({
+        newItemNotification: { count: 5, filename: 'doc2.docx' },
+      });
+      expect(useCollateStore.getState().newItemNotification).not.toBeNull();
+
+      useCollateStore.getState().dismissNotification();
+      expect(useCollateStore.getState().newItemNotification).toBeNull();
     });
   });
 });
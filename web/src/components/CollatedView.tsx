@@ -1,16 +1,18 @@
 import { useMemo } from 'react';
 import { useCollateStore } from '../hooks/useCollateStore';
 import { ParagraphBlock } from './ParagraphBlock';
+import { GeneratedChangesSummary } from './GeneratedChangesSummary';
 import { FileText, ChevronDown, CheckCircle2 } from 'lucide-react';
 
 export function CollatedView() {
-  const { mergedParagraphs, documents, searchQuery, activeFilter, statuses, bulkSetStatus } = useCollateStore();
+  const { mergedParagraphs, documents, documentTitle, searchQuery, activeFilter, statuses, bulkSetStatus } = useCollateStore();
 
   // Filter paragraphs that have actionable items
   const actionableParagraphs = useMemo(() => {
     return mergedParagraphs.filter((p) => {
       const isWholesale = p.paragraph_status === 'WhollyInserted' || p.paragraph_status === 'WhollyDeleted';
-      const hasItems = p.track_changes.length > 0 || p.comments.length > 0 || p.manual_comments.length > 0;
+      const hasItems =
+        p.track_changes.some((tc) => !tc.machine_generated) || p.comments.length > 0 || p.manual_comments.length > 0;
       if (!hasItems && !isWholesale) return false;
 
       // Search filter
@@ -56,14 +58,11 @@ export function CollatedView() {
     );
   }
 
-  const firstDoc = documents.values().next().value;
-  const title = firstDoc?.document_title;
-
   return (
     <div>
       {/* Page header */}
       <div className="mb-6">
-        {title && <h1 className="mb-1">{title}</h1>}
+        {documentTitle && <h1 className="mb-1">{documentTitle}</h1>}
         <p className="text-sm" style={{ color: 'var(--text-muted)' }}>
           {actionableParagraphs.length} paragraphs with feedback · {documents.size} document{documents.size !== 1 ? 's' : ''}
           {searchQuery && (
@@ -95,6 +94,9 @@ export function CollatedView() {
         )}
       </div>
 
+      {/* Field updates, one item per field type */}
+      <GeneratedChangesSummary paragraphs={mergedParagraphs} />
+
       {/* Paragraphs */}
       <div className="space-y-4">
         {actionableParagraphs.length === 0 ? (
@@ -122,7 +124,7 @@ function JumpToUnresolved() {
   const jumpToNext = () => {
     for (const para of mergedParagraphs) {
       const allIds = [
-        ...para.track_changes.map((tc) => tc.id),
+        ...para.track_changes.filter((tc) => !tc.machine_generated).map((tc) => tc.id),
         ...para.comments.map((c) => c.id),
         ...para.manual_comments.map((mc) => mc.id),
       ];
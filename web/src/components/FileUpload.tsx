@@ -2,6 +2,18 @@ import { useCallback, useRef, useState } from 'react';
 import { Upload, FileText, Loader2, AlertCircle } from 'lucide-react';
 import { useCollateStore } from '../hooks/useCollateStore';
 
+/** File extensions the core can read */
+const ACCEPTED_EXTENSIONS = ['.docx', '.docm', '.dotx', '.dotm', '.xml', '.odt', '.doc', '.rtf', '.pdf'];
+/** Word package and Flat OPC types some browsers only offer when named in `accept` */
+const ACCEPTED_TYPES = [
+  'application/vnd.ms-word.document.macroEnabled.12',
+  'application/vnd.openxmlformats-officedocument.wordprocessingml.template',
+  'application/vnd.ms-word.template.macroEnabled.12',
+  'application/xml',
+  'text/xml',
+];
+const ACCEPT = [...ACCEPTED_EXTENSIONS, ...ACCEPTED_TYPES].join(',');
+
 interface FileUploadProps {
   compact?: boolean;
 }
@@ -16,7 +28,7 @@ export function FileUpload({ compact = false }: FileUploadProps) {
       if (!files) return;
       for (let i = 0; i < files.length; i++) {
         const file = files[i];
-        if (file.name.endsWith('.docx')) {
+        if (ACCEPTED_EXTENSIONS.some((ext) => file.name.toLowerCase().endsWith(ext))) {
           await addDocument(file);
         }
       }
@@ -39,7 +51,7 @@ export function FileUpload({ compact = false }: FileUploadProps) {
         <input
           ref={inputRef}
           type="file"
-          accept=".docx"
+          accept={ACCEPT}
           multiple
           className="hidden"
           onChange={(e) => handleFiles(e.target.files)}
@@ -65,7 +77,7 @@ export function FileUpload({ compact = false }: FileUploadProps) {
       <input
         ref={inputRef}
         type="file"
-        accept=".docx"
+        accept={ACCEPT}
         multiple
         className="hidden"
         onChange={(e) => handleFiles(e.target.files)}
@@ -103,7 +115,7 @@ export function FileUpload({ compact = false }: FileUploadProps) {
             </div>
             <div>
               <p className="font-medium" style={{ color: 'var(--text)' }}>
-                Drop your marked-up .docx files here
+                Drop your marked-up .docx, .doc, .odt, .rtf or .pdf files here
               </p>
               <p className="text-sm mt-1" style={{ color: 'var(--text-muted)' }}>
                 or click to browse — multiple files supported
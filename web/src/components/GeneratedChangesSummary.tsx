@@ -0,0 +1,137 @@
+import { useMemo, useState } from 'react';
+import { MergedParagraph, TrackChange } from '../wasm';
+import { useCollateStore } from '../hooks/useCollateStore';
+import { ChevronDown, ChevronRight, CheckCircle2 } from 'lucide-react';
+
+interface GeneratedChangesSummaryProps {
+  paragraphs: MergedParagraph[];
+}
+
+interface GeneratedChange {
+  paragraph: number;
+  change: TrackChange;
+}
+
+const FIELD_LABELS: Record<string, string> = {
+  TOC: 'Table of contents',
+  PAGEREF: 'Page references',
+  PAGE: 'Page numbers',
+  REF: 'Cross-references',
+  NOTEREF: 'Note references',
+  DATE: 'Dates',
+};
+
+/**
+ * Changes Word made itself while updating fields, collapsed into one item per
+ * field type so they do not bury the reviewers' own edits. They can also be
+ * left out altogether, which collates the loaded files again without them.
+ */
+export function GeneratedChangesSummary({ paragraphs }: GeneratedChangesSummaryProps) {
+  const { documents, dropMachineGenerated, setDropMachineGenerated } = useCollateStore();
+  const groups = useMemo(() => {
+    const byType = new Map<string, GeneratedChange[]>();
+    for (const para of paragraphs) {
+      for (const tc of para.track_changes) {
+        if (!tc.machine_generated) continue;
+        const existing = byType.get(tc.machine_generated) || [];
+        existing.push({ paragraph: para.index, change: tc });
+        byType.set(tc.machine_generated, existing);
+      }
+    }
+    return Array.from(byType.entries());
+  }, [paragraphs]);
+
+  if (dropMachineGenerated) {
+    return (
+      <div className="flex items-center gap-2 mb-4 text-xs" style={{ color: 'var(--text-light)' }}>
+        <span>Changes Word made while updating fields are left out.</span>
+        <button onClick={() => setDropMachineGenerated(false)} className="btn btn-xs">
+          Show them
+        </button>
+      </div>
+    );
+  }
+
+  if (groups.length === 0) return null;
+
+  return (
+    <div className="space-y-2 mb-4">
+      {documents.size > 0 && (
+        <div className="flex justify-end">
+          <button onClick={() => setDropMachineGenerated(true)} className="btn btn-xs">
+            Leave out field updates
+          </button>
+        </div>
+      )}
+      {groups.map(([fieldType, changes]) => (
+        <GeneratedChangeGroup key={fieldType} fieldType={fieldType} changes={changes} />
+      ))}
+    </div>
+  );
+}
+
+function GeneratedChangeGroup({ fieldType, changes }: { fieldType: string; changes: GeneratedChange[] }) {
+  const { statuses, bulkSetStatus } = useCollateStore();
+  const [collapsed, setCollapsed] = useState(true);
+
+  const unresolvedIds = changes
+    .map(({ change }) => change.id)
+    .filter((id) => {
+      const s = statuses.get(id)?.status;
+      return !s || s === 'unresolved';
+    });
+  const paragraphCount = new Set(changes.map(({ paragraph }) => paragraph)).size;
+
+  return (
+    <div className="card overflow-hidden" style={{ borderLeftWidth: 3, borderLeftColor: 'var(--border)' }}>
+      <div
+        className="flex items-center justify-between cursor-pointer -m-5 mb-0 px-5 py-3"
+        style={{ background: 'var(--bg-main)' }}
+        onClick={() => setCollapsed(!collapsed)}
+      >
+        <div className="flex items-center gap-2">
+          {collapsed
+            ? <ChevronRight className="w-4 h-4" style={{ color: 'var(--text-light)' }} />
+            : <ChevronDown className="w-4 h-4" style={{ color: 'var(--text-light)' }} />
+          }
+          <span className="text-sm font-semibold">{FIELD_LABELS[fieldType] || fieldType} updated</span>
+          <span className="badge badge-grey">Field update</span>
+        </div>
+        <div className="flex items-center gap-3">
+          <span className="text-xs" style={{ color: 'var(--text-light)' }}>
+            {changes.length} change{changes.length !== 1 ? 's' : ''} in {paragraphCount} paragraph{paragraphCount !== 1 ? 's' : ''}
+          </span>
+          {unresolvedIds.length > 0 ? (
+            <button
+              onClick={(e) => { e.stopPropagation(); bulkSetStatus(unresolvedIds, 'accepted'); }}
+              className="btn btn-xs badge-green"
+              style={{ border: '1px solid var(--green-border)' }}
+            >
+              Accept all
+            </button>
+          ) : (
+            <span className="badge badge-green">
+              <CheckCircle2 className="w-3 h-3" />
+              Resolved
+            </span>
+          )}
+        </div>
+      </div>
+
+      {!collapsed && (
+        <ul className="mt-4 space-y-1 text-sm animate-fade-in">
+          {changes.map(({ paragraph, change }) => (
+            <li key={`${paragraph}-${change.id}`} className="flex items-baseline gap-2">
+              <span className="text-xs font-mono" style={{ color: 'var(--text-muted)' }}>¶ {paragraph + 1}</span>
+              <span className="text-xs" style={{ color: 'var(--text-light)' }}>{change.author}</span>
+              {change.original_text && <del className="tc-deletion">{change.original_text}</del>}
+              {change.new_text && change.new_text !== change.original_text && (
+                <ins className="tc-insertion">{change.new_text}</ins>
+              )}
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
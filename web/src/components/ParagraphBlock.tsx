@@ -23,8 +23,11 @@ export function ParagraphBlock({ paragraph }: ParagraphBlockProps) {
   const { activeFilter, statuses, bulkSetStatus } = useCollateStore();
   const [collapsed, setCollapsed] = useState(false);
 
+  // Changes Word made while updating fields are listed in their own summary
+  const trackChanges = paragraph.track_changes.filter((tc) => !tc.machine_generated);
+
   const allIds = [
-    ...paragraph.track_changes.map((tc) => tc.id),
+    ...trackChanges.map((tc) => tc.id),
     ...paragraph.comments.map((c) => c.id),
     ...paragraph.manual_comments.map((mc) => mc.id),
   ];
@@ -47,7 +50,7 @@ export function ParagraphBlock({ paragraph }: ParagraphBlockProps) {
   // Apply filters
   if (activeFilter === 'unresolved' && unresolvedIds.length === 0) return null;
   if (activeFilter === 'conflicts' && !paragraph.has_conflicts) return null;
-  if (activeFilter === 'track_changes' && paragraph.track_changes.length === 0) return null;
+  if (activeFilter === 'track_changes' && trackChanges.length === 0) return null;
   if (activeFilter === 'comments' && paragraph.comments.length === 0 && paragraph.manual_comments.length === 0) return null;
   if (activeFilter === 'new' && !paragraph.has_new_items) return null;
   if (activeFilter === 'wholesale' && !isWholesale) return null;
@@ -149,10 +152,10 @@ export function ParagraphBlock({ paragraph }: ParagraphBlockProps) {
         </div>
 
         <div className="flex items-center gap-3">
-          {paragraph.track_changes.length > 0 && (
+          {trackChanges.length > 0 && (
             <span className="badge badge-grey">
               <GitCompare className="w-3 h-3" />
-              {paragraph.track_changes.length}
+              {trackChanges.length}
             </span>
           )}
           {(paragraph.comments.length + paragraph.manual_comments.length) > 0 && (
@@ -219,11 +222,11 @@ export function ParagraphBlock({ paragraph }: ParagraphBlockProps) {
           )}
 
           {/* Track changes */}
-          {paragraph.track_changes.length > 0 && (
+          {trackChanges.length > 0 && (
             <div className="mb-3">
               <div className="section-label mb-2">Track Changes</div>
               <TrackChangeView
-                trackChanges={paragraph.track_changes}
+                trackChanges={trackChanges}
                 baseText={paragraph.base_text}
               />
             </div>
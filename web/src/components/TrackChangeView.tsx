@@ -60,6 +60,32 @@ export function TrackChangeView({ trackChanges, baseText }: TrackChangeViewProps
 
                 return (
                   <div key={tc.id} className="flex items-center gap-2">
+                    {tc.move_link && (
+                      <span className="text-xs shrink-0" style={{ color: 'var(--text-light)' }}>
+                        {tc.move_link.end === 'From' ? 'Moved to' : 'Moved from'}
+                        {tc.move_link.other_paragraph !== null ? ` ¶ ${tc.move_link.other_paragraph + 1}` : ' (other end not found)'}
+                      </span>
+                    )}
+                    {(tc.change_type === 'ParagraphSplit' || tc.change_type === 'ParagraphJoin') && (
+                      <span className="text-xs shrink-0" style={{ color: 'var(--text-light)' }} title={tc.context_before + ' ¶ ' + tc.context_after}>
+                        {tc.change_type === 'ParagraphSplit' ? 'Paragraph split' : 'Paragraphs joined'}
+                      </span>
+                    )}
+                    {tc.change_type in TABLE_CHANGE_LABELS && (
+                      <span className="text-xs shrink-0" style={{ color: 'var(--text-light)' }} title={tc.original_text || tc.new_text}>
+                        {TABLE_CHANGE_LABELS[tc.change_type]}
+                      </span>
+                    )}
+                    {tc.change_type === 'FormatChange' && (
+                      <span className="text-xs shrink-0" style={{ color: 'var(--text-light)' }} title={tc.original_text}>
+                        Format: {tc.summary}
+                      </span>
+                    )}
+                    {tc.change_type === 'LayoutChange' && (
+                      <span className="text-xs shrink-0" style={{ color: 'var(--text-light)' }}>
+                        Layout: {tc.summary}
+                      </span>
+                    )}
                     <select
                       value={currentStatus}
                       onChange={(e) => setStatus(tc.id, e.target.value as any)}
@@ -97,6 +123,17 @@ export function TrackChangeView({ trackChanges, baseText }: TrackChangeViewProps
   );
 }
 
+const TABLE_CHANGE_LABELS: Partial<Record<TrackChange['change_type'], string>> = {
+  RowInsertion: 'Row inserted',
+  RowDeletion: 'Row deleted',
+  CellInsertion: 'Cell inserted',
+  CellDeletion: 'Cell deleted',
+};
+
+/** A move's source end reads as a deletion here, its destination as an insertion */
+const removes = (tc: TrackChange) => tc.change_type === 'Deletion' || tc.move_link?.end === 'From';
+const adds = (tc: TrackChange) => tc.change_type === 'Insertion' || tc.move_link?.end === 'To';
+
 function InlineDiff({ baseText, changes }: { baseText: string; changes: TrackChange[] }) {
   const parts: React.ReactElement[] = [];
 
@@ -111,7 +148,7 @@ function InlineDiff({ baseText, changes }: { baseText: string; changes: TrackCha
   for (let i = 0; i < sorted.length; i++) {
     const tc = sorted[i];
 
-    if (tc.change_type === 'Deletion') {
+    if (removes(tc)) {
       const pos = baseText.indexOf(tc.original_text, lastEnd);
       if (pos >= 0) {
         if (pos > lastEnd) {
@@ -122,7 +159,7 @@ function InlineDiff({ baseText, changes }: { baseText: string; changes: TrackCha
         );
         lastEnd = pos + tc.original_text.length;
       }
-    } else if (tc.change_type === 'Insertion') {
+    } else if (adds(tc)) {
       const contextPos = baseText.indexOf(tc.context_before, lastEnd > 0 ? lastEnd - tc.context_before.length : 0);
       const insertPos = contextPos >= 0 ? contextPos + tc.context_before.length : lastEnd;
 
@@ -145,8 +182,8 @@ function InlineDiff({ baseText, changes }: { baseText: string; changes: TrackCha
       <span>
         {changes.map((tc, i) => (
           <span key={i}>
-            {tc.change_type === 'Deletion' && <del className="tc-deletion">{tc.original_text}</del>}
-            {tc.change_type === 'Insertion' && <ins className="tc-insertion">{tc.new_text}</ins>}
+            {removes(tc) && <del className="tc-deletion">{tc.original_text}</del>}
+            {adds(tc) && <ins className="tc-insertion">{tc.new_text}</ins>}
             {i < changes.length - 1 && ' '}
           </span>
         ))}
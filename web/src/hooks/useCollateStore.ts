@@ -5,8 +5,7 @@ import {
   ManualComment,
   MergedParagraph,
   Reviewer,
-  REVIEWER_COLOURS,
-  parseDocx,
+  collateDocuments,
 } from '../wasm';
 
 // ─── Document metadata ─────────────────────────────────────────────
@@ -17,36 +16,12 @@ interface DocumentMeta {
 }
 
 
-// ─── Paragraph matching (Jaccard similarity) ───────────────────────
-
-function tokenize(text: string): Set<string> {
-  return new Set(
-    text
-      .toLowerCase()
-      .replace(/[^\w\s]/g, '')
-      .split(/\s+/)
-      .filter((w) => w.length > 0)
-  );
-}
-
-function jaccardSimilarity(a: string, b: string): number {
-  const setA = tokenize(a);
-  const setB = tokenize(b);
-  if (setA.size === 0 && setB.size === 0) return 1;
-  if (setA.size === 0 || setB.size === 0) return 0;
-  let intersection = 0;
-  for (const word of setA) {
-    if (setB.has(word)) intersection++;
-  }
-  const union = setA.size + setB.size - intersection;
-  return union === 0 ? 0 : intersection / union;
-}
-
 // ─── Store types ───────────────────────────────────────────────────
 
 interface CollateStore {
-  // Document data
-  documents: Map<string, CollateResult>;
+  // Document data — the uploaded files, collated together on every change
+  documents: Map<string, File>;
+  documentTitle: string | null;
   documentMeta: Map<string, DocumentMeta>;
   baseDocument: string | null;
 
@@ -85,9 +60,13 @@ interface CollateStore {
   // Project management
   currentProjectName: string | null;
 
+  /** Leave out changes Word made itself while updating fields */
+  dropMachineGenerated: boolean;
+
   // Actions
   addDocument: (file: File) => Promise<void>;
-  removeDocument: (filename: string) => void;
+  removeDocument: (filename: string) => Promise<void>;
+  setDropMachineGenerated: (drop: boolean) => Promise<void>;
   addManualComment: (comment: ManualComment) => void;
   removeManualComment: (id: string) => void;
   setStatus: (commentId: string, status: CommentStatus['status'], note?: string) => void;
@@ -242,134 +221,47 @@ if (typeof window !== 'undefined') {
 }
 
 // ─── Merge logic ───────────────────────────────────────────────────
+// Paragraphs are matched and merged across copies by collateDocuments; the
+// store only adds its own per-paragraph state to the result.
+
+function itemIds(p: { track_changes: { id: string }[]; comments: { id: string }[] }): string[] {
+  return [...p.track_changes.map((tc) => tc.id), ...p.comments.map((c) => c.id)];
+}
 
-function buildMergedParagraphs(
-  documents: Map<string, CollateResult>,
-  baseDocument: string | null,
+function toMergedParagraphs(
+  result: CollateResult,
   manualComments: ManualComment[],
   newItemIds: Set<string>
 ): MergedParagraph[] {
-  if (!baseDocument || !documents.has(baseDocument)) return [];
-
-  const base = documents.get(baseDocument)!;
-  const merged: MergedParagraph[] = base.paragraphs.map((p) => {
-    const itemIds = [
-      ...p.track_changes.map((tc) => tc.id),
-      ...p.comments.map((c) => c.id),
-    ];
-    return {
-      index: p.index,
-      base_text: p.base_text,
-      revised_text: p.revised_text,
-      paragraph_status: p.paragraph_status,
-      paragraph_change_author: p.paragraph_change_author,
-      reviewer_versions: [...p.reviewer_versions],
-      comments: [...p.comments],
-      track_changes: [...p.track_changes],
-      manual_comments: manualComments.filter((mc) => mc.paragraph_index === p.index),
-      has_conflicts: p.has_conflicts,
-      source_file: baseDocument,
-      has_new_items: itemIds.some((id) => newItemIds.has(id)),
-    };
-  });
-
-  // Merge additional documents
-  for (const [filename, result] of documents) {
-    if (filename === baseDocument) continue;
-
-    for (const para of result.paragraphs) {
-      // Wholly-inserted paragraphs from a reviewer doc won't match anything
-      // in the base — they ARE new paragraphs that the reviewer added
-      const paraIsWhollyNew = para.paragraph_status === 'WhollyInserted';
-
-      // Find best matching base paragraph
-      let bestIdx = -1;
-      let bestSim = 0;
-
-      // Build text to match: use revised text if available, else base text
-      const paraText =
-        para.reviewer_versions.length > 0
-          ? para.reviewer_versions[0].resulting_text
-          : para.base_text || para.revised_text;
-
-      for (let i = 0; i < merged.length; i++) {
-        const baseForMatch = merged[i].base_text || merged[i].revised_text;
-        const sim = jaccardSimilarity(paraText, baseForMatch);
-        if (sim > bestSim) {
-          bestSim = sim;
-          bestIdx = i;
-        }
-      }
-
-      if (bestIdx >= 0 && bestSim > 0.3) {
-        // Matched — merge comments and track changes
-        merged[bestIdx].comments.push(...para.comments);
-        merged[bestIdx].track_changes.push(...para.track_changes);
-        merged[bestIdx].reviewer_versions.push(...para.reviewer_versions);
-        if (para.has_conflicts || merged[bestIdx].track_changes.length > 0) {
-          const authors = new Set(merged[bestIdx].track_changes.map((tc) => tc.author));
-          merged[bestIdx].has_conflicts = authors.size > 1;
-        }
-        // Check if any new items were added
-        const newIds = [
-          ...para.track_changes.map((tc) => tc.id),
-          ...para.comments.map((c) => c.id),
-        ];
-        if (newIds.some((id) => newItemIds.has(id))) {
-          merged[bestIdx].has_new_items = true;
-        }
-      } else {
-        // Unmatched paragraph — add it to the merged list at a sensible position
-        // (appended after the last paragraph, or inserted near its original index)
-        const newIndex = merged.length > 0 ? merged[merged.length - 1].index + 1 : 0;
-        const itemIds = [
-          ...para.track_changes.map((tc) => tc.id),
-          ...para.comments.map((c) => c.id),
-        ];
-        merged.push({
-          index: newIndex,
-          base_text: para.base_text,
-          revised_text: para.revised_text,
-          paragraph_status: para.paragraph_status,
-          paragraph_change_author: para.paragraph_change_author,
-          reviewer_versions: [...para.reviewer_versions],
-          comments: [...para.comments],
-          track_changes: [...para.track_changes],
-          manual_comments: [],
-          has_conflicts: para.has_conflicts,
-          source_file: filename,
-          has_new_items: itemIds.some((id) => newItemIds.has(id)),
-        });
-      }
-    }
-  }
-
-  return merged;
+  return result.paragraphs.map((p) => ({
+    index: p.index,
+    base_text: p.base_text,
+    revised_text: p.revised_text,
+    paragraph_status: p.paragraph_status,
+    paragraph_change_author: p.paragraph_change_author,
+    reviewer_versions: p.reviewer_versions,
+    comments: p.comments,
+    track_changes: p.track_changes,
+    manual_comments: manualComments.filter((mc) => mc.paragraph_index === p.index),
+    has_conflicts: p.conflicts.length > 0,
+    source_file: p.source_file,
+    has_new_items: itemIds(p).some((id) => newItemIds.has(id)),
+  }));
 }
 
-function buildReviewerList(
-  documents: Map<string, CollateResult>,
-  existingColours: Map<string, string>
-): Reviewer[] {
-  const reviewerMap = new Map<string, Reviewer>();
-  let colourIdx = 0;
-
-  for (const [, result] of documents) {
-    for (const r of result.reviewers) {
-      if (reviewerMap.has(r.name)) {
-        const existing = reviewerMap.get(r.name)!;
-        existing.comment_count += r.comment_count;
-        existing.change_count += r.change_count;
-      } else {
-        const colour =
-          existingColours.get(r.name) || REVIEWER_COLOURS[colourIdx % REVIEWER_COLOURS.length].accent;
-        reviewerMap.set(r.name, { ...r, colour });
-        colourIdx++;
-      }
-    }
-  }
+function withManualComments(
+  paragraphs: MergedParagraph[],
+  manualComments: ManualComment[]
+): MergedParagraph[] {
+  return paragraphs.map((p) => ({
+    ...p,
+    manual_comments: manualComments.filter((mc) => mc.paragraph_index === p.index),
+  }));
+}
 
-  return Array.from(reviewerMap.values());
+/** Reviewers from a collated result, keeping the colours already shown */
+function keepColours(reviewers: Reviewer[], existingColours: Map<string, string>): Reviewer[] {
+  return reviewers.map((r) => ({ ...r, colour: existingColours.get(r.name) || r.colour }));
 }
 
 // ─── Store ─────────────────────────────────────────────────────────
@@ -378,6 +270,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
   documents: new Map(),
   documentMeta: new Map(),
   baseDocument: null,
+  documentTitle: null,
   mergedParagraphs: [],
   manualComments: [],
   statuses: new Map(),
@@ -391,56 +284,54 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
   newItemIds: new Set(),
   newItemNotification: null,
   currentProjectName: null,
+  dropMachineGenerated: false,
 
   addDocument: async (file: File) => {
     set({ isLoading: true, loadingFile: file.name, error: null });
 
     try {
-      const result = await parseDocx(file);
+      const state = get();
+      const isFirstDocument = state.documents.size === 0;
+
+      const newDocs = new Map(state.documents);
+      newDocs.set(file.name, file);
+      const baseDoc = state.baseDocument || file.name;
+
+      const result = await collateDocuments(Array.from(newDocs.values()), baseDoc, {
+        dropMachineGenerated: state.dropMachineGenerated,
+      });
 
       if (result.error) {
         set({ isLoading: false, loadingFile: null, error: result.error });
         return;
       }
 
-      const state = get();
-      const isFirstDocument = state.documents.size === 0;
-      const now = Date.now();
-
-      const newDocs = new Map(state.documents);
-      newDocs.set(file.name, result);
-
       const newMeta = new Map(state.documentMeta);
-      newMeta.set(file.name, { filename: file.name, addedAt: now });
+      newMeta.set(file.name, { filename: file.name, addedAt: Date.now() });
 
-      const baseDoc = state.baseDocument || file.name;
       const existingColours = new Map(state.reviewers.map((r) => [r.name, r.colour]));
-      const reviewers = buildReviewerList(newDocs, existingColours);
 
-      // Track new item IDs if this is NOT the first document
+      // Items read from this file are new if it is NOT the first document
       const newItemIds = new Set(state.newItemIds);
       let newItemCount = 0;
       if (!isFirstDocument) {
         for (const para of result.paragraphs) {
-          for (const tc of para.track_changes) {
-            newItemIds.add(tc.id);
-            newItemCount++;
-          }
-          for (const c of para.comments) {
-            newItemIds.add(c.id);
-            newItemCount++;
+          for (const item of [...para.track_changes, ...para.comments]) {
+            if (item.source_file === file.name) {
+              newItemIds.add(item.id);
+              newItemCount++;
+            }
           }
         }
       }
 
-      const mergedParagraphs = buildMergedParagraphs(newDocs, baseDoc, state.manualComments, newItemIds);
-
       set({
         documents: newDocs,
         documentMeta: newMeta,
         baseDocument: baseDoc,
-        reviewers,
-        mergedParagraphs,
+        documentTitle: result.document_title,
+        reviewers: keepColours(result.reviewers, existingColours),
+        mergedParagraphs: toMergedParagraphs(result, state.manualComments, newItemIds),
         newItemIds,
         newItemNotification: !isFirstDocument && newItemCount > 0
           ? { count: newItemCount, filename: file.name }
@@ -461,7 +352,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
     }
   },
 
-  removeDocument: (filename: string) => {
+  removeDocument: async (filename: string) => {
     const state = get();
     const newDocs = new Map(state.documents);
     newDocs.delete(filename);
@@ -473,26 +364,68 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
       ? (newDocs.size > 0 ? newDocs.keys().next().value ?? null : null)
       : state.baseDocument;
 
-    const existingColours = new Map(state.reviewers.map((r) => [r.name, r.colour]));
-    const reviewers = buildReviewerList(newDocs, existingColours);
-    const mergedParagraphs = buildMergedParagraphs(newDocs, baseDoc, state.manualComments, state.newItemIds);
+    // Collate what is left; with no files there is nothing to show
+    let result: CollateResult | null = null;
+    if (baseDoc) {
+      try {
+        result = await collateDocuments(Array.from(newDocs.values()), baseDoc, {
+          dropMachineGenerated: state.dropMachineGenerated,
+        });
+      } catch (e) {
+        set({ error: e instanceof Error ? e.message : 'Failed to parse document' });
+        return;
+      }
+      if (result.error) {
+        set({ error: result.error });
+        return;
+      }
+    }
 
+    const existingColours = new Map(state.reviewers.map((r) => [r.name, r.colour]));
     set({
       documents: newDocs,
       documentMeta: newMeta,
       baseDocument: baseDoc,
-      reviewers,
-      mergedParagraphs,
+      documentTitle: result?.document_title ?? null,
+      reviewers: result ? keepColours(result.reviewers, existingColours) : [],
+      mergedParagraphs: result
+        ? toMergedParagraphs(result, state.manualComments, state.newItemIds)
+        : [],
       currentView: newDocs.size === 0 ? 'landing' : 'collate',
     });
 
     saveToStorage(get());
   },
 
+  setDropMachineGenerated: async (drop: boolean) => {
+    const state = get();
+    set({ dropMachineGenerated: drop });
+    // Sessions restored without their files keep what was collated
+    if (!state.baseDocument) return;
+
+    try {
+      const result = await collateDocuments(Array.from(state.documents.values()), state.baseDocument, {
+        dropMachineGenerated: drop,
+      });
+      if (result.error) {
+        set({ error: result.error });
+        return;
+      }
+      const existingColours = new Map(state.reviewers.map((r) => [r.name, r.colour]));
+      set({
+        reviewers: keepColours(result.reviewers, existingColours),
+        mergedParagraphs: toMergedParagraphs(result, state.manualComments, state.newItemIds),
+      });
+      saveToStorage(get());
+    } catch (e) {
+      set({ error: e instanceof Error ? e.message : 'Failed to parse document' });
+    }
+  },
+
   addManualComment: (comment: ManualComment) => {
     const state = get();
     const manualComments = [...state.manualComments, comment];
-    const mergedParagraphs = buildMergedParagraphs(state.documents, state.baseDocument, manualComments, state.newItemIds);
+    const mergedParagraphs = withManualComments(state.mergedParagraphs, manualComments);
 
     set({ manualComments, mergedParagraphs });
     saveToStorage(get());
@@ -501,7 +434,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
   removeManualComment: (id: string) => {
     const state = get();
     const manualComments = state.manualComments.filter((mc) => mc.id !== id);
-    const mergedParagraphs = buildMergedParagraphs(state.documents, state.baseDocument, manualComments, state.newItemIds);
+    const mergedParagraphs = withManualComments(state.mergedParagraphs, manualComments);
 
     set({ manualComments, mergedParagraphs });
     saveToStorage(get());
@@ -582,6 +515,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
       documents: new Map(),
       documentMeta: new Map(),
       baseDocument: null,
+      documentTitle: null,
       mergedParagraphs: [],
       manualComments: [],
       statuses: new Map(),
@@ -595,6 +529,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
       newItemIds: new Set(),
       newItemNotification: null,
       currentProjectName: null,
+      dropMachineGenerated: false,
     });
   },
 
@@ -669,6 +604,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
         documents: new Map(),
         documentMeta: new Map(),
         baseDocument: null,
+        documentTitle: null,
         manualComments: snapshot.manualComments || [],
         statuses: new Map(snapshot.statuses || []),
         reviewers: snapshot.reviewers || [],
@@ -705,6 +641,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
       documents: new Map(),
       documentMeta: new Map(),
       baseDocument: null,
+      documentTitle: null,
       mergedParagraphs: [],
       manualComments: [],
       statuses: new Map(),
@@ -718,6 +655,7 @@ export const useCollateStore = create<CollateStore>((set, get) => ({
       newItemIds: new Set(),
       newItemNotification: null,
       currentProjectName: null,
+      dropMachineGenerated: false,
     });
   },
 }));
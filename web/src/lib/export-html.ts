@@ -1,4 +1,4 @@
-import type { MergedParagraph, Reviewer, CommentStatus } from '../wasm';
+import type { MergedParagraph, Reviewer, CommentStatus, TrackChange } from '../wasm';
 
 interface StoreSnapshot {
   mergedParagraphs: MergedParagraph[];
@@ -73,12 +73,21 @@ export function generateHtmlReport(store: StoreSnapshot): string {
           const status = store.statuses.get(tc.id);
           const badge = statusBadge(status?.status || 'unresolved');
           const diffHtml =
-            tc.change_type === 'Deletion'
+            tc.change_type === 'Deletion' ||
+            tc.change_type === 'RowDeletion' ||
+            tc.change_type === 'CellDeletion' ||
+            tc.move_link?.end === 'From'
               ? `<del style="color:#991B1B;text-decoration:line-through;background:#FEE2E2">${esc(tc.original_text)}</del>`
-              : `<ins style="color:#065F46;text-decoration:underline;background:#D1FAE5">${esc(tc.new_text)}</ins>`;
+              : tc.change_type === 'ParagraphSplit' || tc.change_type === 'ParagraphJoin'
+                ? `<span style="color:#6b7280">${esc(tc.context_before)} <strong>${tc.change_type === 'ParagraphSplit' ? '¶ inserted' : '¶ deleted'}</strong> ${esc(tc.context_after)}</span>`
+                : tc.change_type === 'FormatChange'
+                  ? `<span style="background:#EDE9FE">${esc(tc.original_text)}</span> <em style="color:#6b7280">${esc(tc.summary || '')}</em>`
+                  : tc.change_type === 'LayoutChange'
+                    ? `<em style="color:#6b7280">${esc(tc.summary || '')}</em>`
+                    : `<ins style="color:#065F46;text-decoration:underline;background:#D1FAE5">${esc(tc.new_text)}</ins>`;
 
           return `<div style="margin:8px 0;padding:8px;border:1px solid #e5e7eb;border-radius:6px">
-            <strong>${esc(tc.author)}</strong> — ${tc.change_type} ${badge}
+            <strong>${esc(tc.author)}</strong> — ${tc.change_type}${moveLabel(tc)} ${badge}
             <div style="margin-top:4px">${diffHtml}</div>
             ${status?.note ? `<div style="margin-top:4px;font-size:12px;color:#6b7280">Note: ${esc(status.note)}</div>` : ''}
           </div>`;
@@ -183,6 +192,13 @@ function esc(s: string): string {
     .replace(/"/g, '&quot;');
 }
 
+function moveLabel(tc: TrackChange): string {
+  const link = tc.move_link;
+  if (!link) return '';
+  const where = link.other_paragraph !== null ? ` ¶ ${link.other_paragraph + 1}` : ' (other end not found)';
+  return ` (${link.end === 'From' ? 'moved to' : 'moved from'}${where})`;
+}
+
 function statusBadge(status: string): string {
   const colours: Record<string, string> = {
     unresolved: 'background:#f3f4f6;color:#4b5563',